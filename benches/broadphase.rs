@@ -0,0 +1,124 @@
+//! Collision cost for one tick in a crowded arena: 100 tanks and 1000
+//! bullets, with every tank looking for the nearest enemy the way NPCs do.
+//! The brute-force variants check every bullet or tank against every tank,
+//! as the server did before the grid.
+
+use battlexone_shared::collision::separate_tanks;
+use battlexone_shared::grid::SpatialGrid;
+use battlexone_shared::map::Map;
+use battlexone_shared::sweep::segment_circle;
+use battlexone_shared::{Position, Tank};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const TANKS: usize = 100;
+const BULLETS: usize = 1000;
+const HIT_RADIUS: f32 = 30.0;
+const BULLET_STEP: f32 = 10.0;
+const SIGHT_RANGE: f32 = 400.0;
+
+/// A bullet's owner and the path it covers in one tick
+type Shot = (usize, Position, Position);
+
+fn scenario() -> (Vec<Tank>, Vec<Shot>) {
+    let mut rng = StdRng::seed_from_u64(7);
+    let position = |rng: &mut StdRng| Position {
+        x: rng.gen_range(-950.0..950.0),
+        y: rng.gen_range(-950.0..950.0),
+    };
+    let tanks = (0..TANKS)
+        .map(|i| Tank {
+            id: format!("tank{}", i),
+            position: position(&mut rng),
+            rotation: 0.0,
+            turret_rotation: 0.0,
+            is_player: false,
+            health: 100,
+            is_dead: false,
+            respawn_time: None,
+            last_fire_time: 0,
+            spawn_protected_until: None,
+            team: None,
+        })
+        .collect();
+    let bullets = (0..BULLETS)
+        .map(|i| {
+            let from = position(&mut rng);
+            let heading: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+            let to = Position {
+                x: from.x + heading.cos() * BULLET_STEP,
+                y: from.y + heading.sin() * BULLET_STEP,
+            };
+            (i % TANKS, from, to)
+        })
+        .collect();
+    (tanks, bullets)
+}
+
+fn distance_sq(a: &Position, b: &Position) -> f32 {
+    (a.x - b.x).powi(2) + (a.y - b.y).powi(2)
+}
+
+fn grid_queries(tanks: &[Tank], bullets: &[Shot]) -> usize {
+    let grid = SpatialGrid::from_tanks(tanks);
+    let hits = bullets
+        .iter()
+        .filter_map(|(owner, from, to)| grid.first_hit(tanks, from, to, HIT_RADIUS, |t| t.id != tanks[*owner].id))
+        .count();
+    let sighted = tanks
+        .iter()
+        .filter_map(|npc| grid.nearest(tanks, &npc.position, SIGHT_RANGE, |t| t.id != npc.id))
+        .count();
+    hits + sighted
+}
+
+fn brute_force_queries(tanks: &[Tank], bullets: &[Shot]) -> usize {
+    let hits = bullets
+        .iter()
+        .filter_map(|(owner, from, to)| {
+            tanks
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| i != owner)
+                .filter_map(|(i, t)| segment_circle(from, to, &t.position, HIT_RADIUS).map(|hit| (i, hit)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+        })
+        .count();
+    let sighted = tanks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, npc)| {
+            tanks
+                .iter()
+                .enumerate()
+                .filter(|&(j, t)| j != i && distance_sq(&t.position, &npc.position) <= SIGHT_RANGE * SIGHT_RANGE)
+                .min_by(|a, b| {
+                    distance_sq(&a.1.position, &npc.position).total_cmp(&distance_sq(&b.1.position, &npc.position))
+                })
+        })
+        .count();
+    hits + sighted
+}
+
+fn broadphase(c: &mut Criterion) {
+    let (tanks, bullets) = scenario();
+    let map = Map::default();
+
+    let mut group = c.benchmark_group("tick");
+    group.bench_function("grid", |b| b.iter(|| grid_queries(black_box(&tanks), black_box(&bullets))));
+    group.bench_function("brute_force", |b| {
+        b.iter(|| brute_force_queries(black_box(&tanks), black_box(&bullets)))
+    });
+    group.bench_function("separate_tanks", |b| {
+        b.iter_batched_ref(
+            || tanks.clone(),
+            |tanks| separate_tanks(tanks, &map),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, broadphase);
+criterion_main!(benches);
@@ -0,0 +1,175 @@
+//! Who a joining player is. The server hands every player a session token
+//! signed with its own secret; presenting it again later is the only way to
+//! resume that identity. Private servers can also require a shared key, or
+//! a name and password from a local accounts file, before anyone joins.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// How long a session token stays valid, in seconds
+pub const DEFAULT_TOKEN_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// The token is malformed or wasn't signed by this server
+    InvalidToken,
+    ExpiredToken,
+    /// The server needs a key or password and none was given
+    MissingCredentials,
+    WrongCredentials,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::InvalidToken => write!(f, "session token is not valid on this server"),
+            AuthError::ExpiredToken => write!(f, "session token has expired"),
+            AuthError::MissingCredentials => write!(f, "this server is private and needs a password to join"),
+            AuthError::WrongCredentials => write!(f, "wrong name or password"),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Issues and checks session tokens of the form
+/// `<user id>.<expiry, UNIX seconds>.<HMAC-SHA256 of the rest, hex>`.
+#[derive(Clone)]
+pub struct TokenSigner {
+    secret: Vec<u8>,
+    ttl_secs: u64,
+}
+
+impl TokenSigner {
+    pub fn new(secret: &[u8], ttl_secs: u64) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            ttl_secs,
+        }
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC takes keys of any length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    /// A token for `user_id`, valid for the signer's lifetime from `now`
+    /// (UNIX seconds).
+    pub fn issue(&self, user_id: &str, now: u64) -> String {
+        let payload = format!("{}.{}", user_id, now.saturating_add(self.ttl_secs));
+        let signature = hex::encode(self.mac(&payload).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    /// The user id `token` was issued for, if it is genuine and hasn't
+    /// expired by `now` (UNIX seconds).
+    pub fn verify(&self, token: &str, now: u64) -> Result<String, AuthError> {
+        // Split from the right, so user ids may contain dots
+        let mut parts = token.rsplitn(3, '.');
+        let (Some(signature), Some(expires_at), Some(user_id)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(AuthError::InvalidToken);
+        };
+        let signature = hex::decode(signature).map_err(|_| AuthError::InvalidToken)?;
+        self.mac(&format!("{}.{}", user_id, expires_at))
+            .verify_slice(&signature)
+            .map_err(|_| AuthError::InvalidToken)?;
+
+        let expires_at: u64 = expires_at.parse().map_err(|_| AuthError::InvalidToken)?;
+        if now >= expires_at {
+            return Err(AuthError::ExpiredToken);
+        }
+        Ok(user_id.to_string())
+    }
+}
+
+/// Hex SHA-256 of `password`, as stored in accounts files.
+pub fn hash_password(password: &str) -> String {
+    hex::encode(Sha256::digest(password.as_bytes()))
+}
+
+/// Compares two secrets in time that doesn't depend on where they differ.
+fn secrets_match(given: &str, expected: &str) -> bool {
+    let (given, expected) = (Sha256::digest(given.as_bytes()), Sha256::digest(expected.as_bytes()));
+    given.iter().zip(expected.iter()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub name: String,
+    /// From `hash_password`
+    pub password_sha256: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct AccountsFile {
+    accounts: Vec<Account>,
+}
+
+/// Who may join without a session token.
+#[derive(Debug, Clone, Default)]
+pub enum AuthMode {
+    /// Anyone, as a new player
+    #[default]
+    Open,
+    /// Anyone with the server's shared key
+    SharedKey(String),
+    /// Only the listed accounts, by name and password hash
+    Accounts(HashMap<String, String>),
+}
+
+impl AuthMode {
+    /// Accounts from a JSON file of the form
+    /// `{"accounts": [{"name": ..., "password_sha256": ...}]}`.
+    pub fn load_accounts(path: impl AsRef<Path>) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("could not read accounts: {}", e))?;
+        let file: AccountsFile = serde_json::from_str(&json).map_err(|e| format!("invalid accounts JSON: {}", e))?;
+        Ok(AuthMode::Accounts(
+            file.accounts
+                .into_iter()
+                .map(|account| (account.name, account.password_sha256.to_lowercase()))
+                .collect(),
+        ))
+    }
+
+    /// Checks a join without a token. The user id to give the player comes
+    /// back: `None` for a newcomer who gets a fresh one, or the account's
+    /// own id so logging in again always resumes the same identity.
+    pub fn admit(&self, name: &str, password: Option<&str>) -> Result<Option<String>, AuthError> {
+        match self {
+            AuthMode::Open => Ok(None),
+            AuthMode::SharedKey(key) => {
+                let password = password.ok_or(AuthError::MissingCredentials)?;
+                if secrets_match(password, key) {
+                    Ok(None)
+                } else {
+                    Err(AuthError::WrongCredentials)
+                }
+            }
+            AuthMode::Accounts(accounts) => {
+                let password = password.ok_or(AuthError::MissingCredentials)?;
+                // Unknown names take as long as wrong passwords
+                let expected = accounts.get(name).map(String::as_str).unwrap_or_default();
+                if secrets_match(&hash_password(password), expected) && accounts.contains_key(name) {
+                    Ok(Some(account_user_id(name)))
+                } else {
+                    Err(AuthError::WrongCredentials)
+                }
+            }
+        }
+    }
+
+    pub fn is_private(&self) -> bool {
+        !matches!(self, AuthMode::Open)
+    }
+}
+
+/// User id of the player logged in as account `name`.
+pub fn account_user_id(name: &str) -> String {
+    format!("account:{}", name)
+}
@@ -0,0 +1,208 @@
+//! Moving bullets for one tick and resolving what they hit. Each bullet is
+//! swept along the path it covers, so a fast one can't skip over a tank or
+//! a thin wall, and a lagging shooter's bullets are tested against where
+//! tanks stood in the snapshot that shooter last saw. The barrel is swept
+//! too when a bullet is fired, so a tank pressed against a wall can't shoot
+//! through it.
+
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::damage::damage_from;
+use crate::grid::SpatialGrid;
+use crate::map::Map;
+use crate::rewind::TankHistory;
+use crate::server_config::Tuning;
+use crate::sweep;
+use crate::teams::is_enemy;
+use crate::{Bullet, HitEvent, Position, Tank, Team, Velocity};
+
+/// Bullets leave the barrel this far from the tank's centre
+pub const MUZZLE_DISTANCE: f32 = 30.0;
+
+/// What bullets fly through and by which rules.
+pub struct BulletWorld<'a> {
+    pub map: &'a Map,
+    pub tuning: Tuning,
+    /// Whether bullets hurt teammates in team modes
+    pub friendly_fire: bool,
+    /// Killed tanks respawn this long after dying
+    pub respawn_delay_ms: u64,
+    /// Where tanks stood over recent ticks, for lag compensation
+    pub history: &'a TankHistory,
+    pub tick: u64,
+}
+
+/// What came of a tick of bullets.
+#[derive(Debug, Default)]
+pub struct BulletResults {
+    pub hits: Vec<HitEvent>,
+    /// Where and when (server time) tanks died
+    pub deaths: Vec<(u64, Position)>,
+    /// The team of the shooter, for every kill of an enemy by a team player
+    pub team_kills: Vec<Team>,
+}
+
+impl BulletResults {
+    /// Adds `other`'s hits, deaths and kills after these.
+    pub fn append(&mut self, other: BulletResults) {
+        self.hits.extend(other.hits);
+        self.deaths.extend(other.deaths);
+        self.team_kills.extend(other.team_kills);
+    }
+}
+
+/// A bullet leaving `tank`'s muzzle at `wall_ms` (Unix milliseconds), along
+/// its turret.
+pub fn bullet_from(tank: &Tank, tuning: &Tuning, wall_ms: u64) -> Bullet {
+    let (sin, cos) = tank.turret_rotation.sin_cos();
+    Bullet {
+        id: Uuid::new_v4().to_string(),
+        position: Position {
+            x: tank.position.x + cos * MUZZLE_DISTANCE,
+            y: tank.position.y + sin * MUZZLE_DISTANCE,
+        },
+        velocity: Velocity {
+            x: cos * tuning.bullet_speed,
+            y: sin * tuning.bullet_speed,
+        },
+        owner_id: tank.id.clone(),
+        created_at: wall_ms,
+        damage: tuning.bullet_damage,
+    }
+}
+
+/// Fires `bullet` from its owner's muzzle, tested `rewind` ticks into the
+/// past like any other bullet. The barrel, from the owner's centre to the
+/// muzzle, is swept first: a wall there stops the bullet and a tank there
+/// is hit at once. Otherwise the bullet joins `bullets`.
+pub fn fire(
+    bullet: Bullet,
+    rewind: u64,
+    bullets: &mut Vec<Bullet>,
+    bullet_rewind: &mut HashMap<String, u64>,
+    tanks: &mut [Tank],
+    world: &BulletWorld,
+    server_time: u64,
+) -> BulletResults {
+    let Some(centre) = tanks.iter().find(|t| t.id == bullet.owner_id).map(|t| t.position.clone()) else {
+        bullets.push(bullet);
+        return BulletResults::default();
+    };
+    // The barrel flies as one second from the centre to the muzzle, by the
+    // same rules as a tick of flight
+    let mut barrel = vec![Bullet {
+        position: centre.clone(),
+        velocity: Velocity {
+            x: bullet.position.x - centre.x,
+            y: bullet.position.y - centre.y,
+        },
+        ..bullet.clone()
+    }];
+    let mut barrel_rewind = HashMap::new();
+    if rewind > 0 {
+        barrel_rewind.insert(bullet.id.clone(), rewind);
+    }
+    let results = update_bullets(&mut barrel, tanks, &mut barrel_rewind, world, 1.0, server_time, bullet.created_at);
+    if !barrel.is_empty() {
+        bullet_rewind.extend(barrel_rewind);
+        bullets.push(bullet);
+    }
+    results
+}
+
+/// Moves every bullet `dt` seconds on, damaging the first tank each one
+/// reaches before an obstacle. Bullets stop on what they hit and expire
+/// after the bullet lifetime. `bullet_rewind` has the ticks into the past
+/// each bullet is tested against; it is pruned of bullets that are gone.
+/// `server_time` stamps respawns and spawn protection, `wall_ms` (Unix
+/// milliseconds) bullet ages.
+pub fn update_bullets(
+    bullets: &mut Vec<Bullet>,
+    tanks: &mut [Tank],
+    bullet_rewind: &mut HashMap<String, u64>,
+    world: &BulletWorld,
+    dt: f32,
+    server_time: u64,
+    wall_ms: u64,
+) -> BulletResults {
+    let tuning = world.tuning;
+    let mut results = BulletResults::default();
+    let owner_teams: HashMap<String, Option<Team>> = tanks.iter().map(|t| (t.id.clone(), t.team)).collect();
+    let grid = SpatialGrid::from_tanks(tanks);
+    // The world as lagging shooters saw it, built once per distinct rewind
+    let mut rewound: HashMap<u64, (Vec<Tank>, SpatialGrid)> = HashMap::new();
+
+    bullets.retain_mut(|bullet| {
+        if wall_ms.saturating_sub(bullet.created_at) > tuning.bullet_lifetime_ms {
+            return false;
+        }
+
+        let from = bullet.position.clone();
+        bullet.position.x += bullet.velocity.x * dt;
+        bullet.position.y += bullet.velocity.y * dt;
+        let obstacle = world.map.bullet_impact(&from, &bullet.position);
+
+        // Check for collisions with tanks along the path, up to the first
+        // obstacle. Without friendly fire, bullets pass through teammates
+        let owner_team = owner_teams.get(&bullet.owner_id).copied().flatten();
+        let rewind = bullet_rewind.get(&bullet.id).copied().unwrap_or(0);
+        if rewind > 0 && !rewound.contains_key(&rewind) {
+            if let Some(past) = world.history.rewound(tanks, world.tick.saturating_sub(rewind)) {
+                let past_grid = SpatialGrid::from_tanks(&past);
+                rewound.insert(rewind, (past, past_grid));
+            }
+        }
+        let (targets, targets_grid) = match rewound.get(&rewind) {
+            Some((past, past_grid)) => (past.as_slice(), past_grid),
+            None => (&*tanks, &grid),
+        };
+        let hit = targets_grid.first_hit(targets, &from, &bullet.position, tuning.bullet_hit_radius, |tank| {
+            // Don't hit yourself or tanks killed earlier this tick
+            tank.id != bullet.owner_id && !tank.is_dead && (world.friendly_fire || is_enemy(owner_team, tank.team))
+        });
+        let Some((index, t)) = hit.filter(|&(_, t)| obstacle.is_none_or(|wall| t <= wall)) else {
+            // Bullets stop on walls, boxes and the edge of the map
+            return obstacle.is_none() && !world.map.blocks_bullet(&bullet.position);
+        };
+        let impact = sweep::lerp(&from, &bullet.position, t);
+
+        let tank = &mut tanks[index];
+        if tank.spawn_protected_until.is_some_and(|until| server_time < until) {
+            return false; // Absorbed by spawn protection
+        }
+
+        let (damage, side) = damage_from(bullet, tank);
+        tank.health = (tank.health - damage).max(0);
+        let killed = tank.health == 0;
+        if killed {
+            tank.is_dead = true;
+            for (past, _) in rewound.values_mut() {
+                past[index].is_dead = true;
+            }
+            tank.respawn_time = Some(server_time + world.respawn_delay_ms);
+            results.deaths.push((server_time, tank.position.clone()));
+            if let Some(team) = owner_team.filter(|&team| tank.team != Some(team)) {
+                results.team_kills.push(team);
+            }
+        }
+        results.hits.push(HitEvent {
+            tick: world.tick,
+            attacker_id: bullet.owner_id.clone(),
+            target_id: tank.id.clone(),
+            damage,
+            side,
+            position: impact,
+            health: tank.health,
+            killed,
+        });
+        false // Bullet is consumed by one tank
+    });
+
+    if !bullet_rewind.is_empty() {
+        let live: HashSet<&String> = bullets.iter().map(|b| &b.id).collect();
+        bullet_rewind.retain(|id, _| live.contains(id));
+    }
+    results
+}
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use bevy::window::WindowResolution;
 use std::sync::{Arc, Mutex};
+use battlexone_shared::SIMULATION_TICK_RATE;
 
 mod tank_model;
 use tank_model::TankModel;
@@ -12,10 +13,19 @@ mod game_state;
 use game_state::{GameStateResource, PlayerInfo};
 
 mod input;
-use input::{InputState, handle_input, send_input};
+use input::{InputState, follow_server_tick_rate, handle_input, send_input};
+
+mod prediction;
+use prediction::{PredictionState, reconcile_prediction};
+
+mod hud;
+use hud::{setup_hud, spawn_hit_feedback, update_announcements, update_hit_feedback, update_match_text, update_status_text};
+
+mod scoreboard;
+use scoreboard::{setup_scoreboard, update_leaderboard, update_scoreboard};
 
 mod rendering;
-use rendering::{setup_rendering, update_game_entities, update_camera};
+use rendering::{setup_rendering, update_game_entities, update_camera, update_flag_markers, update_zone_rings, update_map_obstacles};
 
 fn main() {
     App::new()
@@ -27,11 +37,14 @@ fn main() {
             }),
             ..default()
         }))
+        .insert_resource(Time::<Fixed>::from_hz(SIMULATION_TICK_RATE as f64))
         .insert_resource(GameStateResource::new())
         .insert_resource(PlayerInfo::new())
         .insert_resource(InputState::new())
+        .insert_resource(PredictionState::new())
         .insert_resource(ConnectionState {
             connected: Arc::new(Mutex::new(false)),
+            rejection: Arc::new(Mutex::new(None)),
         })
         .insert_resource(WebSocketSender {
             sender: Arc::new(Mutex::new(None)),
@@ -40,12 +53,22 @@ fn main() {
             body_scene: Handle::default(),
             turret_scene: Handle::default(),
         })
-        .add_systems(Startup, setup)
+        .add_systems(Startup, (setup, setup_hud, setup_scoreboard))
+        .add_systems(FixedUpdate, (send_input, reconcile_prediction).chain())
         .add_systems(Update, (
             handle_input,
-            send_input,
+            follow_server_tick_rate,
             update_game_entities,
             update_camera,
+            update_flag_markers,
+            update_zone_rings,
+            update_map_obstacles,
+            update_status_text,
+            update_match_text,
+            update_announcements,
+            (spawn_hit_feedback, update_hit_feedback).chain(),
+            update_scoreboard,
+            update_leaderboard,
         ))
         .run();
 }
@@ -67,6 +90,7 @@ fn setup(
         game_state,
         player_info,
         connection_state.connected.clone(),
+        connection_state.rejection.clone(),
         ws_sender.sender.clone(),
     );
 } 
\ No newline at end of file
@@ -0,0 +1,86 @@
+//! Client-side prediction for the local tank. Every input sent to the
+//! server is kept until a snapshot acknowledges it; the predicted tank is
+//! the latest authoritative tank with all unacknowledged inputs replayed on
+//! top, at the server's tick length so the replay matches the simulation.
+
+use std::collections::VecDeque;
+
+use crate::map::Map;
+use crate::movement::apply_movement;
+use crate::{Tank, SIMULATION_TICK_RATE};
+
+/// Unacknowledged inputs kept at most, oldest dropped first, so a server
+/// that stops acknowledging can't grow the replay without bound
+pub const MAX_PENDING_INPUTS: usize = 120;
+
+#[derive(Debug, Clone)]
+pub struct Prediction {
+    pending_inputs: VecDeque<(u32, u16)>,
+    predicted_tank: Option<Tank>,
+    dt: f32,
+}
+
+impl Default for Prediction {
+    fn default() -> Self {
+        Self::new(SIMULATION_TICK_RATE)
+    }
+}
+
+impl Prediction {
+    /// Prediction for a server simulating `tick_rate` ticks a second.
+    pub fn new(tick_rate: u32) -> Self {
+        Self {
+            pending_inputs: VecDeque::new(),
+            predicted_tank: None,
+            dt: 1.0 / tick_rate.max(1) as f32,
+        }
+    }
+
+    /// Length of one predicted tick in seconds.
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    pub fn record_input(&mut self, seq: u32, input: u16) {
+        self.pending_inputs.push_back((seq, input));
+        while self.pending_inputs.len() > MAX_PENDING_INPUTS {
+            self.pending_inputs.pop_front();
+        }
+    }
+
+    /// Inputs sent but not yet applied by the server.
+    pub fn pending_inputs(&self) -> usize {
+        self.pending_inputs.len()
+    }
+
+    /// Rebuilds the predicted tank from an authoritative snapshot of it and
+    /// the sequence number of the last input the server applied.
+    pub fn reconcile(&mut self, authoritative: &Tank, last_processed_input: u32, map: &Map) {
+        while self
+            .pending_inputs
+            .front()
+            .is_some_and(|&(seq, _)| seq <= last_processed_input)
+        {
+            self.pending_inputs.pop_front();
+        }
+
+        let mut tank = authoritative.clone();
+        for &(_, input) in &self.pending_inputs {
+            apply_movement(&mut tank, input, self.dt, map);
+        }
+        self.predicted_tank = Some(tank);
+    }
+
+    pub fn predicted_tank(&self) -> Option<&Tank> {
+        self.predicted_tank.as_ref()
+    }
+
+    /// Replaces the local tank in `tanks` with its predicted state.
+    pub fn apply_to(&self, tanks: &mut [Tank]) {
+        if let Some(predicted) = &self.predicted_tank {
+            if let Some(tank) = tanks.iter_mut().find(|t| t.id == predicted.id) {
+                *tank = predicted.clone();
+            }
+        }
+    }
+}
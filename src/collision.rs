@@ -0,0 +1,67 @@
+//! Tank-vs-tank collision. Tanks are circles of `TANK_RADIUS`; after
+//! everyone has moved for a tick, overlapping tanks are pushed apart along
+//! the line between their centres, each taking half the correction. Only
+//! the overlap is removed, so tanks meeting at an angle slide past each
+//! other instead of stopping dead. Candidate pairs come from a
+//! `SpatialGrid` rebuilt each pass, so a crowded arena doesn't cost a check
+//! per pair of tanks.
+
+use crate::grid::SpatialGrid;
+use crate::map::{Map, TANK_RADIUS};
+use crate::Tank;
+
+// A pileup needs a few rounds to settle, since separating one pair can push
+// a tank into a third
+const SEPARATION_PASSES: usize = 8;
+
+/// Pushes living tanks out of each other and keeps them out of `map`'s
+/// obstacles. Returns whether any tanks overlapped.
+pub fn separate_tanks(tanks: &mut [Tank], map: &Map) -> bool {
+    let min_distance = TANK_RADIUS * 2.0;
+    let mut any_overlap = false;
+
+    for _ in 0..SEPARATION_PASSES {
+        let mut overlapped = false;
+        let grid = SpatialGrid::from_tanks(tanks);
+        for i in 0..tanks.len() {
+            if tanks[i].is_dead {
+                continue;
+            }
+            for j in grid.query(&tanks[i].position, min_distance) {
+                if j <= i {
+                    continue;
+                }
+                let dx = tanks[j].position.x - tanks[i].position.x;
+                let dy = tanks[j].position.y - tanks[i].position.y;
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance >= min_distance {
+                    continue;
+                }
+                overlapped = true;
+
+                // Tanks on exactly the same spot get split in a direction
+                // that differs per pair, so a pileup fans out
+                let (nx, ny) = if distance > f32::EPSILON {
+                    (dx / distance, dy / distance)
+                } else {
+                    let angle = (i * tanks.len() + j) as f32;
+                    (angle.cos(), angle.sin())
+                };
+                let push = (min_distance - distance) / 2.0;
+                tanks[i].position.x -= nx * push;
+                tanks[i].position.y -= ny * push;
+                tanks[j].position.x += nx * push;
+                tanks[j].position.y += ny * push;
+            }
+        }
+        if !overlapped {
+            break;
+        }
+        any_overlap = true;
+        for tank in tanks.iter_mut().filter(|t| !t.is_dead) {
+            map.resolve_tank(&mut tank.position);
+        }
+    }
+
+    any_overlap
+}
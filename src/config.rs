@@ -1,8 +1,10 @@
 use bevy::prelude::*;
 use bevy::window::WindowResolution;
 use std::sync::{Arc, Mutex};
+use battlexone_shared::SIMULATION_TICK_RATE;
 use crate::game_state::{GameStateResource, PlayerInfo};
 use crate::input::InputState;
+use crate::prediction::PredictionState;
 use crate::network::{ConnectionState, WebSocketSender};
 use crate::tank_model::TankModel;
 
@@ -18,11 +20,14 @@ pub fn setup_window_config() -> WindowPlugin {
 }
 
 pub fn setup_resources(app: &mut App) {
-    app.insert_resource(GameStateResource::new())
+    app.insert_resource(Time::<Fixed>::from_hz(SIMULATION_TICK_RATE as f64))
+        .insert_resource(GameStateResource::new())
         .insert_resource(PlayerInfo::new())
         .insert_resource(InputState::new())
+        .insert_resource(PredictionState::new())
         .insert_resource(ConnectionState {
             connected: Arc::new(Mutex::new(false)),
+            rejection: Arc::new(Mutex::new(None)),
         })
         .insert_resource(WebSocketSender {
             sender: Arc::new(Mutex::new(None)),
@@ -0,0 +1,54 @@
+//! Directional armour: how much of a bullet's damage gets through depends on
+//! which side of the hull it strikes.
+
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_4, PI, TAU};
+
+use crate::{Bullet, Tank};
+
+/// Damage taken from a front hit, relative to the bullet's damage
+pub const FRONT_ARMOUR_MULTIPLIER: f32 = 0.6;
+pub const SIDE_ARMOUR_MULTIPLIER: f32 = 1.0;
+pub const REAR_ARMOUR_MULTIPLIER: f32 = 1.5;
+
+/// Which face of the hull a hit landed on. The front and rear arcs are 90°
+/// wide, centred on the hull's facing; everything else is a side hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HitSide {
+    Front,
+    Side,
+    Rear,
+}
+
+impl HitSide {
+    pub fn armour_multiplier(self) -> f32 {
+        match self {
+            HitSide::Front => FRONT_ARMOUR_MULTIPLIER,
+            HitSide::Side => SIDE_ARMOUR_MULTIPLIER,
+            HitSide::Rear => REAR_ARMOUR_MULTIPLIER,
+        }
+    }
+}
+
+/// Classifies a hit on a hull facing `rotation` by a bullet travelling
+/// along `bullet_heading` (both in radians, the convention `Tank::rotation`
+/// uses for forward).
+pub fn hit_side(rotation: f32, bullet_heading: f32) -> HitSide {
+    // The direction the bullet came from, relative to the hull's facing
+    let impact = (bullet_heading + PI - rotation + PI).rem_euclid(TAU) - PI;
+    if impact.abs() <= FRAC_PI_4 {
+        HitSide::Front
+    } else if impact.abs() >= PI - FRAC_PI_4 {
+        HitSide::Rear
+    } else {
+        HitSide::Side
+    }
+}
+
+/// Damage `bullet` deals to `tank` after armour, and the side it struck.
+pub fn damage_from(bullet: &Bullet, tank: &Tank) -> (i32, HitSide) {
+    let side = hit_side(tank.rotation, bullet.velocity.y.atan2(bullet.velocity.x));
+    let damage = (bullet.damage as f32 * side.armour_multiplier()).round() as i32;
+    (damage, side)
+}
@@ -0,0 +1,259 @@
+//! Delta compression for game state snapshots. The server diffs each new
+//! snapshot against the last one a client acknowledged and sends only what
+//! changed; the client applies the delta to its own copy of that base.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::{Bullet, CaptureZone, GameState, Position, Tank, Team};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameStateDelta {
+    /// Tick of the snapshot this delta must be applied to
+    pub base_tick: u64,
+    pub tick: u64,
+    pub server_time: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spawned_tanks: Vec<Tank>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed_tanks: Vec<TankDelta>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub despawned_tanks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spawned_bullets: Vec<Bullet>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moved_bullets: Vec<BulletDelta>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub despawned_bullets: Vec<String>,
+    /// Only the entries that changed since the base snapshot
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub last_processed_input: HashMap<String, u32>,
+    /// Every zone, sent whenever any of them changed (there are only a few)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zones: Option<Vec<CaptureZone>>,
+}
+
+/// Fields of a tank that changed since the base snapshot; `None` means
+/// unchanged. `is_player` never changes after spawn so it is not tracked.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TankDelta {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turret_rotation: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_dead: Option<bool>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "double_option"
+    )]
+    pub respawn_time: Option<Option<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_fire_time: Option<u64>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "double_option"
+    )]
+    pub spawn_protected_until: Option<Option<u64>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "double_option"
+    )]
+    pub team: Option<Option<Team>>,
+}
+
+/// Bullets fly with a constant velocity, so only their position changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulletDelta {
+    pub id: String,
+    pub position: Position,
+}
+
+// Distinguishes an absent field (unchanged) from an explicit `null` (changed to None)
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+fn changed<T: PartialEq + Clone>(before: &T, after: &T) -> Option<T> {
+    (before != after).then(|| after.clone())
+}
+
+impl TankDelta {
+    fn between(base: &Tank, current: &Tank) -> Option<Self> {
+        let delta = Self {
+            id: current.id.clone(),
+            position: changed(&base.position, &current.position),
+            rotation: changed(&base.rotation, &current.rotation),
+            turret_rotation: changed(&base.turret_rotation, &current.turret_rotation),
+            health: changed(&base.health, &current.health),
+            is_dead: changed(&base.is_dead, &current.is_dead),
+            respawn_time: changed(&base.respawn_time, &current.respawn_time),
+            last_fire_time: changed(&base.last_fire_time, &current.last_fire_time),
+            spawn_protected_until: changed(&base.spawn_protected_until, &current.spawn_protected_until),
+            team: changed(&base.team, &current.team),
+        };
+        let unchanged = Self {
+            id: current.id.clone(),
+            ..Self::default()
+        };
+        (delta != unchanged).then_some(delta)
+    }
+
+    fn apply(&self, tank: &mut Tank) {
+        if let Some(position) = &self.position {
+            tank.position = position.clone();
+        }
+        if let Some(rotation) = self.rotation {
+            tank.rotation = rotation;
+        }
+        if let Some(turret_rotation) = self.turret_rotation {
+            tank.turret_rotation = turret_rotation;
+        }
+        if let Some(health) = self.health {
+            tank.health = health;
+        }
+        if let Some(is_dead) = self.is_dead {
+            tank.is_dead = is_dead;
+        }
+        if let Some(respawn_time) = self.respawn_time {
+            tank.respawn_time = respawn_time;
+        }
+        if let Some(last_fire_time) = self.last_fire_time {
+            tank.last_fire_time = last_fire_time;
+        }
+        if let Some(spawn_protected_until) = self.spawn_protected_until {
+            tank.spawn_protected_until = spawn_protected_until;
+        }
+        if let Some(team) = self.team {
+            tank.team = team;
+        }
+    }
+}
+
+impl GameStateDelta {
+    /// Describes how to turn `base` into `current`.
+    pub fn between(base: &GameState, current: &GameState) -> Self {
+        let base_tanks: HashMap<&str, &Tank> = base.tanks.iter().map(|t| (t.id.as_str(), t)).collect();
+        let base_bullets: HashMap<&str, &Bullet> = base.bullets.iter().map(|b| (b.id.as_str(), b)).collect();
+        let current_tanks: HashSet<&str> = current.tanks.iter().map(|t| t.id.as_str()).collect();
+        let current_bullets: HashSet<&str> = current.bullets.iter().map(|b| b.id.as_str()).collect();
+
+        let mut delta = Self {
+            base_tick: base.tick,
+            tick: current.tick,
+            server_time: current.server_time,
+            spawned_tanks: Vec::new(),
+            changed_tanks: Vec::new(),
+            despawned_tanks: Vec::new(),
+            spawned_bullets: Vec::new(),
+            moved_bullets: Vec::new(),
+            despawned_bullets: Vec::new(),
+            last_processed_input: HashMap::new(),
+            zones: changed(&base.zones, &current.zones),
+        };
+
+        for tank in &current.tanks {
+            match base_tanks.get(tank.id.as_str()) {
+                Some(before) => delta.changed_tanks.extend(TankDelta::between(before, tank)),
+                None => delta.spawned_tanks.push(tank.clone()),
+            }
+        }
+        delta.despawned_tanks = base
+            .tanks
+            .iter()
+            .filter(|t| !current_tanks.contains(t.id.as_str()))
+            .map(|t| t.id.clone())
+            .collect();
+
+        for bullet in &current.bullets {
+            match base_bullets.get(bullet.id.as_str()) {
+                Some(before) if before.position == bullet.position => {}
+                Some(_) => delta.moved_bullets.push(BulletDelta {
+                    id: bullet.id.clone(),
+                    position: bullet.position.clone(),
+                }),
+                None => delta.spawned_bullets.push(bullet.clone()),
+            }
+        }
+        delta.despawned_bullets = base
+            .bullets
+            .iter()
+            .filter(|b| !current_bullets.contains(b.id.as_str()))
+            .map(|b| b.id.clone())
+            .collect();
+
+        delta.last_processed_input = current
+            .last_processed_input
+            .iter()
+            .filter(|(player_id, seq)| base.last_processed_input.get(*player_id) != Some(seq))
+            .map(|(player_id, seq)| (player_id.clone(), *seq))
+            .collect();
+
+        delta
+    }
+
+    /// Rebuilds the snapshot at `self.tick` from the base snapshot it was
+    /// computed against. Returns `None` if `base` is not that snapshot.
+    pub fn apply(&self, base: &GameState) -> Option<GameState> {
+        if base.tick != self.base_tick {
+            return None;
+        }
+
+        let despawned_tanks: HashSet<&str> = self.despawned_tanks.iter().map(String::as_str).collect();
+        let changed_tanks: HashMap<&str, &TankDelta> =
+            self.changed_tanks.iter().map(|d| (d.id.as_str(), d)).collect();
+        let mut tanks: Vec<Tank> = base
+            .tanks
+            .iter()
+            .filter(|t| !despawned_tanks.contains(t.id.as_str()))
+            .cloned()
+            .collect();
+        for tank in tanks.iter_mut() {
+            if let Some(delta) = changed_tanks.get(tank.id.as_str()) {
+                delta.apply(tank);
+            }
+        }
+        tanks.extend(self.spawned_tanks.iter().cloned());
+
+        let despawned_bullets: HashSet<&str> = self.despawned_bullets.iter().map(String::as_str).collect();
+        let moved_bullets: HashMap<&str, &Position> =
+            self.moved_bullets.iter().map(|d| (d.id.as_str(), &d.position)).collect();
+        let mut bullets: Vec<Bullet> = base
+            .bullets
+            .iter()
+            .filter(|b| !despawned_bullets.contains(b.id.as_str()))
+            .cloned()
+            .collect();
+        for bullet in bullets.iter_mut() {
+            if let Some(position) = moved_bullets.get(bullet.id.as_str()) {
+                bullet.position = (*position).clone();
+            }
+        }
+        bullets.extend(self.spawned_bullets.iter().cloned());
+
+        let mut last_processed_input = base.last_processed_input.clone();
+        last_processed_input.retain(|player_id, _| !despawned_tanks.contains(player_id.as_str()));
+        last_processed_input.extend(self.last_processed_input.iter().map(|(k, v)| (k.clone(), *v)));
+
+        Some(GameState {
+            tick: self.tick,
+            server_time: self.server_time,
+            tanks,
+            bullets,
+            last_processed_input,
+            zones: self.zones.clone().unwrap_or_else(|| base.zones.clone()),
+        })
+    }
+}
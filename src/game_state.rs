@@ -1,33 +1,107 @@
 use bevy::prelude::*;
 use std::sync::{Arc, Mutex};
 use battlexone_shared::*;
+use battlexone_shared::map::Map;
+use battlexone_shared::interpolation::SnapshotBuffer;
 
 #[derive(Resource)]
 pub struct GameStateResource {
-    data: Arc<Mutex<(Vec<Tank>, Vec<Bullet>)>>,
+    data: Arc<Mutex<GameState>>,
+    snapshots: Arc<Mutex<SnapshotBuffer>>,
+    hits: Arc<Mutex<Vec<HitEvent>>>,
+    scoreboard: Arc<Mutex<Vec<ScoreboardEntry>>>,
+    leaderboard: Arc<Mutex<Vec<LeaderboardEntry>>>,
+    match_state: Arc<Mutex<Option<MatchState>>>,
+    flag_events: Arc<Mutex<Vec<FlagEvent>>>,
+    zone_events: Arc<Mutex<Vec<ZoneEvent>>>,
+    map: Arc<Mutex<Map>>,
+    joins: Arc<Mutex<u64>>,
+    tick_rate: Arc<Mutex<u32>>,
 }
 
 impl GameStateResource {
     pub fn new() -> Self {
         Self {
-            data: Arc::new(Mutex::new((Vec::new(), Vec::new()))),
+            data: Arc::new(Mutex::new(GameState::default())),
+            snapshots: Arc::new(Mutex::new(SnapshotBuffer::new())),
+            hits: Arc::new(Mutex::new(Vec::new())),
+            scoreboard: Arc::new(Mutex::new(Vec::new())),
+            leaderboard: Arc::new(Mutex::new(Vec::new())),
+            match_state: Arc::new(Mutex::new(None)),
+            flag_events: Arc::new(Mutex::new(Vec::new())),
+            zone_events: Arc::new(Mutex::new(Vec::new())),
+            map: Arc::new(Mutex::new(Map::default())),
+            joins: Arc::new(Mutex::new(0)),
+            tick_rate: Arc::new(Mutex::new(SIMULATION_TICK_RATE)),
         }
     }
 
-    pub fn get_data(&self) -> Arc<Mutex<(Vec<Tank>, Vec<Bullet>)>> {
+    /// Latest authoritative snapshot, used for prediction and the camera
+    pub fn get_data(&self) -> Arc<Mutex<GameState>> {
         self.data.clone()
     }
 
+    /// Recent snapshots that remote entities are interpolated between
+    pub fn get_snapshots(&self) -> Arc<Mutex<SnapshotBuffer>> {
+        self.snapshots.clone()
+    }
+
+    /// Hit events received since the HUD last drained them
+    pub fn get_hits(&self) -> Arc<Mutex<Vec<HitEvent>>> {
+        self.hits.clone()
+    }
+
+    /// Latest scoreboard from the server, best player first
+    pub fn get_scoreboard(&self) -> Arc<Mutex<Vec<ScoreboardEntry>>> {
+        self.scoreboard.clone()
+    }
+
+    /// Skill leaderboard as last requested, best rated first
+    pub fn get_leaderboard(&self) -> Arc<Mutex<Vec<LeaderboardEntry>>> {
+        self.leaderboard.clone()
+    }
+
+    /// Current match phase and limits, once the server has sent them
+    pub fn get_match_state(&self) -> Arc<Mutex<Option<MatchState>>> {
+        self.match_state.clone()
+    }
+
+    /// Flag events received since the HUD last announced them
+    pub fn get_flag_events(&self) -> Arc<Mutex<Vec<FlagEvent>>> {
+        self.flag_events.clone()
+    }
+
+    /// The map the server sent on join
+    pub fn get_map(&self) -> Arc<Mutex<Map>> {
+        self.map.clone()
+    }
+
+    /// How many times we've joined, so state kept from an earlier
+    /// connection can tell it is out of date
+    pub fn get_joins(&self) -> Arc<Mutex<u64>> {
+        self.joins.clone()
+    }
+
+    /// Simulation ticks per second on the server we joined
+    pub fn get_tick_rate(&self) -> Arc<Mutex<u32>> {
+        self.tick_rate.clone()
+    }
+
+    /// Zone events received since the HUD last announced them
+    pub fn get_zone_events(&self) -> Arc<Mutex<Vec<ZoneEvent>>> {
+        self.zone_events.clone()
+    }
+
     #[allow(dead_code)]
-    pub fn get_game_state(&self) -> (Vec<Tank>, Vec<Bullet>) {
+    pub fn get_game_state(&self) -> GameState {
         let data = self.data.lock().unwrap();
         data.clone()
     }
 
     #[allow(dead_code)]
-    pub fn update_game_state(&self, tanks: Vec<Tank>, bullets: Vec<Bullet>) {
+    pub fn update_game_state(&self, state: GameState) {
         let mut data = self.data.lock().unwrap();
-        *data = (tanks, bullets);
+        *data = state;
     }
 }
 
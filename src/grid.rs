@@ -0,0 +1,176 @@
+//! Uniform-grid broadphase over tank positions.
+//!
+//! The grid is rebuilt from scratch whenever it's needed, which is cheap
+//! next to the per-pair checks it saves: a query only looks at tanks in the
+//! cells a circle overlaps, so bullet hits, tank collisions and NPC sight
+//! stay close to linear in the number of tanks. Cells are laid out densely
+//! over the area the tanks cover, sorted by cell, so a lookup is a pair of
+//! offsets rather than a hash.
+
+use crate::sweep::{lerp, segment_circle};
+use crate::{Position, Tank};
+
+/// Side of one grid cell. About twice a tank's diameter, so most collision
+/// and hit queries touch four cells or fewer.
+pub const CELL_SIZE: f32 = 64.0;
+
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    /// Cell coordinates of the first column and row
+    min_cell: (i32, i32),
+    columns: usize,
+    rows: usize,
+    /// Where each cell's indices start in `entries`, plus one past the end
+    starts: Vec<usize>,
+    entries: Vec<usize>,
+}
+
+impl SpatialGrid {
+    /// A grid holding `index` at `position` for every pair given.
+    pub fn new(cell_size: f32, points: &[(usize, &Position)]) -> Self {
+        let cell = |p: &Position| ((p.x / cell_size).floor() as i32, (p.y / cell_size).floor() as i32);
+        let cells: Vec<(i32, i32)> = points.iter().map(|(_, p)| cell(p)).collect();
+        let (min_x, max_x) = bounds(cells.iter().map(|c| c.0));
+        let (min_y, max_y) = bounds(cells.iter().map(|c| c.1));
+        let columns = (max_x - min_x + 1) as usize;
+        let rows = (max_y - min_y + 1) as usize;
+
+        // Counting sort by cell, keeping the given order within a cell
+        let slot = |c: (i32, i32)| (c.1 - min_y) as usize * columns + (c.0 - min_x) as usize;
+        let mut starts = vec![0; columns * rows + 1];
+        for &c in &cells {
+            starts[slot(c) + 1] += 1;
+        }
+        for i in 1..starts.len() {
+            starts[i] += starts[i - 1];
+        }
+        let mut next = starts.clone();
+        let mut entries = vec![0; points.len()];
+        for (&c, &(index, _)) in cells.iter().zip(points) {
+            entries[next[slot(c)]] = index;
+            next[slot(c)] += 1;
+        }
+
+        Self {
+            cell_size,
+            min_cell: (min_x, min_y),
+            columns,
+            rows,
+            starts,
+            entries,
+        }
+    }
+
+    /// A grid over the living tanks in `tanks`, holding their indices.
+    pub fn from_tanks(tanks: &[Tank]) -> Self {
+        let living: Vec<(usize, &Position)> = tanks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| !t.is_dead)
+            .map(|(i, t)| (i, &t.position))
+            .collect();
+        Self::new(CELL_SIZE, &living)
+    }
+
+    /// Calls `f` with every index in the cells the circle at `position`
+    /// touches: a superset of what's actually within `radius`.
+    fn for_each_candidate(&self, position: &Position, radius: f32, mut f: impl FnMut(usize)) {
+        if self.entries.is_empty() {
+            return;
+        }
+        // Clamped to one past either edge, so far-off circles stay cheap
+        let offset = |v: f32, min: i32, len: usize| {
+            ((v / self.cell_size).floor() as i64 - min as i64).clamp(-1, len as i64)
+        };
+        let column = |x: f32| offset(x, self.min_cell.0, self.columns);
+        let row = |y: f32| offset(y, self.min_cell.1, self.rows);
+        let (first_column, last_column) = (column(position.x - radius).max(0), column(position.x + radius));
+        let (first_row, last_row) = (row(position.y - radius).max(0), row(position.y + radius));
+        if last_column < 0 || last_row < 0 {
+            return;
+        }
+        let last_column = last_column.min(self.columns as i64 - 1);
+        let last_row = last_row.min(self.rows as i64 - 1);
+
+        for row in first_row..=last_row {
+            // A row's cells are contiguous, so take them in one slice
+            let base = row as usize * self.columns;
+            let from = self.starts[base + first_column as usize];
+            let to = self.starts[base + last_column as usize + 1];
+            for &index in &self.entries[from..to] {
+                f(index);
+            }
+        }
+    }
+
+    /// Indices in every cell the circle at `position` touches, in ascending
+    /// order. A superset of what's actually within `radius`.
+    pub fn query(&self, position: &Position, radius: f32) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.for_each_candidate(position, radius, |i| found.push(i));
+        found.sort_unstable();
+        found
+    }
+
+    /// Earliest tank the path from `from` to `to` passes within `radius`
+    /// of and `accept` lets through, with how far along the path it's met.
+    /// Ties go to the lower index.
+    pub fn first_hit(
+        &self,
+        tanks: &[Tank],
+        from: &Position,
+        to: &Position,
+        radius: f32,
+        mut accept: impl FnMut(&Tank) -> bool,
+    ) -> Option<(usize, f32)> {
+        // Every tank the path can touch is within this circle
+        let centre = lerp(from, to, 0.5);
+        let reach = distance_sq(from, to).sqrt() / 2.0 + radius;
+
+        let mut first: Option<(usize, f32)> = None;
+        self.for_each_candidate(&centre, reach, |i| {
+            let Some(t) = segment_circle(from, to, &tanks[i].position, radius) else {
+                return;
+            };
+            let earlier = first.is_none_or(|(j, best)| t < best || (t == best && i < j));
+            if earlier && accept(&tanks[i]) {
+                first = Some((i, t));
+            }
+        });
+        first
+    }
+
+    /// Closest tank within `radius` of `position` that `accept` lets through.
+    pub fn nearest(
+        &self,
+        tanks: &[Tank],
+        position: &Position,
+        radius: f32,
+        mut accept: impl FnMut(&Tank) -> bool,
+    ) -> Option<usize> {
+        let mut nearest: Option<(usize, f32)> = None;
+        self.for_each_candidate(position, radius, |i| {
+            let d = distance_sq(&tanks[i].position, position);
+            if d <= radius * radius && nearest.is_none_or(|(_, best)| d < best) && accept(&tanks[i]) {
+                nearest = Some((i, d));
+            }
+        });
+        nearest.map(|(i, _)| i)
+    }
+}
+
+/// Smallest and largest of `values`, or an empty range at zero.
+fn bounds(values: impl Iterator<Item = i32>) -> (i32, i32) {
+    values.fold(None, |range: Option<(i32, i32)>, v| match range {
+        Some((lo, hi)) => Some((lo.min(v), hi.max(v))),
+        None => Some((v, v)),
+    })
+    .unwrap_or((0, 0))
+}
+
+fn distance_sq(a: &Position, b: &Position) -> f32 {
+    let dx = a.x - b.x;
+    let dy = a.y - b.y;
+    dx * dx + dy * dy
+}
@@ -0,0 +1,359 @@
+use bevy::prelude::*;
+use battlexone_shared::damage::HitSide;
+use battlexone_shared::{FlagEvent, FlagEventKind, GameMode, MatchPhase, MatchState, ZoneEvent, ZoneEventKind};
+use crate::game_state::{GameStateResource, PlayerInfo};
+use crate::network::ConnectionState;
+
+const DAMAGE_NUMBER_LIFETIME: f32 = 1.0; // seconds
+const DAMAGE_NUMBER_RISE: f32 = 15.0; // world units over its lifetime
+const HIT_MARKER_LIFETIME: f32 = 0.25; // seconds
+const ANNOUNCEMENT_LIFETIME: f32 = 3.0; // seconds
+
+#[derive(Component)]
+pub struct StatusText;
+
+/// Match phase and countdown, top centre.
+#[derive(Component)]
+pub struct MatchText;
+
+/// Flag pickups, drops, captures and returns, shown under the match status.
+#[derive(Component)]
+pub struct Announcement {
+    remaining: f32,
+}
+
+/// Floating number above where a hit involving the local player landed.
+#[derive(Component)]
+pub struct DamageNumber {
+    world_position: Vec3,
+    age: f32,
+}
+
+/// Crosshair flash shown when the local player lands a hit.
+#[derive(Component)]
+pub struct HitMarker {
+    remaining: f32,
+}
+
+pub fn setup_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "Connecting...",
+            TextStyle {
+                font_size: 22.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        StatusText,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 26.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Percent(40.0),
+            ..default()
+        }),
+        MatchText,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::srgb(1.0, 0.9, 0.4),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(44.0),
+            left: Val::Percent(40.0),
+            ..default()
+        }),
+        Announcement { remaining: 0.0 },
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "X",
+            TextStyle {
+                font_size: 32.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(50.0),
+            top: Val::Percent(50.0),
+            ..default()
+        }),
+        Visibility::Hidden,
+        HitMarker { remaining: 0.0 },
+    ));
+}
+
+pub fn update_status_text(
+    connection_state: Res<ConnectionState>,
+    game_state: Res<GameStateResource>,
+    player_info: Res<PlayerInfo>,
+    mut query: Query<&mut Text, With<StatusText>>,
+) {
+    let status = if let Some(reason) = connection_state.rejection.lock().unwrap().as_ref() {
+        format!("Rejected by server: {}", reason)
+    } else if *connection_state.connected.lock().unwrap() {
+        player_status(&game_state, &player_info)
+    } else {
+        "Not connected, retrying...".to_string()
+    };
+
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != status {
+            text.sections[0].value = status.clone();
+        }
+    }
+}
+
+pub fn update_match_text(
+    game_state: Res<GameStateResource>,
+    mut query: Query<&mut Text, With<MatchText>>,
+) {
+    let server_time = game_state.get_data().lock().unwrap().server_time;
+    let status = game_state
+        .get_match_state()
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or_else(String::new, |state| match_status(state, server_time));
+
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != status {
+            text.sections[0].value = status.clone();
+        }
+    }
+}
+
+fn match_status(state: &MatchState, server_time: u64) -> String {
+    let remaining_secs = state
+        .phase_ends_at
+        .map_or(0, |ends_at| ends_at.saturating_sub(server_time).div_ceil(1000));
+    let scores = state
+        .team_scores
+        .iter()
+        .map(|s| format!("{} {}", s.team.name(), s.score))
+        .collect::<Vec<_>>()
+        .join(" - ");
+    let status = match state.phase {
+        MatchPhase::Lobby => "Waiting for players...".to_string(),
+        MatchPhase::Warmup => format!("Warmup - match {} starts in {}", state.match_number, remaining_secs),
+        MatchPhase::InProgress => format!(
+            "{}:{:02} left | First to {} {}",
+            remaining_secs / 60,
+            remaining_secs % 60,
+            state.score_limit,
+            match state.mode {
+                GameMode::FreeForAll | GameMode::TeamDeathmatch => "kills",
+                GameMode::CaptureTheFlag => "captures",
+                GameMode::KingOfTheHill | GameMode::Domination => "points",
+            }
+        ),
+        // The results screen shows the rest
+        MatchPhase::Results => "Match over".to_string(),
+    };
+    if scores.is_empty() {
+        status
+    } else {
+        format!("{} | {}", scores, status)
+    }
+}
+
+/// Announces flag events and fades the announcement out.
+pub fn update_announcements(
+    time: Res<Time>,
+    game_state: Res<GameStateResource>,
+    player_info: Res<PlayerInfo>,
+    mut query: Query<(&mut Announcement, &mut Text)>,
+) {
+    let player_id = player_info.get_player_id();
+    let mut messages: Vec<String> = game_state
+        .get_flag_events()
+        .lock()
+        .unwrap()
+        .drain(..)
+        .map(|event| flag_announcement(&event, player_id.as_deref()))
+        .collect();
+    messages.extend(game_state.get_zone_events().lock().unwrap().drain(..).map(|event| zone_announcement(&event)));
+
+    for (mut announcement, mut text) in query.iter_mut() {
+        if let Some(message) = messages.last() {
+            text.sections[0].value = message.clone();
+            announcement.remaining = ANNOUNCEMENT_LIFETIME;
+        }
+        announcement.remaining = (announcement.remaining - time.delta_seconds()).max(0.0);
+        if announcement.remaining == 0.0 && !text.sections[0].value.is_empty() {
+            text.sections[0].value.clear();
+        }
+    }
+}
+
+fn flag_announcement(event: &FlagEvent, player_id: Option<&str>) -> String {
+    let flag = event.flag_team.name();
+    let taker = if event.tank_id.is_some() && event.tank_id.as_deref() == player_id {
+        "You".to_string()
+    } else {
+        format!("{} team", event.flag_team.opponent().name())
+    };
+    match event.kind {
+        FlagEventKind::Pickup => format!("{} took the {} flag!", taker, flag),
+        FlagEventKind::Drop => format!("The {} flag was dropped", flag),
+        FlagEventKind::Capture => format!("{} captured the {} flag!", taker, flag),
+        FlagEventKind::Return => format!("The {} flag was returned", flag),
+    }
+}
+
+fn zone_announcement(event: &ZoneEvent) -> String {
+    match event.kind {
+        ZoneEventKind::Captured => format!("{} team captured {}!", event.team.name(), event.zone),
+        ZoneEventKind::Neutralized => format!("{} team neutralized {}", event.team.name(), event.zone),
+    }
+}
+
+/// Health, respawn countdown or spawn protection notice for the local tank.
+fn player_status(game_state: &GameStateResource, player_info: &PlayerInfo) -> String {
+    let Some(player_id) = player_info.get_player_id() else {
+        return String::new();
+    };
+    let data = game_state.get_data();
+    let state = data.lock().unwrap();
+    let Some(tank) = state.tanks.iter().find(|t| t.id == player_id) else {
+        return String::new();
+    };
+
+    if tank.is_dead {
+        match tank.respawn_time {
+            Some(respawn_time) => {
+                let remaining_ms = respawn_time.saturating_sub(state.server_time);
+                format!("Destroyed! Respawning in {}...", remaining_ms.div_ceil(1000))
+            }
+            None => "Destroyed!".to_string(),
+        }
+    } else if tank.spawn_protected_until.is_some_and(|until| until > state.server_time) {
+        format!("Health: {} | Spawn protection (ends when you fire)", tank.health)
+    } else {
+        format!("Health: {}", tank.health)
+    }
+}
+
+/// Turns hit events that involve the local player into damage numbers and
+/// hit marker flashes.
+pub fn spawn_hit_feedback(
+    mut commands: Commands,
+    game_state: Res<GameStateResource>,
+    player_info: Res<PlayerInfo>,
+    mut marker_query: Query<&mut HitMarker>,
+) {
+    let hits: Vec<_> = game_state.get_hits().lock().unwrap().drain(..).collect();
+    let Some(player_id) = player_info.get_player_id() else {
+        return;
+    };
+
+    for hit in hits {
+        let dealt = hit.attacker_id == player_id;
+        if !dealt && hit.target_id != player_id {
+            continue;
+        }
+
+        let mut label = format!("-{}", hit.damage);
+        match hit.side {
+            HitSide::Front => label.push_str(" front"),
+            HitSide::Rear => label.push_str(" rear"),
+            HitSide::Side => {}
+        }
+        if hit.killed {
+            label.push_str(if dealt { " KILL" } else { " DESTROYED" });
+        }
+        let color = if dealt {
+            Color::srgb(1.0, 0.9, 0.2)
+        } else {
+            Color::srgb(1.0, 0.3, 0.3)
+        };
+
+        commands.spawn((
+            TextBundle::from_section(label, TextStyle { font_size: 20.0, color, ..default() })
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                }),
+            Visibility::Hidden, // until placed on screen
+            DamageNumber {
+                world_position: Vec3::new(hit.position.x, 10.0, hit.position.y),
+                age: 0.0,
+            },
+        ));
+
+        if dealt {
+            for mut marker in marker_query.iter_mut() {
+                marker.remaining = HIT_MARKER_LIFETIME;
+            }
+        }
+    }
+}
+
+/// Floats damage numbers upwards, fades them out and flashes the hit marker.
+pub fn update_hit_feedback(
+    mut commands: Commands,
+    time: Res<Time>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    mut number_query: Query<(Entity, &mut DamageNumber, &mut Style, &mut Text, &mut Visibility), Without<HitMarker>>,
+    mut marker_query: Query<(&mut HitMarker, &mut Visibility), Without<DamageNumber>>,
+) {
+    let dt = time.delta_seconds();
+    let camera = camera_query.get_single().ok();
+
+    for (entity, mut number, mut style, mut text, mut visibility) in number_query.iter_mut() {
+        number.age += dt;
+        if number.age >= DAMAGE_NUMBER_LIFETIME {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let progress = number.age / DAMAGE_NUMBER_LIFETIME;
+        let world_position = number.world_position + Vec3::Y * DAMAGE_NUMBER_RISE * progress;
+        match camera.and_then(|(camera, transform)| camera.world_to_viewport(transform, world_position)) {
+            Some(screen) => {
+                style.left = Val::Px(screen.x);
+                style.top = Val::Px(screen.y);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+        let color = text.sections[0].style.color;
+        text.sections[0].style.color = color.with_alpha(1.0 - progress);
+    }
+
+    for (mut marker, mut visibility) in marker_query.iter_mut() {
+        marker.remaining = (marker.remaining - dt).max(0.0);
+        *visibility = if marker.remaining > 0.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
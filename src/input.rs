@@ -2,17 +2,22 @@ use bevy::prelude::*;
 use std::collections::HashMap;
 use battlexone_shared::*;
 use crate::network::WebSocketSender;
-use crate::game_state::PlayerInfo;
+use crate::game_state::{GameStateResource, PlayerInfo};
+use crate::prediction::PredictionState;
 
 #[derive(Resource)]
 pub struct InputState {
     pub keys: HashMap<String, bool>,
+    pub next_seq: u32,
+    pub tick: u64,
 }
 
 impl InputState {
     pub fn new() -> Self {
         Self {
             keys: HashMap::new(),
+            next_seq: 1,
+            tick: 0,
         }
     }
 
@@ -33,10 +38,6 @@ impl InputState {
         self.keys.insert("Space".to_string(), keyboard_input.pressed(KeyCode::Space));
     }
 
-    pub fn has_input(&self) -> bool {
-        self.keys.values().any(|&pressed| pressed)
-    }
-
     pub fn get_input_bitfield(&self) -> u16 {
         encode_keys(&self.keys.iter().map(|(k, v)| (k.as_str(), *v)).collect())
     }
@@ -49,8 +50,21 @@ pub fn handle_input(
     input_state.update_from_keyboard(&keyboard_input);
 }
 
+/// Keeps `FixedUpdate` at the tick rate of the server we joined, so
+/// `send_input` sends one input per server tick.
+pub fn follow_server_tick_rate(game_state: Res<GameStateResource>, mut time: ResMut<Time<Fixed>>) {
+    let tick_rate = *game_state.get_tick_rate().lock().unwrap();
+    let timestep = std::time::Duration::from_secs_f64(1.0 / tick_rate.max(1) as f64);
+    if time.timestep() != timestep {
+        time.set_timestep(timestep);
+    }
+}
+
+/// Runs on `FixedUpdate` at the server tick rate so every message covers
+/// exactly one simulation tick, regardless of the render frame rate.
 pub fn send_input(
-    input_state: Res<InputState>,
+    mut input_state: ResMut<InputState>,
+    mut prediction: ResMut<PredictionState>,
     ws_sender: Res<WebSocketSender>,
     player_info: Res<PlayerInfo>,
 ) {
@@ -59,18 +73,20 @@ pub fn send_input(
         return;
     }
     
-    // Check if we have any keys pressed
-    if !input_state.has_input() {
-        return; // Don't send empty input
-    }
+    input_state.tick += 1;
     
-    // Send input to server
+    // Send input to server, including empty input so the server consumes one per tick
     if let Some(sender) = ws_sender.sender.lock().unwrap().as_ref() {
-        let input_msg = ClientMessage::Input { 
-            input: input_state.get_input_bitfield() 
+        let seq = input_state.next_seq;
+        let input = input_state.get_input_bitfield();
+        let input_msg = ClientMessage::Input {
+            seq,
+            tick: input_state.tick,
+            input,
         };
-        if let Ok(msg_str) = serde_json::to_string(&input_msg) {
-            let _ = sender.send(msg_str);
+        if sender.send(input_msg).is_ok() {
+            input_state.next_seq += 1;
+            prediction.record_input(seq, input);
         }
     }
 } 
\ No newline at end of file
@@ -0,0 +1,73 @@
+//! Buffering a player's inputs between the network and the simulation. The
+//! client sends one numbered input per tick; the server applies one per
+//! tick in order, and when the next one is late it carries on with the last
+//! so a bit of jitter doesn't make the tank stutter.
+
+use std::collections::VecDeque;
+
+use crate::INPUT_FIRE;
+
+/// Inputs beyond this many ticks ahead are dropped (oldest first) so a
+/// client flooding input cannot build up an ever-growing backlog
+pub const MAX_BUFFERED_INPUTS: usize = 8;
+/// Ticks the last input is repeated for while waiting on the next one, so a
+/// client that stops sending doesn't drive on forever
+pub const MAX_REPEATED_INPUTS: u32 = 8;
+
+/// Inputs received from one player, consumed one per simulation tick.
+#[derive(Debug, Default)]
+pub struct InputQueue {
+    pending: VecDeque<(u32, u16)>,
+    last_received_seq: Option<u32>,
+    last_processed_seq: u32,
+    // The last input applied and how many ticks it has been repeated
+    last_input: Option<u16>,
+    repeats: u32,
+}
+
+impl InputQueue {
+    /// Queues input `seq`, ignoring duplicates and anything older than an
+    /// input already received.
+    pub fn push(&mut self, seq: u32, input: u16) {
+        if self.last_received_seq.is_some_and(|last| seq <= last) {
+            return;
+        }
+        self.last_received_seq = Some(seq);
+
+        self.pending.push_back((seq, input));
+        while self.pending.len() > MAX_BUFFERED_INPUTS {
+            self.pending.pop_front();
+        }
+    }
+
+    /// The input to apply this tick: the next one queued, or else the last
+    /// one again without its fire bit, so a lost tap isn't turned into
+    /// a stream of shots.
+    pub fn pop(&mut self) -> Option<u16> {
+        if let Some((seq, input)) = self.pending.pop_front() {
+            self.last_processed_seq = seq;
+            self.last_input = Some(input);
+            self.repeats = 0;
+            return Some(input);
+        }
+        if self.repeats >= MAX_REPEATED_INPUTS {
+            return None;
+        }
+        self.repeats += 1;
+        self.last_input.map(|input| input & !INPUT_FIRE)
+    }
+
+    /// Sequence number of the last input taken off the queue, which the
+    /// client reconciles its prediction against.
+    pub fn last_processed_seq(&self) -> u32 {
+        self.last_processed_seq
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
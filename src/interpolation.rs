@@ -0,0 +1,204 @@
+//! Smoothing remote entities between snapshots. The client draws other
+//! tanks and bullets a little in the past, interpolated between the two
+//! snapshots that bracket that moment, and briefly extrapolates when
+//! snapshots stop arriving. Times are in milliseconds; `local_ms` is the
+//! client's own clock and `server_time` the server's.
+
+use std::collections::{HashMap, VecDeque};
+use std::f32::consts::{PI, TAU};
+use std::time::Instant;
+
+use crate::{Bullet, GameState, Position, Tank};
+
+/// How far behind the server remote entities are drawn. Two snapshot
+/// intervals at 20 Hz, so one late or lost packet still leaves a pair of
+/// snapshots to interpolate between.
+pub const INTERPOLATION_DELAY_MS: f64 = 100.0;
+/// Longest we keep extrapolating past the newest snapshot before freezing.
+pub const MAX_EXTRAPOLATION_MS: f64 = 250.0;
+pub const MAX_BUFFERED_SNAPSHOTS: usize = 32;
+/// Weight of each new sample in the smoothed server clock offset.
+pub const CLOCK_SMOOTHING: f64 = 0.1;
+
+/// Jitter buffer of recent snapshots. Remote tanks and bullets are rendered
+/// at a point slightly in the past, interpolated between the two snapshots
+/// that bracket it.
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<GameState>,
+    started_at: Instant,
+    clock_offset_ms: Option<f64>,
+}
+
+impl Default for SnapshotBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SnapshotBuffer {
+    pub fn new() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            started_at: Instant::now(),
+            clock_offset_ms: None,
+        }
+    }
+
+    pub fn push(&mut self, state: GameState) {
+        self.push_at(state, self.local_ms());
+    }
+
+    /// Adds a snapshot that arrived at `local_ms`.
+    pub fn push_at(&mut self, state: GameState, local_ms: f64) {
+        // Track the offset between our clock and the server's, smoothed so
+        // network jitter does not make the render time jump around
+        let offset = state.server_time as f64 - local_ms;
+        self.clock_offset_ms = Some(match self.clock_offset_ms {
+            Some(previous) => previous + (offset - previous) * CLOCK_SMOOTHING,
+            None => offset,
+        });
+
+        // Drop snapshots that arrive out of order
+        if self.snapshots.back().is_some_and(|last| state.tick <= last.tick) {
+            return;
+        }
+
+        self.snapshots.push_back(state);
+        while self.snapshots.len() > MAX_BUFFERED_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Forgets every snapshot and the server clock, for a new connection:
+    /// a restarted server counts ticks and time from zero again, which
+    /// `push` would otherwise drop as stale.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.clock_offset_ms = None;
+    }
+
+    /// Smoothed server time minus local time, once a snapshot has arrived.
+    pub fn clock_offset_ms(&self) -> Option<f64> {
+        self.clock_offset_ms
+    }
+
+    fn local_ms(&self) -> f64 {
+        self.started_at.elapsed().as_secs_f64() * 1000.0
+    }
+
+    /// Tanks and bullets as they should be drawn right now.
+    pub fn sample(&self) -> Option<(Vec<Tank>, Vec<Bullet>)> {
+        self.sample_at(self.local_ms())
+    }
+
+    /// Tanks and bullets as they should be drawn at `local_ms`.
+    pub fn sample_at(&self, local_ms: f64) -> Option<(Vec<Tank>, Vec<Bullet>)> {
+        let render_time = local_ms + self.clock_offset_ms? - INTERPOLATION_DELAY_MS;
+        self.sample_server_time(render_time)
+    }
+
+    fn sample_server_time(&self, render_time: f64) -> Option<(Vec<Tank>, Vec<Bullet>)> {
+        let newest = self.snapshots.back()?;
+
+        if render_time >= newest.server_time as f64 {
+            // Snapshots stopped arriving in time: carry on from the newest
+            // one for a bounded amount of time, then hold still
+            let previous = self.snapshots.iter().rev().nth(1);
+            let ahead_ms = (render_time - newest.server_time as f64).min(MAX_EXTRAPOLATION_MS);
+            return Some(extrapolate(previous, newest, ahead_ms as f32));
+        }
+
+        let next_index = self
+            .snapshots
+            .iter()
+            .position(|s| s.server_time as f64 > render_time)?;
+        if next_index == 0 {
+            let oldest = &self.snapshots[0];
+            return Some((oldest.tanks.clone(), oldest.bullets.clone()));
+        }
+
+        let from = &self.snapshots[next_index - 1];
+        let to = &self.snapshots[next_index];
+        let span = (to.server_time - from.server_time).max(1) as f64;
+        let t = ((render_time - from.server_time as f64) / span) as f32;
+        Some(interpolate(from, to, t))
+    }
+}
+
+fn interpolate(from: &GameState, to: &GameState, t: f32) -> (Vec<Tank>, Vec<Bullet>) {
+    let from_tanks: HashMap<&str, &Tank> = from.tanks.iter().map(|t| (t.id.as_str(), t)).collect();
+    let from_bullets: HashMap<&str, &Bullet> = from.bullets.iter().map(|b| (b.id.as_str(), b)).collect();
+
+    let tanks = to
+        .tanks
+        .iter()
+        .map(|tank| match from_tanks.get(tank.id.as_str()) {
+            // Don't slide a tank across the map when it respawns
+            Some(previous) if !previous.is_dead && !tank.is_dead => Tank {
+                position: lerp_position(&previous.position, &tank.position, t),
+                rotation: lerp_angle(previous.rotation, tank.rotation, t),
+                turret_rotation: lerp_angle(previous.turret_rotation, tank.turret_rotation, t),
+                ..tank.clone()
+            },
+            _ => tank.clone(),
+        })
+        .collect();
+
+    let bullets = to
+        .bullets
+        .iter()
+        .map(|bullet| match from_bullets.get(bullet.id.as_str()) {
+            Some(previous) => Bullet {
+                position: lerp_position(&previous.position, &bullet.position, t),
+                ..bullet.clone()
+            },
+            None => bullet.clone(),
+        })
+        .collect();
+
+    (tanks, bullets)
+}
+
+fn extrapolate(previous: Option<&GameState>, newest: &GameState, ahead_ms: f32) -> (Vec<Tank>, Vec<Bullet>) {
+    let mut tanks = newest.tanks.clone();
+    if let Some(previous) = previous {
+        let span_ms = newest.server_time.saturating_sub(previous.server_time).max(1) as f32;
+        let scale = ahead_ms / span_ms;
+        for tank in tanks.iter_mut().filter(|t| !t.is_dead) {
+            if let Some(before) = previous.tanks.iter().find(|t| t.id == tank.id && !t.is_dead) {
+                tank.position.x += (tank.position.x - before.position.x) * scale;
+                tank.position.y += (tank.position.y - before.position.y) * scale;
+                tank.rotation += angle_delta(before.rotation, tank.rotation) * scale;
+                tank.turret_rotation += angle_delta(before.turret_rotation, tank.turret_rotation) * scale;
+            }
+        }
+    }
+
+    // Bullets fly in a straight line, so their own velocity is exact
+    let ahead_secs = ahead_ms / 1000.0;
+    let mut bullets = newest.bullets.clone();
+    for bullet in bullets.iter_mut() {
+        bullet.position.x += bullet.velocity.x * ahead_secs;
+        bullet.position.y += bullet.velocity.y * ahead_secs;
+    }
+
+    (tanks, bullets)
+}
+
+pub fn lerp_position(from: &Position, to: &Position, t: f32) -> Position {
+    Position {
+        x: from.x + (to.x - from.x) * t,
+        y: from.y + (to.y - from.y) * t,
+    }
+}
+
+/// Signed shortest rotation from `from` to `to`, in `[-PI, PI)`.
+pub fn angle_delta(from: f32, to: f32) -> f32 {
+    (to - from + PI).rem_euclid(TAU) - PI
+}
+
+/// Interpolates between two angles along the shortest arc, so a turret going
+/// from 350° to 10° turns 20° rather than spinning back through 180°.
+pub fn lerp_angle(from: f32, to: f32, t: f32) -> f32 {
+    from + angle_delta(from, to) * t
+}
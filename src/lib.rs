@@ -1,28 +1,74 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod auth;
+pub mod bullets;
+pub mod client_prediction;
+pub mod collision;
+pub mod damage;
+pub mod delta;
+pub mod grid;
+pub mod input_queue;
+pub mod interpolation;
+pub mod lifecycle;
+pub mod map;
+pub mod movement;
+pub mod nav;
+pub mod npc;
+pub mod npcs;
+pub mod objectives;
+pub mod players;
+pub mod profiles;
+pub mod rating;
+pub mod reconnect;
+pub mod respawn;
+pub mod rewind;
+pub mod server_config;
+pub mod sweep;
+pub mod teams;
+pub mod wire;
+pub mod zones;
+
+use damage::HitSide;
+use delta::GameStateDelta;
+use map::Map;
+
+/// Version of the message formats, input bit layout and `wire` encoding.
+/// Bumped on any change to them, including a `wire::WIRE_VERSION` bump; the
+/// server rejects joins from any other version.
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// Fixed simulation rate (ticks per second) the server runs at by default and
+/// clients sample input at. Each `ClientMessage::Input` covers exactly one tick.
+pub const SIMULATION_TICK_RATE: u32 = 60;
+
+/// Health a tank spawns with
+pub const TANK_MAX_HEALTH: i32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Position {
     pub x: f32,
     pub y: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Velocity {
     pub x: f32,
     pub y: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Bullet {
     pub id: String,
     pub position: Position,
     pub velocity: Velocity,
     pub owner_id: String,
     pub created_at: u64,
+    /// Health removed by a side hit; see `damage` for armour
+    pub damage: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tank {
     pub id: String,
     pub position: Position,
@@ -31,17 +77,165 @@ pub struct Tank {
     pub is_player: bool,
     pub health: i32,
     pub is_dead: bool,
+    /// Server time (see `GameState::server_time`) at which a dead tank respawns
     pub respawn_time: Option<u64>,
     pub last_fire_time: u64,
+    /// Server time until which a freshly spawned tank can't be damaged
+    #[serde(default)]
+    pub spawn_protected_until: Option<u64>,
+    /// `None` in free-for-all, where every other tank is an enemy
+    #[serde(default)]
+    pub team: Option<Team>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Team {
+    Red,
+    Blue,
+}
+
+impl Team {
+    pub const ALL: [Team; 2] = [Team::Red, Team::Blue];
+
+    pub fn opponent(self) -> Team {
+        match self {
+            Team::Red => Team::Blue,
+            Team::Blue => Team::Red,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Team::Red => "Red",
+            Team::Blue => "Blue",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    #[default]
+    FreeForAll,
+    TeamDeathmatch,
+    CaptureTheFlag,
+    /// Teams score for every second they hold the single central zone
+    KingOfTheHill,
+    /// Teams score for every second they hold each of three zones
+    Domination,
+}
+
+impl GameMode {
+    pub fn has_teams(self) -> bool {
+        self != GameMode::FreeForAll
+    }
+
+    pub fn has_zones(self) -> bool {
+        matches!(self, GameMode::KingOfTheHill | GameMode::Domination)
+    }
+}
+
+/// A circular area teams fight over in king of the hill and domination.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptureZone {
+    pub name: String,
+    pub position: Position,
+    pub radius: f32,
+    /// Team holding the zone and scoring from it
+    pub owner: Option<Team>,
+    /// Team `progress` counts towards; the owner while it holds the zone
+    pub capturing_team: Option<Team>,
+    /// 0.0 (neutral) to 1.0 (captured)
+    pub progress: f32,
+    /// Both teams are inside, so progress is frozen
+    pub contested: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneEventKind {
+    /// The team took the zone
+    Captured,
+    /// The team wore down the other team's hold and the zone is neutral
+    Neutralized,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneEvent {
+    pub kind: ZoneEventKind,
+    pub zone: String,
+    pub team: Team,
+}
+
+/// A capture-the-flag flag. While carried it moves with its carrier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flag {
+    /// The team defending this flag
+    pub team: Team,
+    /// Where the flag starts and where enemy flags are captured
+    pub base: Position,
+    pub position: Position,
+    pub carrier_id: Option<String>,
+    /// Server time a dropped flag was left at, so it can return on its own
+    pub dropped_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagEventKind {
+    /// An enemy took the flag
+    Pickup,
+    /// The carrier died or left and the flag lies where they were
+    Drop,
+    /// The carrier brought the flag to their own base
+    Capture,
+    /// A defender touched the dropped flag, or it was left too long
+    Return,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlagEvent {
+    pub kind: FlagEventKind,
+    /// Team the flag belongs to
+    pub flag_team: Team,
+    /// Tank that picked up, dropped, captured or returned it
+    pub tank_id: Option<String>,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamScore {
+    pub team: Team,
+    /// Kills in team deathmatch, captures in capture the flag, seconds of
+    /// zone control in king of the hill and domination
+    pub score: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct GameState {
+    /// Server simulation tick this snapshot was taken after
+    #[serde(default)]
+    pub tick: u64,
+    /// Milliseconds since the server started, used by clients to place the
+    /// snapshot on a timeline for interpolation
+    #[serde(default)]
+    pub server_time: u64,
     pub tanks: Vec<Tank>,
     pub bullets: Vec<Bullet>,
+    /// Sequence number of the last input the server applied, keyed by player id
+    #[serde(default)]
+    pub last_processed_input: HashMap<String, u32>,
+    /// Capture zones in king of the hill and domination, empty otherwise
+    #[serde(default)]
+    pub zones: Vec<CaptureZone>,
 }
 
 // Input handling
+
+/// Bit of an input bitfield that fires the main gun (Space)
+pub const INPUT_FIRE: u16 = 1 << 8;
+
 pub struct PlayerInput {
     pub w: bool,
     pub a: bool,
@@ -54,16 +248,183 @@ pub struct PlayerInput {
     pub space: bool,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientKind {
+    Native,
+    #[default]
+    Browser,
+    Bot,
+}
+
+/// Optional protocol capabilities, negotiated in `Join`/`Joined`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Game states in the compact binary encoding from `wire`
+    BinaryEncoding,
+    /// Game states as deltas against acknowledged snapshots
+    DeltaSnapshots,
+    /// A feature this build doesn't know about, e.g. from a newer client
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    UnsupportedProtocolVersion,
+    /// The same user joined again from another connection, which took over
+    SessionTakenOver,
+    /// A private server turned down the key or password
+    Unauthorized,
+    /// Every player slot is taken; worth trying again later
+    ServerFull,
+}
+
+/// A bullet struck a tank. Sent to every client as it happens so they can
+/// show hit markers and damage numbers ahead of the next snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HitEvent {
+    /// Simulation tick the hit happened on
+    pub tick: u64,
+    pub attacker_id: String,
+    pub target_id: String,
+    /// Health removed after armour
+    pub damage: i32,
+    pub side: HitSide,
+    /// Where the bullet struck
+    pub position: Position,
+    /// Target's health after the hit
+    pub health: i32,
+    pub killed: bool,
+}
+
+/// A player's statistics for the current match, recorded by the server.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub kills: u32,
+    pub deaths: u32,
+    pub shots_fired: u32,
+    /// Shots that struck a tank, whether or not they did damage
+    pub hits: u32,
+}
+
+impl PlayerStats {
+    /// Fraction of shots fired that hit, 0 before the first shot.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            self.hits as f32 / self.shots_fired as f32
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreboardEntry {
+    pub player_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub team: Option<Team>,
+    #[serde(flatten)]
+    pub stats: PlayerStats,
+    /// `stats.accuracy()`, precomputed for clients
+    pub accuracy: f32,
+}
+
+/// One row of the skill leaderboard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    /// 1 for the top player
+    pub rank: u32,
+    pub name: String,
+    pub rating: f64,
+    pub rated_matches: u64,
+    pub kills: u64,
+    pub deaths: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchPhase {
+    /// Waiting for enough players; free play
+    Lobby,
+    /// Free play before the match; scores are reset when it ends
+    Warmup,
+    InProgress,
+    /// The match is over and play is frozen while results are shown
+    Results,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchState {
+    #[serde(default)]
+    pub mode: GameMode,
+    pub phase: MatchPhase,
+    /// Counts up from 1 with each match the server runs
+    pub match_number: u32,
+    /// Server time the current phase ends at, if it is timed
+    pub phase_ends_at: Option<u64>,
+    pub score_limit: u32,
+    pub time_limit_ms: u64,
+    /// Name of the top player or team once the match is over; `None` for a draw
+    pub winner: Option<String>,
+    /// Final standings, best first; only filled in during `Results`
+    pub results: Vec<ScoreboardEntry>,
+    /// One entry per team in team modes, empty in free-for-all
+    #[serde(default)]
+    pub team_scores: Vec<TeamScore>,
+    /// Capture-the-flag flags, empty in other modes
+    #[serde(default)]
+    pub flags: Vec<Flag>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ClientMessage {
     #[serde(rename = "join")]
     Join { 
         name: String,
+        /// Informational; the identity is resumed from `token`
         user_id: Option<String>,
+        /// Session token from an earlier `Joined`
+        #[serde(default)]
+        token: Option<String>,
+        /// Shared key or account password, for private servers
+        #[serde(default)]
+        password: Option<String>,
+        /// Clients from before the handshake existed send no version (0)
+        #[serde(default)]
+        protocol_version: u32,
+        #[serde(default)]
+        client_kind: ClientKind,
+        #[serde(default)]
+        features: Vec<Feature>,
     },
+    /// One tick worth of input. `seq` increases by one per message and `tick`
+    /// is the client simulation tick the keys were sampled on.
     #[serde(rename = "input")]
-    Input { input: u16 },
+    Input {
+        seq: u32,
+        tick: u64,
+        input: u16,
+    },
+    /// The client has applied the snapshot taken at `tick`; later deltas may
+    /// be computed against it.
+    #[serde(rename = "ack")]
+    Ack { tick: u64 },
+    /// The client cannot apply a delta (e.g. it no longer has the base
+    /// snapshot) and needs a full game state.
+    #[serde(rename = "request_keyframe")]
+    RequestKeyframe,
+    /// Preferences to remember in the player's profile, merged over the
+    /// ones already saved
+    #[serde(rename = "update_settings")]
+    UpdateSettings { settings: BTreeMap<String, String> },
+    /// Asks for the top of the skill leaderboard
+    #[serde(rename = "request_leaderboard")]
+    RequestLeaderboard,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -73,9 +434,53 @@ pub enum ServerMessage {
     Joined { 
         player_id: String,
         user_id: String,
+        /// Present this in the next `Join` to come back as the same player
+        #[serde(default)]
+        token: String,
+        /// Preferences saved in the player's profile
+        #[serde(default)]
+        settings: BTreeMap<String, String>,
+        protocol_version: u32,
+        /// The subset of the client's features the server will use
+        features: Vec<Feature>,
+        /// The arena being played on
+        #[serde(default)]
+        map: Map,
+        /// Simulation ticks per second; clients send one input per tick
+        /// and predict with the same tick length
+        tick_rate: u32,
+    },
+    /// The join was refused; the server closes the connection after this.
+    #[serde(rename = "rejected")]
+    Rejected {
+        reason: RejectReason,
+        message: String,
+        protocol_version: u32,
     },
+    /// Full snapshot (keyframe)
     #[serde(rename = "game_state")]
     GameState(GameState),
+    /// Changes relative to a snapshot the client acknowledged
+    #[serde(rename = "game_state_delta")]
+    GameStateDelta(GameStateDelta),
+    #[serde(rename = "hit")]
+    Hit(HitEvent),
+    /// Every connected player's statistics, best first; sent periodically
+    #[serde(rename = "scoreboard")]
+    Scoreboard { entries: Vec<ScoreboardEntry> },
+    /// Best rated players first; the answer to `RequestLeaderboard`
+    #[serde(rename = "leaderboard")]
+    Leaderboard { entries: Vec<LeaderboardEntry> },
+    /// Sent on every phase change and periodically in between
+    #[serde(rename = "match_state")]
+    MatchState(MatchState),
+    /// A capture-the-flag flag changed hands; followed by a `MatchState`
+    /// with the flags' new positions
+    #[serde(rename = "flag")]
+    Flag(FlagEvent),
+    /// A capture zone changed hands; the zone itself is in `GameState`
+    #[serde(rename = "zone")]
+    Zone(ZoneEvent),
 }
 
 impl ClientMessage {
@@ -89,7 +494,7 @@ impl ClientMessage {
             arrow_right: (input & (1 << 5)) != 0,
             arrow_up: (input & (1 << 6)) != 0,
             arrow_down: (input & (1 << 7)) != 0,
-            space: (input & (1 << 8)) != 0,
+            space: (input & INPUT_FIRE) != 0,
         }
     }
 }
@@ -105,7 +510,7 @@ pub fn encode_keys(keys: &HashMap<&str, bool>) -> u16 {
     if *keys.get("ArrowRight").unwrap_or(&false) { bitfield |= 1 << 5; }
     if *keys.get("ArrowUp").unwrap_or(&false) { bitfield |= 1 << 6; }
     if *keys.get("ArrowDown").unwrap_or(&false) { bitfield |= 1 << 7; }
-    if *keys.get("Space").unwrap_or(&false) { bitfield |= 1 << 8; }
+    if *keys.get("Space").unwrap_or(&false) { bitfield |= INPUT_FIRE; }
     
     bitfield
 } 
\ No newline at end of file
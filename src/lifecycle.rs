@@ -0,0 +1,175 @@
+//! Match state machine: lobby → warmup → in progress → results → next match.
+//!
+//! The server feeds it the time and the current standings every tick and
+//! reacts to the phase changes it reports; clients only ever see the
+//! resulting `MatchState`.
+
+use crate::teams::{new_flags, FlagBases};
+use crate::{Flag, GameMode, MatchPhase, MatchState, ScoreboardEntry, Team, TeamScore};
+
+#[derive(Debug, Clone)]
+pub struct MatchSettings {
+    pub mode: GameMode,
+    /// Score that wins a match: a player's kills in free-for-all, a team's
+    /// kills in team deathmatch and a team's captures in capture the flag
+    pub score_limit: u32,
+    pub time_limit_ms: u64,
+    pub warmup_ms: u64,
+    /// How long the results screen stays up before the next warmup
+    pub results_ms: u64,
+    /// Players needed before leaving the lobby
+    pub min_players: usize,
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::FreeForAll,
+            score_limit: 10,
+            time_limit_ms: 5 * 60 * 1000,
+            warmup_ms: 10_000,
+            results_ms: 10_000,
+            min_players: 1,
+        }
+    }
+}
+
+pub struct MatchLifecycle {
+    settings: MatchSettings,
+    state: MatchState,
+    flag_bases: FlagBases,
+}
+
+impl MatchLifecycle {
+    pub fn new(settings: MatchSettings) -> Self {
+        let state = MatchState {
+            mode: settings.mode,
+            phase: MatchPhase::Lobby,
+            match_number: 1,
+            phase_ends_at: None,
+            score_limit: settings.score_limit,
+            time_limit_ms: settings.time_limit_ms,
+            winner: None,
+            results: Vec::new(),
+            team_scores: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut lifecycle = Self { settings, state, flag_bases: FlagBases::default() };
+        lifecycle.reset_objectives();
+        lifecycle
+    }
+
+    /// The lifecycle with capture-the-flag flags kept at `bases`.
+    pub fn with_flag_bases(mut self, bases: FlagBases) -> Self {
+        self.flag_bases = bases;
+        self.reset_objectives();
+        self
+    }
+
+    pub fn state(&self) -> &MatchState {
+        &self.state
+    }
+
+    /// Whether tanks may move, fire and take damage.
+    pub fn is_live(&self) -> bool {
+        self.state.phase != MatchPhase::Results
+    }
+
+    /// Advances the state machine to server time `now` given how many
+    /// players are connected and the most kills any of them has. Returns the
+    /// phase entered, if it changed.
+    pub fn update(&mut self, now: u64, players: usize, leader_kills: u32) -> Option<MatchPhase> {
+        let enough_players = players >= self.settings.min_players.max(1);
+        let phase_over = self.state.phase_ends_at.is_some_and(|ends_at| now >= ends_at);
+        let leader_score = if self.settings.mode.has_teams() {
+            self.state.team_scores.iter().map(|s| s.score).max().unwrap_or(0)
+        } else {
+            leader_kills
+        };
+
+        let next = match self.state.phase {
+            MatchPhase::Lobby if enough_players => MatchPhase::Warmup,
+            MatchPhase::Warmup | MatchPhase::InProgress if players == 0 => MatchPhase::Lobby,
+            MatchPhase::Warmup if phase_over => MatchPhase::InProgress,
+            MatchPhase::InProgress if phase_over || leader_score >= self.settings.score_limit => {
+                MatchPhase::Results
+            }
+            MatchPhase::Results if phase_over && enough_players => MatchPhase::Warmup,
+            MatchPhase::Results if phase_over => MatchPhase::Lobby,
+            _ => return None,
+        };
+
+        self.enter(next, now);
+        Some(next)
+    }
+
+    /// Records the final standings, best first, once the match has ended.
+    /// In team modes the team with the higher score wins.
+    pub fn record_results(&mut self, results: Vec<ScoreboardEntry>) {
+        self.state.winner = if self.settings.mode.has_teams() {
+            self.leading_team().map(|team| format!("{} team", team.name()))
+        } else {
+            results.first().map(|entry| entry.name.clone())
+        };
+        self.state.results = results;
+    }
+
+    /// Adds to a team's score; only counts while the match is in progress.
+    pub fn add_team_score(&mut self, team: Team, points: u32) {
+        if self.state.phase != MatchPhase::InProgress {
+            return;
+        }
+        if let Some(entry) = self.state.team_scores.iter_mut().find(|s| s.team == team) {
+            entry.score += points;
+        }
+    }
+
+    pub fn flags_mut(&mut self) -> &mut Vec<Flag> {
+        &mut self.state.flags
+    }
+
+    /// The team ahead on score, `None` on a draw or outside team modes.
+    fn leading_team(&self) -> Option<Team> {
+        let mut scores = self.state.team_scores.clone();
+        scores.sort_by_key(|s| std::cmp::Reverse(s.score));
+        match scores.as_slice() {
+            [first, second, ..] if first.score == second.score => None,
+            [first, ..] => Some(first.team),
+            [] => None,
+        }
+    }
+
+    /// Zeroes team scores and puts flags back at their bases.
+    fn reset_objectives(&mut self) {
+        self.state.team_scores = if self.settings.mode.has_teams() {
+            Team::ALL.iter().map(|&team| TeamScore { team, score: 0 }).collect()
+        } else {
+            Vec::new()
+        };
+        self.state.flags = if self.settings.mode == GameMode::CaptureTheFlag {
+            new_flags(&self.flag_bases)
+        } else {
+            Vec::new()
+        };
+    }
+
+    fn enter(&mut self, phase: MatchPhase, now: u64) {
+        if self.state.phase == MatchPhase::Results {
+            self.state.match_number += 1;
+            self.state.winner = None;
+            self.state.results.clear();
+        }
+
+        if matches!(phase, MatchPhase::Warmup | MatchPhase::InProgress) {
+            self.reset_objectives();
+        }
+
+        self.state.phase = phase;
+        self.state.phase_ends_at = match phase {
+            MatchPhase::Lobby => None,
+            MatchPhase::Warmup => Some(now + self.settings.warmup_ms),
+            MatchPhase::InProgress => Some(now + self.settings.time_limit_ms),
+            MatchPhase::Results => Some(now + self.settings.results_ms),
+        };
+    }
+}
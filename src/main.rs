@@ -4,6 +4,9 @@ mod tank_model;
 mod network;
 mod game_state;
 mod input;
+mod hud;
+mod scoreboard;
+mod prediction;
 mod rendering;
 mod systems;
 mod config;
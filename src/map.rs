@@ -0,0 +1,346 @@
+//! Arena layout: bounds, obstacles, spawn points and, optionally, where the
+//! objectives go.
+//!
+//! The server loads a map from a JSON file at startup and sends it to every
+//! client on join. Tanks are treated as circles of `TANK_RADIUS` that get
+//! pushed out of walls and boxes after each move, which lets them slide
+//! along an obstacle instead of sticking to it. Bullets are points and stop
+//! as soon as their path enters one.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::sweep::{segment_box, segment_capsule};
+use crate::teams::FlagBases;
+use crate::zones::{zones_for_mode, ZoneSpot};
+use crate::{CaptureZone, GameMode, Position, Team};
+
+/// Collision radius of a tank hull
+pub const TANK_RADIUS: f32 = 15.0;
+/// Half the side of the square arena when a map doesn't say otherwise
+pub const DEFAULT_BOUNDS: f32 = 1000.0;
+pub const DEFAULT_WALL_THICKNESS: f32 = 20.0;
+
+// Resolving one obstacle can push a tank into another at a corner
+const RESOLVE_PASSES: usize = 3;
+
+fn default_wall_thickness() -> f32 {
+    DEFAULT_WALL_THICKNESS
+}
+
+/// A straight wall between two points.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Wall {
+    pub from: Position,
+    pub to: Position,
+    #[serde(default = "default_wall_thickness")]
+    pub thickness: f32,
+}
+
+/// An axis-aligned box.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapBox {
+    pub min: Position,
+    pub max: Position,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Map {
+    pub name: String,
+    /// The arena spans -bounds..bounds on both axes
+    pub bounds: f32,
+    #[serde(default)]
+    pub walls: Vec<Wall>,
+    #[serde(default)]
+    pub boxes: Vec<MapBox>,
+    /// Where tanks can spawn; the server picks its own when empty
+    #[serde(default)]
+    pub spawn_points: Vec<Position>,
+    /// Capture-the-flag bases; either side of the centre when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag_bases: Option<FlagBases>,
+    /// Zones for king of the hill and domination; the mode's standard ones
+    /// when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zones: Option<Vec<ZoneSpot>>,
+}
+
+impl Default for Map {
+    /// An empty arena, used until a client has been sent the real map.
+    fn default() -> Self {
+        Self {
+            name: "Open arena".to_string(),
+            bounds: DEFAULT_BOUNDS,
+            walls: Vec::new(),
+            boxes: Vec::new(),
+            spawn_points: Vec::new(),
+            flag_bases: None,
+            zones: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapError {
+    Io(String),
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(e) => write!(f, "could not read map: {}", e),
+            MapError::Parse(e) => write!(f, "invalid map JSON: {}", e),
+            MapError::Invalid(e) => write!(f, "invalid map: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+/// Closest point to `p` on the segment from `a` to `b`.
+fn closest_on_segment(p: &Position, a: &Position, b: &Position) -> Position {
+    let (abx, aby) = (b.x - a.x, b.y - a.y);
+    let length_sq = abx * abx + aby * aby;
+    let t = if length_sq > 0.0 {
+        (((p.x - a.x) * abx + (p.y - a.y) * aby) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    Position {
+        x: a.x + abx * t,
+        y: a.y + aby * t,
+    }
+}
+
+/// Whether both of `p`'s coordinates are numbers rather than NaN or infinite.
+fn finite(p: &Position) -> bool {
+    p.x.is_finite() && p.y.is_finite()
+}
+
+impl Wall {
+    /// Direction and depth to push a circle of `radius` at `p` out of the
+    /// wall, if it overlaps.
+    fn penetration(&self, p: &Position, radius: f32) -> Option<(f32, f32, f32)> {
+        let closest = closest_on_segment(p, &self.from, &self.to);
+        let (dx, dy) = (p.x - closest.x, p.y - closest.y);
+        let distance = (dx * dx + dy * dy).sqrt();
+        let reach = self.thickness / 2.0 + radius;
+        if distance >= reach {
+            return None;
+        }
+        if distance > 0.0 {
+            return Some((dx / distance, dy / distance, reach - distance));
+        }
+        // Dead on the centre line: push out sideways
+        let (ax, ay) = (self.to.x - self.from.x, self.to.y - self.from.y);
+        let length = (ax * ax + ay * ay).sqrt().max(f32::EPSILON);
+        Some((-ay / length, ax / length, reach))
+    }
+}
+
+impl MapBox {
+    fn contains(&self, p: &Position) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+    }
+
+    fn penetration(&self, p: &Position, radius: f32) -> Option<(f32, f32, f32)> {
+        if self.contains(p) {
+            // Out through the nearest face
+            let faces = [
+                (-1.0, 0.0, p.x - self.min.x),
+                (1.0, 0.0, self.max.x - p.x),
+                (0.0, -1.0, p.y - self.min.y),
+                (0.0, 1.0, self.max.y - p.y),
+            ];
+            let (nx, ny, depth) = faces.into_iter().min_by(|a, b| a.2.total_cmp(&b.2)).unwrap();
+            return Some((nx, ny, depth + radius));
+        }
+        let (dx, dy) = (
+            p.x - p.x.clamp(self.min.x, self.max.x),
+            p.y - p.y.clamp(self.min.y, self.max.y),
+        );
+        let distance = (dx * dx + dy * dy).sqrt();
+        (distance < radius).then(|| (dx / distance, dy / distance, radius - distance))
+    }
+}
+
+impl Map {
+    /// Parses and validates a map.
+    pub fn from_json(json: &str) -> Result<Self, MapError> {
+        let map: Map = serde_json::from_str(json).map_err(|e| MapError::Parse(e.to_string()))?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        let json = std::fs::read_to_string(path).map_err(|e| MapError::Io(e.to_string()))?;
+        Self::from_json(&json)
+    }
+
+    /// The map resized to `bounds`, as long as its spawn points still fit.
+    pub fn with_bounds(mut self, bounds: f32) -> Result<Self, MapError> {
+        self.bounds = bounds;
+        self.validate()?;
+        Ok(self)
+    }
+
+    fn validate(&self) -> Result<(), MapError> {
+        if !self.bounds.is_finite() || self.bounds <= TANK_RADIUS {
+            return Err(MapError::Invalid(format!("bounds must be more than {}", TANK_RADIUS)));
+        }
+        if let Some(i) = self.walls.iter().position(|w| !finite(&w.from) || !finite(&w.to)) {
+            return Err(MapError::Invalid(format!("wall {} needs finite coordinates", i)));
+        }
+        if let Some(i) = self.walls.iter().position(|w| !w.thickness.is_finite() || w.thickness <= 0.0) {
+            return Err(MapError::Invalid(format!("wall {} needs a positive thickness", i)));
+        }
+        if let Some(i) = self.boxes.iter().position(|b| !finite(&b.min) || !finite(&b.max)) {
+            return Err(MapError::Invalid(format!("box {} needs finite coordinates", i)));
+        }
+        if let Some(i) = self.boxes.iter().position(|b| b.min.x >= b.max.x || b.min.y >= b.max.y) {
+            return Err(MapError::Invalid(format!("box {} has min not below max", i)));
+        }
+        for (i, point) in self.spawn_points.iter().enumerate() {
+            if !finite(point) {
+                return Err(MapError::Invalid(format!("spawn point {} needs finite coordinates", i)));
+            }
+            if !self.reachable(point) {
+                return Err(MapError::Invalid(format!("spawn point {} is blocked or out of bounds", i)));
+            }
+        }
+        if let Some(bases) = &self.flag_bases {
+            for team in Team::ALL {
+                if !finite(bases.of(team)) {
+                    return Err(MapError::Invalid(format!("{} flag base needs finite coordinates", team.name())));
+                }
+                if !self.reachable(bases.of(team)) {
+                    return Err(MapError::Invalid(format!("{} flag base is blocked or out of bounds", team.name())));
+                }
+            }
+            if bases.red == bases.blue {
+                return Err(MapError::Invalid("flag bases are in the same place".to_string()));
+            }
+        }
+        if let Some(zones) = &self.zones {
+            if zones.is_empty() {
+                return Err(MapError::Invalid("zones needs at least one zone, or leave it out".to_string()));
+            }
+            for (i, zone) in zones.iter().enumerate() {
+                if !zone.radius.is_finite() || zone.radius <= 0.0 {
+                    return Err(MapError::Invalid(format!("zone {} needs a positive radius", i)));
+                }
+                if !finite(&zone.position) {
+                    return Err(MapError::Invalid(format!("zone {} needs finite coordinates", i)));
+                }
+                if !self.inside(&zone.position) {
+                    return Err(MapError::Invalid(format!("zone {} is out of bounds", i)));
+                }
+                if zone.name.is_empty() || zones[..i].iter().any(|other| other.name == zone.name) {
+                    return Err(MapError::Invalid(format!("zone {} needs a name of its own", i)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the map can host `mode`: the flag bases and zones it falls
+    /// back to when it doesn't place its own have to fit in the arena too.
+    pub fn check_mode(&self, mode: GameMode) -> Result<(), MapError> {
+        if mode == GameMode::CaptureTheFlag {
+            let bases = self.flag_bases();
+            for team in Team::ALL {
+                if !self.reachable(bases.of(team)) {
+                    return Err(MapError::Invalid(format!("{} flag base is blocked or out of bounds", team.name())));
+                }
+            }
+        }
+        if mode.has_zones() {
+            if let Some(zone) = self.zones_for(mode).iter().find(|zone| !self.inside(&zone.position)) {
+                return Err(MapError::Invalid(format!("zone {} is out of bounds", zone.name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a tank centred at `position` would be inside the arena.
+    fn inside(&self, position: &Position) -> bool {
+        let limit = self.bounds - TANK_RADIUS;
+        position.x.abs() <= limit && position.y.abs() <= limit
+    }
+
+    /// Whether a tank could stand at `position`.
+    fn reachable(&self, position: &Position) -> bool {
+        self.inside(position) && !self.blocks_tank(position)
+    }
+
+    /// Where the capture-the-flag bases are.
+    pub fn flag_bases(&self) -> FlagBases {
+        self.flag_bases.clone().unwrap_or_default()
+    }
+
+    /// The neutral zones `mode` is played over on this map.
+    pub fn zones_for(&self, mode: GameMode) -> Vec<CaptureZone> {
+        match &self.zones {
+            Some(spots) if mode.has_zones() => spots.iter().map(ZoneSpot::zone).collect(),
+            _ => zones_for_mode(mode),
+        }
+    }
+
+    /// Whether a tank centred at `position` would overlap an obstacle.
+    pub fn blocks_tank(&self, position: &Position) -> bool {
+        self.blocks_circle(position, TANK_RADIUS)
+    }
+
+    /// Whether a circle of `radius` at `position` would overlap an obstacle.
+    pub fn blocks_circle(&self, position: &Position, radius: f32) -> bool {
+        self.walls.iter().any(|w| w.penetration(position, radius).is_some())
+            || self.boxes.iter().any(|b| b.penetration(position, radius).is_some())
+    }
+
+    /// Whether a bullet at `position` has hit an obstacle or left the arena.
+    pub fn blocks_bullet(&self, position: &Position) -> bool {
+        position.x.abs() > self.bounds
+            || position.y.abs() > self.bounds
+            || self.walls.iter().any(|w| w.penetration(position, 0.0).is_some())
+            || self.boxes.iter().any(|b| b.contains(position))
+    }
+
+    /// How far along the path from `from` to `to` a bullet first strikes a
+    /// wall or box, as a fraction of the path, so a fast bullet can't skip
+    /// over a thin wall between ticks.
+    pub fn bullet_impact(&self, from: &Position, to: &Position) -> Option<f32> {
+        let walls = self
+            .walls
+            .iter()
+            .filter_map(|w| segment_capsule(from, to, &w.from, &w.to, w.thickness / 2.0));
+        let boxes = self.boxes.iter().filter_map(|b| segment_box(from, to, &b.min, &b.max));
+        walls.chain(boxes).reduce(f32::min)
+    }
+
+    /// Moves a tank at `position` the shortest way out of any obstacles it
+    /// overlaps and back inside the arena.
+    pub fn resolve_tank(&self, position: &mut Position) {
+        for _ in 0..RESOLVE_PASSES {
+            let penetrations = self
+                .walls
+                .iter()
+                .filter_map(|w| w.penetration(position, TANK_RADIUS))
+                .chain(self.boxes.iter().filter_map(|b| b.penetration(position, TANK_RADIUS)))
+                .collect::<Vec<_>>();
+            if penetrations.is_empty() {
+                break;
+            }
+            for (nx, ny, depth) in penetrations {
+                position.x += nx * depth;
+                position.y += ny * depth;
+            }
+        }
+
+        let limit = self.bounds - TANK_RADIUS;
+        position.x = position.x.clamp(-limit, limit);
+        position.y = position.y.clamp(-limit, limit);
+    }
+}
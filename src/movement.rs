@@ -0,0 +1,49 @@
+//! Tank movement rules shared by the server simulation and client-side
+//! prediction. Both sides must call `apply_movement` with the same input, dt
+//! and map so a predicted tank ends up exactly where the server puts it.
+
+use crate::map::Map;
+use crate::{ClientMessage, Tank, SIMULATION_TICK_RATE};
+
+/// Length of one simulation tick in seconds.
+pub const SIMULATION_DT: f32 = 1.0 / SIMULATION_TICK_RATE as f32;
+
+pub const TANK_SPEED: f32 = 150.0; // units/s
+pub const TANK_TURN_RATE: f32 = 3.0; // rad/s
+pub const TURRET_TURN_RATE: f32 = 3.0; // rad/s
+
+/// Applies one tick of hull and turret movement for `input` to `tank`,
+/// sliding it along any obstacle on `map` it runs into. Dead tanks do not
+/// move. Firing is handled by the server separately.
+pub fn apply_movement(tank: &mut Tank, input: u16, dt: f32, map: &Map) {
+    if tank.is_dead {
+        return;
+    }
+
+    let keys = ClientMessage::decode_input(input);
+
+    // Movement
+    if keys.w {
+        tank.position.x += tank.rotation.cos() * TANK_SPEED * dt;
+        tank.position.y += tank.rotation.sin() * TANK_SPEED * dt;
+    }
+    if keys.a {
+        tank.rotation -= TANK_TURN_RATE * dt;
+    }
+    if keys.s {
+        tank.position.x -= tank.rotation.cos() * TANK_SPEED * dt;
+        tank.position.y -= tank.rotation.sin() * TANK_SPEED * dt;
+    }
+    if keys.d {
+        tank.rotation += TANK_TURN_RATE * dt;
+    }
+    map.resolve_tank(&mut tank.position);
+
+    // Turret rotation
+    if keys.arrow_left {
+        tank.turret_rotation -= TURRET_TURN_RATE * dt;
+    }
+    if keys.arrow_right {
+        tank.turret_rotation += TURRET_TURN_RATE * dt;
+    }
+}
@@ -0,0 +1,259 @@
+//! Navigation for NPCs. The arena is cut into square cells, each marked
+//! walkable if a tank fits there with a little room to spare; A* finds a
+//! route over the cells and string-pulling straightens it into as few
+//! waypoints as the obstacles allow.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::map::{Map, TANK_RADIUS};
+use crate::Position;
+
+/// Side of one navigation cell
+pub const NAV_CELL_SIZE: f32 = 20.0;
+/// Room kept between a tank's hull and an obstacle when marking cells, so
+/// a route along a row of walkable cells doesn't scrape the corners
+pub const NAV_MARGIN: f32 = 5.0;
+/// A goal that has moved this far from where the route was planned to gets
+/// a new route
+pub const REPATH_DISTANCE: f32 = 50.0;
+/// Routes are replanned at least this often, in milliseconds
+pub const REPATH_INTERVAL_MS: u64 = 1000;
+/// A waypoint counts as reached this close
+pub const WAYPOINT_RADIUS: f32 = 15.0;
+
+// Step costs on the grid, scaled so a diagonal is about √2 times a side
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+// How far (in cells) to look for a walkable cell near a blocked endpoint
+const SNAP_SEARCH_CELLS: i32 = 8;
+
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    cell_size: f32,
+    /// Lower edge of the grid on both axes
+    origin: f32,
+    columns: usize,
+    walkable: Vec<bool>,
+}
+
+impl NavGrid {
+    /// Marks every cell of `map` a tank can stand in.
+    pub fn from_map(map: &Map) -> Self {
+        let cell_size = NAV_CELL_SIZE;
+        let columns = ((map.bounds * 2.0) / cell_size).ceil().max(1.0) as usize;
+        let origin = -map.bounds;
+        let limit = map.bounds - TANK_RADIUS;
+        let walkable = (0..columns * columns)
+            .map(|i| {
+                let centre = Position {
+                    x: origin + ((i % columns) as f32 + 0.5) * cell_size,
+                    y: origin + ((i / columns) as f32 + 0.5) * cell_size,
+                };
+                centre.x.abs() <= limit
+                    && centre.y.abs() <= limit
+                    && !map.blocks_circle(&centre, TANK_RADIUS + NAV_MARGIN)
+            })
+            .collect();
+        Self {
+            cell_size,
+            origin,
+            columns,
+            walkable,
+        }
+    }
+
+    fn cell_of(&self, position: &Position) -> (i32, i32) {
+        let cell = |v: f32| ((v - self.origin) / self.cell_size).floor() as i32;
+        (cell(position.x), cell(position.y))
+    }
+
+    fn centre(&self, (x, y): (i32, i32)) -> Position {
+        Position {
+            x: self.origin + (x as f32 + 0.5) * self.cell_size,
+            y: self.origin + (y as f32 + 0.5) * self.cell_size,
+        }
+    }
+
+    fn index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        let columns = self.columns as i32;
+        (x >= 0 && y >= 0 && x < columns && y < columns).then(|| (y * columns + x) as usize)
+    }
+
+    fn is_walkable(&self, cell: (i32, i32)) -> bool {
+        self.index(cell).is_some_and(|i| self.walkable[i])
+    }
+
+    /// Whether a tank at `position` is on a walkable cell.
+    pub fn is_walkable_at(&self, position: &Position) -> bool {
+        self.is_walkable(self.cell_of(position))
+    }
+
+    /// The walkable cell nearest `cell`, searching outwards ring by ring.
+    fn snap(&self, cell: (i32, i32)) -> Option<(i32, i32)> {
+        (0..=SNAP_SEARCH_CELLS).find_map(|ring| {
+            let mut ring_cells = (-ring..=ring)
+                .flat_map(|dx| (-ring..=ring).map(move |dy| (dx, dy)))
+                .filter(|&(dx, dy)| dx.abs() == ring || dy.abs() == ring)
+                .map(|(dx, dy)| (cell.0 + dx, cell.1 + dy))
+                .filter(|&c| self.is_walkable(c))
+                .collect::<Vec<_>>();
+            ring_cells.sort_by_key(|&(x, y)| (x - cell.0).pow(2) + (y - cell.1).pow(2));
+            ring_cells.into_iter().next()
+        })
+    }
+
+    /// Whether a tank can drive straight from `from` to `to` without leaving
+    /// walkable cells.
+    pub fn clear_line(&self, from: &Position, to: &Position) -> bool {
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        let steps = ((dx.hypot(dy) / (self.cell_size / 4.0)).ceil() as usize).max(1);
+        (0..=steps).all(|i| {
+            let t = i as f32 / steps as f32;
+            self.is_walkable_at(&Position {
+                x: from.x + dx * t,
+                y: from.y + dy * t,
+            })
+        })
+    }
+
+    /// Waypoints from `from` to `to`, not including `from`. Endpoints inside
+    /// obstacles are moved to the nearest walkable spot; `None` when `to`
+    /// can't be reached at all.
+    pub fn find_path(&self, from: &Position, to: &Position) -> Option<Vec<Position>> {
+        let start = self.snap(self.cell_of(from))?;
+        let goal_cell = self.cell_of(to);
+        let goal = self.snap(goal_cell)?;
+        let cells = self.a_star(start, goal)?;
+
+        // Finish exactly on the goal when it's reachable, else on its cell
+        let end = if goal == goal_cell { to.clone() } else { self.centre(goal) };
+        let mut path: Vec<Position> = cells
+            .iter()
+            .skip(1)
+            .take(cells.len().saturating_sub(2))
+            .map(|&c| self.centre(c))
+            .collect();
+        path.push(end);
+
+        // Starting off the grid, head for the nearest walkable cell first
+        if start == self.cell_of(from) {
+            return Some(self.smooth(from, path));
+        }
+        let first = self.centre(start);
+        let mut smoothed = self.smooth(&first, path);
+        smoothed.insert(0, first);
+        Some(smoothed)
+    }
+
+    fn a_star(&self, start: (i32, i32), goal: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        let start_index = self.index(start)?;
+        let goal_index = self.index(goal)?;
+        let heuristic = |(x, y): (i32, i32)| {
+            let (dx, dy) = ((x - goal.0).unsigned_abs(), (y - goal.1).unsigned_abs());
+            STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+        };
+
+        let mut cost = vec![u32::MAX; self.walkable.len()];
+        let mut came_from = vec![usize::MAX; self.walkable.len()];
+        let mut open = BinaryHeap::new();
+        cost[start_index] = 0;
+        open.push(Reverse((heuristic(start), start_index)));
+
+        while let Some(Reverse((_, index))) = open.pop() {
+            if index == goal_index {
+                let mut cells = vec![goal];
+                let mut at = index;
+                while at != start_index {
+                    at = came_from[at];
+                    let columns = self.columns as i32;
+                    cells.push((at as i32 % columns, at as i32 / columns));
+                }
+                cells.reverse();
+                return Some(cells);
+            }
+            let columns = self.columns as i32;
+            let cell = (index as i32 % columns, index as i32 / columns);
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let next = (cell.0 + dx, cell.1 + dy);
+                let Some(next_index) = self.index(next).filter(|&i| self.walkable[i]) else {
+                    continue;
+                };
+                let diagonal = dx != 0 && dy != 0;
+                // No cutting corners past a blocked cell
+                if diagonal && !(self.is_walkable((cell.0 + dx, cell.1)) && self.is_walkable((cell.0, cell.1 + dy))) {
+                    continue;
+                }
+                let next_cost = cost[index] + if diagonal { DIAGONAL_COST } else { STRAIGHT_COST };
+                if next_cost < cost[next_index] {
+                    cost[next_index] = next_cost;
+                    came_from[next_index] = index;
+                    open.push(Reverse((next_cost + heuristic(next), next_index)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Drops every waypoint that can be skipped by driving straight to a
+    /// later one.
+    fn smooth(&self, from: &Position, path: Vec<Position>) -> Vec<Position> {
+        let mut smoothed = Vec::new();
+        let mut at = from.clone();
+        let mut i = 0;
+        while i < path.len() {
+            // Furthest waypoint in a straight line from here
+            let furthest = (i..path.len()).rev().find(|&j| self.clear_line(&at, &path[j])).unwrap_or(i);
+            at = path[furthest].clone();
+            smoothed.push(at.clone());
+            i = furthest + 1;
+        }
+        smoothed
+    }
+}
+
+/// A route being followed, replanned when the goal moves or it gets stale.
+#[derive(Debug, Clone, Default)]
+pub struct PathFollower {
+    /// Where the current route leads, and when it was planned
+    planned: Option<(Position, u64)>,
+    waypoints: VecDeque<Position>,
+}
+
+impl PathFollower {
+    /// The point to steer at from `position` on the way to `goal` at server
+    /// time `now`. `None` when `goal` can't be reached.
+    pub fn next_waypoint(&mut self, nav: &NavGrid, position: &Position, goal: &Position, now: u64) -> Option<Position> {
+        let stale = match &self.planned {
+            Some((planned_goal, planned_at)) => {
+                (planned_goal.x - goal.x).hypot(planned_goal.y - goal.y) > REPATH_DISTANCE
+                    || now.saturating_sub(*planned_at) >= REPATH_INTERVAL_MS
+            }
+            None => true,
+        };
+        if stale || self.waypoints.is_empty() {
+            let Some(path) = nav.find_path(position, goal) else {
+                self.clear();
+                return None;
+            };
+            self.waypoints = path.into();
+            self.planned = Some((goal.clone(), now));
+        }
+
+        while self.waypoints.len() > 1
+            && self
+                .waypoints
+                .front()
+                .is_some_and(|w| (w.x - position.x).hypot(w.y - position.y) < WAYPOINT_RADIUS)
+        {
+            self.waypoints.pop_front();
+        }
+        self.waypoints.front().cloned()
+    }
+
+    /// Forgets the current route.
+    pub fn clear(&mut self) {
+        self.planned = None;
+        self.waypoints.clear();
+    }
+}
@@ -2,106 +2,281 @@ use bevy::prelude::*;
 use futures_util::{SinkExt, StreamExt};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tokio::sync::mpsc;
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use serde_json;
 
 use battlexone_shared::*;
+use battlexone_shared::delta::GameStateDelta;
+use battlexone_shared::reconnect::Backoff;
+use battlexone_shared::wire::{self, WireEncoding};
 use crate::game_state::{GameStateResource, PlayerInfo};
 
 #[derive(Resource)]
 pub struct ConnectionState {
     pub connected: Arc<Mutex<bool>>,
+    /// Why the server refused our join, if it did
+    pub rejection: Arc<Mutex<Option<String>>>,
 }
 
 #[derive(Resource)]
 pub struct WebSocketSender {
-    pub sender: Arc<Mutex<Option<mpsc::UnboundedSender<String>>>>,
+    pub sender: Arc<Mutex<Option<mpsc::UnboundedSender<ClientMessage>>>>,
+}
+
+// Must cover at least the server's snapshot history so any delta it sends
+// can find its base here
+const RECENT_SNAPSHOTS: usize = 32;
+
+/// Recently applied snapshots, kept so deltas computed against an older
+/// acknowledged snapshot can still be applied.
+struct SnapshotHistory {
+    recent: VecDeque<GameState>,
+    awaiting_keyframe: bool,
+}
+
+impl SnapshotHistory {
+    fn new() -> Self {
+        Self {
+            recent: VecDeque::new(),
+            awaiting_keyframe: false,
+        }
+    }
+
+    fn store(&mut self, state: GameState) {
+        self.awaiting_keyframe = false;
+        self.recent.push_back(state);
+        while self.recent.len() > RECENT_SNAPSHOTS {
+            self.recent.pop_front();
+        }
+    }
+
+    fn base(&self, tick: u64) -> Option<&GameState> {
+        self.recent.iter().rev().find(|s| s.tick == tick)
+    }
+
+    fn apply_delta(&mut self, delta: &GameStateDelta, tx: &mpsc::UnboundedSender<ClientMessage>) -> Option<GameState> {
+        let state = self.base(delta.base_tick).and_then(|base| delta.apply(base));
+        if state.is_none() {
+            // We no longer have the base snapshot
+            self.request_keyframe(tx);
+        }
+        state
+    }
+
+    fn decode_binary(&mut self, bytes: &[u8], tx: &mpsc::UnboundedSender<ClientMessage>) -> Option<ServerMessage> {
+        match wire::decode_server_message(bytes, |tick| self.base(tick)) {
+            Ok(server_msg) => Some(server_msg),
+            Err(wire::WireError::MissingBase(_)) => {
+                self.request_keyframe(tx);
+                None
+            }
+            Err(e) => {
+                eprintln!("Invalid binary message: {}", e);
+                None
+            }
+        }
+    }
+
+    fn request_keyframe(&mut self, tx: &mpsc::UnboundedSender<ClientMessage>) {
+        if !self.awaiting_keyframe {
+            self.awaiting_keyframe = true;
+            let _ = tx.send(ClientMessage::RequestKeyframe);
+        }
+    }
+}
+
+const SERVER_URL: &str = "ws://127.0.0.1:3001";
+
+/// Features we offer the server; JSON is still understood as a fallback if
+/// it turns down the binary encoding.
+const CLIENT_FEATURES: [Feature; 2] = [Feature::BinaryEncoding, Feature::DeltaSnapshots];
+
+fn encode_message(message: &ClientMessage, encoding: WireEncoding) -> Option<Message> {
+    match encoding {
+        WireEncoding::Json => serde_json::to_string(message).ok().map(Message::Text),
+        WireEncoding::Binary => Some(Message::Binary(wire::encode_client_message(message))),
+    }
 }
 
 pub fn setup_network(
     game_state: Res<GameStateResource>,
     player_info: Res<PlayerInfo>,
     connected: Arc<Mutex<bool>>,
-    sender_resource: Arc<Mutex<Option<mpsc::UnboundedSender<String>>>>,
+    rejection: Arc<Mutex<Option<String>>>,
+    sender_resource: Arc<Mutex<Option<mpsc::UnboundedSender<ClientMessage>>>>,
 ) {
     // Clone the resources we need in the thread
     let game_data = game_state.get_data();
+    let snapshots = game_state.get_snapshots();
+    let hits = game_state.get_hits();
+    let scoreboard = game_state.get_scoreboard();
+    let leaderboard = game_state.get_leaderboard();
+    let match_state = game_state.get_match_state();
+    let flag_events = game_state.get_flag_events();
+    let zone_events = game_state.get_zone_events();
+    let map = game_state.get_map();
+    let joins = game_state.get_joins();
+    let tick_rate = game_state.get_tick_rate();
     let player_id = player_info.get_player_id_arc();
     let user_id = player_info.get_user_id_arc();
     
     thread::spawn(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async {
-            println!("Connecting to WebSocket server...");
-            
-            match connect_async("ws://127.0.0.1:3001").await {
-                Ok((ws_stream, _)) => {
-                    println!("Connected to server!");
-                    *connected.lock().unwrap() = true;
-                    let (mut ws_sender, mut ws_receiver) = ws_stream.split();
-                    
-                    // Create a channel for sending messages from the main thread
-                    let (tx, mut rx) = mpsc::unbounded_channel();
-                    *sender_resource.lock().unwrap() = Some(tx);
-                    
-                    // Get the user_id value
-                    let user_id_value = user_id.lock().unwrap().clone();
-                    
-                    // Send join message
-                    let join_msg = ClientMessage::Join {
-                        name: "Bevy Player".to_string(),
-                        user_id: user_id_value,
-                    };
-                    
-                    if let Ok(msg_str) = serde_json::to_string(&join_msg) {
-                        let _ = ws_sender.send(Message::Text(msg_str)).await;
-                    }
-                    
-                    // Spawn task to handle outgoing messages
-                    tokio::spawn(async move {
-                        while let Some(message) = rx.recv().await {
-                            if ws_sender.send(Message::Text(message)).await.is_err() {
-                                break;
-                            }
+            let mut backoff = Backoff::default();
+            // Private servers want a key or an account; the name is the
+            // account name
+            let name = std::env::var("BATTLEXONE_NAME").unwrap_or_else(|_| "Bevy Player".to_string());
+            let password = std::env::var("BATTLEXONE_PASSWORD").ok();
+            // From the last `Joined`, so a reconnection resumes our identity
+            let mut token: Option<String> = None;
+            loop {
+                println!("Connecting to WebSocket server...");
+                
+                match connect_async(SERVER_URL).await {
+                    Ok((ws_stream, _)) => {
+                        println!("Connected to server!");
+                        backoff.reset();
+                        *connected.lock().unwrap() = true;
+                        let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+                        
+                        // Create a channel for sending messages from the main thread
+                        let (tx, mut rx) = mpsc::unbounded_channel();
+                        *sender_resource.lock().unwrap() = Some(tx.clone());
+                        
+                        // The user id from an earlier join, if any, gets our
+                        // old tank back
+                        let user_id_value = user_id.lock().unwrap().clone();
+                        
+                        // Send join message
+                        let join_msg = ClientMessage::Join {
+                            name: name.clone(),
+                            user_id: user_id_value,
+                            token: token.clone(),
+                            password: password.clone(),
+                            protocol_version: PROTOCOL_VERSION,
+                            client_kind: ClientKind::Native,
+                            features: CLIENT_FEATURES.to_vec(),
+                        };
+                        
+                        if let Ok(msg_str) = serde_json::to_string(&join_msg) {
+                            let _ = ws_sender.send(Message::Text(msg_str)).await;
                         }
-                    });
-                    
-                    // Listen for messages from server
-                    while let Some(msg) = ws_receiver.next().await {
-                        match msg {
-                            Ok(Message::Text(text)) => {
-                                if let Ok(server_msg) = serde_json::from_str::<ServerMessage>(&text) {
-                                    match server_msg {
-                                        ServerMessage::Joined { player_id: p_id, user_id: _ } => {
-                                            println!("Joined game with player_id: {}", p_id);
-                                            *player_id.lock().unwrap() = Some(p_id);
-                                        }
-                                        ServerMessage::GameState(state) => {
-                                            let mut data = game_data.lock().unwrap();
-                                            *data = (state.tanks, state.bullets);
-                                        }
-                                    }
+                        
+                        // Encoding the server accepted, set once `Joined` arrives
+                        let encoding = Arc::new(Mutex::new(WireEncoding::Json));
+                        
+                        // Spawn task to handle outgoing messages
+                        let outgoing_encoding = encoding.clone();
+                        tokio::spawn(async move {
+                            while let Some(message) = rx.recv().await {
+                                let encoding = *outgoing_encoding.lock().unwrap();
+                                let Some(message) = encode_message(&message, encoding) else {
+                                    continue;
+                                };
+                                if ws_sender.send(message).await.is_err() {
+                                    break;
                                 }
                             }
-                            Ok(Message::Close(_)) => {
-                                println!("Server closed connection");
-                                *connected.lock().unwrap() = false;
-                                break;
-                            }
-                            Err(e) => {
-                                eprintln!("WebSocket error: {}", e);
-                                *connected.lock().unwrap() = false;
-                                break;
+                        });
+                        
+                        // Listen for messages from server
+                        let mut history = SnapshotHistory::new();
+                        while let Some(msg) = ws_receiver.next().await {
+                            let server_msg = match msg {
+                                Ok(Message::Text(text)) => serde_json::from_str::<ServerMessage>(&text).ok(),
+                                Ok(Message::Binary(bytes)) => history.decode_binary(&bytes, &tx),
+                                Ok(Message::Close(_)) => {
+                                    println!("Server closed connection");
+                                    break;
+                                }
+                                Err(e) => {
+                                    eprintln!("WebSocket error: {}", e);
+                                    break;
+                                }
+                                _ => None,
+                            };
+                            
+                            let state = match server_msg {
+                                Some(ServerMessage::Joined { player_id: p_id, user_id: u_id, token: session_token, features, map: joined_map, tick_rate: server_tick_rate, .. }) => {
+                                    println!("Joined game with player_id: {} (features {:?})", p_id, features);
+                                    println!("Playing on {}", joined_map.name);
+                                    *player_id.lock().unwrap() = Some(p_id);
+                                    *user_id.lock().unwrap() = Some(u_id);
+                                    token = Some(session_token);
+                                    *map.lock().unwrap() = joined_map;
+                                    *tick_rate.lock().unwrap() = server_tick_rate;
+                                    // The server may have restarted, with its
+                                    // ticks and clock back at zero
+                                    snapshots.lock().unwrap().clear();
+                                    *game_data.lock().unwrap() = GameState::default();
+                                    *joins.lock().unwrap() += 1;
+                                    if features.contains(&Feature::BinaryEncoding) {
+                                        *encoding.lock().unwrap() = WireEncoding::Binary;
+                                    }
+                                    None
+                                }
+                                Some(ServerMessage::Rejected { reason, message, protocol_version }) => {
+                                    eprintln!("Join rejected ({:?}, server protocol {}): {}", reason, protocol_version, message);
+                                    // A full server may have room by the next attempt
+                                    if reason != RejectReason::ServerFull {
+                                        *rejection.lock().unwrap() = Some(message);
+                                    }
+                                    None
+                                }
+                                Some(ServerMessage::GameState(state)) => Some(state),
+                                Some(ServerMessage::GameStateDelta(delta)) => history.apply_delta(&delta, &tx),
+                                Some(ServerMessage::Hit(hit)) => {
+                                    hits.lock().unwrap().push(hit);
+                                    None
+                                }
+                                Some(ServerMessage::Scoreboard { entries }) => {
+                                    *scoreboard.lock().unwrap() = entries;
+                                    None
+                                }
+                                Some(ServerMessage::Leaderboard { entries }) => {
+                                    *leaderboard.lock().unwrap() = entries;
+                                    None
+                                }
+                                Some(ServerMessage::MatchState(state)) => {
+                                    *match_state.lock().unwrap() = Some(state);
+                                    None
+                                }
+                                Some(ServerMessage::Flag(event)) => {
+                                    flag_events.lock().unwrap().push(event);
+                                    None
+                                }
+                                Some(ServerMessage::Zone(event)) => {
+                                    zone_events.lock().unwrap().push(event);
+                                    None
+                                }
+                                None => None,
+                            };
+                            
+                            if let Some(state) = state {
+                                let _ = tx.send(ClientMessage::Ack { tick: state.tick });
+                                history.store(state.clone());
+                                snapshots.lock().unwrap().push(state.clone());
+                                *game_data.lock().unwrap() = state;
                             }
-                            _ => {}
                         }
                     }
+                    Err(e) => {
+                        eprintln!("Failed to connect to server: {}", e);
+                    }
                 }
-                Err(e) => {
-                    eprintln!("Failed to connect to server: {}", e);
+                *connected.lock().unwrap() = false;
+                *sender_resource.lock().unwrap() = None;
+                
+                if rejection.lock().unwrap().is_some() {
+                    // Retrying would only be rejected again
+                    break;
                 }
+                let delay = backoff.next_delay();
+                println!("Reconnecting in {:?}", delay);
+                tokio::time::sleep(delay).await;
             }
         });
     });
-} 
\ No newline at end of file
+}
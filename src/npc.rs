@@ -0,0 +1,252 @@
+//! NPC brains. Each tick an NPC takes in the nearest enemy it can see, then
+//! scores what it could do about it and does whichever scores highest:
+//! patrol when there's nothing to fight, engage while healthy, and back off
+//! as damage piles up. Difficulty sets how quickly it reacts to a new enemy
+//! and how far off its aim is.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
+
+use crate::nav::PathFollower;
+use crate::{Position, Tank, TANK_MAX_HEALTH};
+
+/// Utility of patrolling, which everything else has to beat
+const PATROL_UTILITY: f32 = 0.2;
+/// Engaged NPCs close in to this range, then hold and shoot
+pub const ENGAGE_DISTANCE: f32 = 250.0;
+/// Retreating NPCs head for a point this far beyond themselves, away from
+/// the enemy
+pub const RETREAT_DISTANCE: f32 = 300.0;
+/// Turret has to be this close (radians) to the aim before firing
+pub const FIRE_TOLERANCE: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Delay between first seeing an enemy and reacting to it
+    pub fn reaction_ms(self) -> u64 {
+        match self {
+            Difficulty::Easy => 800,
+            Difficulty::Normal => 400,
+            Difficulty::Hard => 150,
+        }
+    }
+
+    /// Largest aim error, in radians either way
+    pub fn aim_error(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.25,
+            Difficulty::Normal => 0.1,
+            Difficulty::Hard => 0.03,
+        }
+    }
+
+    /// Turret traverse speed, in radians per second
+    pub fn turret_turn_rate(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.0,
+            Difficulty::Normal => 1.5,
+            Difficulty::Hard => 2.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NpcAction {
+    Patrol,
+    Engage,
+    Retreat,
+}
+
+/// The enemy an NPC is currently paying attention to.
+#[derive(Debug, Clone)]
+struct Tracked {
+    id: String,
+    first_seen: u64,
+    last_seen: u64,
+    position: Position,
+    /// Estimated from successive sightings, in units per second
+    velocity: (f32, f32),
+    /// Current aim error, re-rolled after every shot
+    aim_offset: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct NpcBrain {
+    pub difficulty: Difficulty,
+    /// Where to drive while there's nothing to fight
+    pub patrol_target: Position,
+    /// Route to wherever it's currently driving
+    pub route: PathFollower,
+    target: Option<Tracked>,
+}
+
+impl NpcBrain {
+    pub fn new(difficulty: Difficulty, patrol_target: Position) -> Self {
+        Self {
+            difficulty,
+            patrol_target,
+            route: PathFollower::default(),
+            target: None,
+        }
+    }
+
+    /// Takes in the nearest enemy in sight at server time `now`, or that
+    /// there's none. Keeping sight of the same enemy refines the estimate of
+    /// how it's moving; a new one starts the reaction time over.
+    pub fn perceive(&mut self, enemy: Option<&Tank>, now: u64, rng: &mut impl Rng) {
+        let Some(enemy) = enemy else {
+            self.target = None;
+            return;
+        };
+        match &mut self.target {
+            Some(tracked) if tracked.id == enemy.id => {
+                let secs = now.saturating_sub(tracked.last_seen) as f32 / 1000.0;
+                if secs > 0.0 {
+                    tracked.velocity = (
+                        (enemy.position.x - tracked.position.x) / secs,
+                        (enemy.position.y - tracked.position.y) / secs,
+                    );
+                }
+                tracked.last_seen = now;
+                tracked.position = enemy.position.clone();
+            }
+            _ => {
+                self.target = Some(Tracked {
+                    id: enemy.id.clone(),
+                    first_seen: now,
+                    last_seen: now,
+                    position: enemy.position.clone(),
+                    velocity: (0.0, 0.0),
+                    aim_offset: self.roll_aim_error(rng),
+                });
+            }
+        }
+    }
+
+    fn roll_aim_error(&self, rng: &mut impl Rng) -> f32 {
+        let error = self.difficulty.aim_error();
+        rng.gen_range(-error..=error)
+    }
+
+    /// The enemy being tracked once the reaction time has passed.
+    fn reacted(&self, now: u64) -> Option<&Tracked> {
+        self.target
+            .as_ref()
+            .filter(|t| now.saturating_sub(t.first_seen) >= self.difficulty.reaction_ms())
+    }
+
+    /// Id of the enemy the NPC has reacted to, if any.
+    pub fn target_id(&self, now: u64) -> Option<&str> {
+        self.reacted(now).map(|t| t.id.as_str())
+    }
+
+    /// Scores each action for `me` and picks the best.
+    pub fn decide(&self, me: &Tank, now: u64) -> NpcAction {
+        if self.reacted(now).is_none() {
+            return NpcAction::Patrol;
+        }
+        let health = (me.health as f32 / TANK_MAX_HEALTH as f32).clamp(0.0, 1.0);
+        let utilities = [
+            (NpcAction::Patrol, PATROL_UTILITY),
+            // Confident while healthy
+            (NpcAction::Engage, 0.5 + 0.5 * health),
+            // Overtakes engaging at about 40% health
+            (NpcAction::Retreat, 1.2 * (1.0 - health)),
+        ];
+        utilities.into_iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap().0
+    }
+
+    /// Where `me` should drive for `action`; `None` to hold position.
+    pub fn destination(&self, me: &Tank, action: NpcAction) -> Option<Position> {
+        let enemy = self.target.as_ref().map(|t| &t.position);
+        match (action, enemy) {
+            (NpcAction::Engage, Some(enemy)) => {
+                let distance = (enemy.x - me.position.x).hypot(enemy.y - me.position.y);
+                (distance > ENGAGE_DISTANCE).then(|| enemy.clone())
+            }
+            (NpcAction::Retreat, Some(enemy)) => {
+                let (dx, dy) = (me.position.x - enemy.x, me.position.y - enemy.y);
+                let distance = dx.hypot(dy).max(f32::EPSILON);
+                Some(Position {
+                    x: me.position.x + dx / distance * RETREAT_DISTANCE,
+                    y: me.position.y + dy / distance * RETREAT_DISTANCE,
+                })
+            }
+            _ => Some(self.patrol_target.clone()),
+        }
+    }
+
+    /// Turret angle that puts a bullet travelling at `bullet_speed` on the
+    /// enemy, leading it by how it's been moving, plus this NPC's aim
+    /// error. `None` until it has reacted to an enemy.
+    pub fn aim(&self, me: &Tank, bullet_speed: f32, now: u64) -> Option<f32> {
+        let target = self.reacted(now)?;
+        Some(lead_angle(&me.position, &target.position, target.velocity, bullet_speed) + target.aim_offset)
+    }
+
+    /// Whether the turret at `turret_rotation` is on `aim` closely enough to
+    /// shoot. Line of fire is for the caller to check.
+    pub fn ready_to_fire(&self, turret_rotation: f32, aim: f32) -> bool {
+        angle_between(turret_rotation, aim).abs() <= FIRE_TOLERANCE
+    }
+
+    /// After a shot: the next one misses differently.
+    pub fn fired(&mut self, rng: &mut impl Rng) {
+        let offset = self.roll_aim_error(rng);
+        if let Some(target) = &mut self.target {
+            target.aim_offset = offset;
+        }
+    }
+}
+
+/// Signed angle from `from` to `to`, in -PI..PI.
+fn angle_between(from: f32, to: f32) -> f32 {
+    (to - from + PI).rem_euclid(TAU) - PI
+}
+
+/// `current` turned towards `desired` the short way round, by at most
+/// `max_turn`.
+pub fn turn_towards(current: f32, desired: f32, max_turn: f32) -> f32 {
+    current + angle_between(current, desired).clamp(-max_turn, max_turn)
+}
+
+/// Heading from `shooter` that meets a target at `target` moving at
+/// `velocity` with a bullet at `bullet_speed`. Aims straight at the target
+/// when no heading can catch it.
+pub fn lead_angle(shooter: &Position, target: &Position, velocity: (f32, f32), bullet_speed: f32) -> f32 {
+    let (dx, dy) = (target.x - shooter.x, target.y - shooter.y);
+    let (vx, vy) = velocity;
+    // |d + v t| = s t, solved for the earliest positive t
+    let a = vx * vx + vy * vy - bullet_speed * bullet_speed;
+    let b = 2.0 * (dx * vx + dy * vy);
+    let c = dx * dx + dy * dy;
+    let time = if a.abs() < f32::EPSILON {
+        // Bullet exactly as fast as the target: one crossing at most
+        (b < 0.0).then(|| -c / b)
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            None
+        } else {
+            let root = discriminant.sqrt();
+            [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+                .into_iter()
+                .filter(|t| *t > 0.0)
+                .reduce(f32::min)
+        }
+    };
+    let (ax, ay) = match time {
+        Some(t) => (dx + vx * t, dy + vy * t),
+        None => (dx, dy),
+    };
+    ay.atan2(ax)
+}
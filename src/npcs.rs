@@ -0,0 +1,145 @@
+//! Driving the NPC tanks for one tick: each brain looks for an enemy,
+//! follows its route over the navigation grid, turns its turret and fires
+//! when the shot is clear of walls and teammates.
+
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::bullets::bullet_from;
+use crate::grid::SpatialGrid;
+use crate::map::Map;
+use crate::nav::NavGrid;
+use crate::npc::{turn_towards, NpcAction, NpcBrain};
+use crate::server_config::{Tuning, NPC_MIN_PATROL_DISTANCE};
+use crate::teams::is_enemy;
+use crate::{Bullet, Position, Tank};
+
+/// A patrol target this close counts as reached
+pub const ARRIVAL_DISTANCE: f32 = 50.0;
+
+/// What NPCs drive around in and by which numbers.
+pub struct NpcWorld<'a> {
+    pub map: &'a Map,
+    pub nav: &'a NavGrid,
+    pub tuning: Tuning,
+    /// New patrol targets are picked within this distance of the centre
+    pub patrol_radius: f32,
+}
+
+/// Somewhere for an NPC to drive to, within `radius` of the centre.
+pub fn random_npc_target(rng: &mut impl Rng, radius: f32) -> Position {
+    let angle = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
+    let distance = rng.gen_range(NPC_MIN_PATROL_DISTANCE..radius);
+    Position {
+        x: angle.cos() * distance,
+        y: angle.sin() * distance,
+    }
+}
+
+/// Moves, aims and fires every living NPC in `tanks` that has a brain.
+/// `now` is server time, which brains react on; `wall_ms` is Unix time in
+/// milliseconds, which fire cooldowns and bullet ages are measured in. The
+/// bullets fired come back.
+pub fn update_npcs(
+    tanks: &mut [Tank],
+    brains: &mut HashMap<String, NpcBrain>,
+    world: &NpcWorld,
+    dt: f32,
+    now: u64,
+    wall_ms: u64,
+    rng: &mut impl Rng,
+) -> Vec<Bullet> {
+    let tuning = world.tuning;
+    let mut fired = Vec::new();
+
+    // What NPCs can see, as of before any of them move this tick
+    let grid = SpatialGrid::from_tanks(tanks);
+
+    for i in 0..tanks.len() {
+        if tanks[i].is_player || tanks[i].is_dead {
+            continue;
+        }
+        let Some(brain) = brains.get_mut(&tanks[i].id) else {
+            continue;
+        };
+
+        // Perceive the nearest enemy with nothing in the way
+        let me = &tanks[i];
+        let enemy = grid
+            .nearest(tanks, &me.position, tuning.npc_sight_range, |t| {
+                t.id != me.id && is_enemy(me.team, t.team) && world.map.bullet_impact(&me.position, &t.position).is_none()
+            })
+            .map(|j| tanks[j].clone());
+        brain.perceive(enemy.as_ref(), now, rng);
+        let action = brain.decide(me, now);
+        let aim = brain.aim(me, tuning.bullet_speed, now);
+        let clear_shot = enemy
+            .as_ref()
+            .is_some_and(|enemy| has_line_of_fire(world.map, &grid, tanks, i, enemy, tuning.bullet_hit_radius));
+        let destination = brain.destination(me, action);
+
+        let tank = &mut tanks[i];
+        if let Some(mut destination) = destination {
+            world.map.resolve_tank(&mut destination);
+            let dx = destination.x - tank.position.x;
+            let dy = destination.y - tank.position.y;
+            let waypoint = if action == NpcAction::Patrol && dx.hypot(dy) < ARRIVAL_DISTANCE {
+                None // Arrived
+            } else {
+                brain.route.next_waypoint(world.nav, &tank.position, &destination, now)
+            };
+            match waypoint {
+                Some(waypoint) => {
+                    let heading = (waypoint.y - tank.position.y).atan2(waypoint.x - tank.position.x);
+                    tank.rotation = heading;
+                    let before = tank.position.clone();
+                    tank.position.x += heading.cos() * tuning.npc_speed * dt;
+                    tank.position.y += heading.sin() * tuning.npc_speed * dt;
+
+                    // Slide along obstacles; if that still doesn't get
+                    // anywhere, plan again from here
+                    world.map.resolve_tank(&mut tank.position);
+                    let moved = (tank.position.x - before.x).hypot(tank.position.y - before.y);
+                    if moved < tuning.npc_speed * dt * 0.25 {
+                        brain.route.clear();
+                    }
+                }
+                // Arrived, or the patrol point can't be reached: patrol
+                // somewhere else. Otherwise hold position.
+                None if action == NpcAction::Patrol => {
+                    brain.patrol_target = random_npc_target(rng, world.patrol_radius);
+                    brain.route.clear();
+                }
+                None => {}
+            }
+        }
+
+        // Aim at the enemy, or look where we're going
+        let desired = aim.unwrap_or(tank.rotation);
+        let max_turn = brain.difficulty.turret_turn_rate() * dt;
+        tank.turret_rotation = turn_towards(tank.turret_rotation, desired, max_turn);
+
+        let on_target = aim.is_some_and(|aim| brain.ready_to_fire(tank.turret_rotation, aim));
+        if !on_target || !clear_shot || wall_ms.saturating_sub(tank.last_fire_time) <= tuning.npc_fire_cooldown_ms {
+            continue;
+        }
+        tank.last_fire_time = wall_ms;
+        tank.spawn_protected_until = None; // Firing ends spawn protection
+
+        fired.push(bullet_from(tank, &tuning, wall_ms));
+        brain.fired(rng);
+    }
+    fired
+}
+
+/// Whether a shot from `tanks[shooter]` at `target` would get there
+/// without hitting a wall or a teammate first.
+pub fn has_line_of_fire(map: &Map, grid: &SpatialGrid, tanks: &[Tank], shooter: usize, target: &Tank, hit_radius: f32) -> bool {
+    let from = &tanks[shooter].position;
+    if map.bullet_impact(from, &target.position).is_some() {
+        return false;
+    }
+    let first = grid.first_hit(tanks, from, &target.position, hit_radius, |t| t.id != tanks[shooter].id);
+    first.is_none_or(|(i, _)| is_enemy(tanks[shooter].team, tanks[i].team))
+}
@@ -0,0 +1,41 @@
+//! Playing the objective modes for one tick: flags and zones move on, and
+//! what they earn goes on the match score.
+
+use crate::lifecycle::MatchLifecycle;
+use crate::teams;
+use crate::zones::{self, held_zone_points, ZONE_SCORE_INTERVAL_SECS};
+use crate::{CaptureZone, FlagEvent, FlagEventKind, Tank, ZoneEvent};
+
+/// Moves the match's flags with their carriers and applies pickups, drops,
+/// returns and captures; each capture scores a point for the capturing
+/// team.
+pub fn update_flags(lifecycle: &mut MatchLifecycle, tanks: &[Tank], now: u64) -> Vec<FlagEvent> {
+    let events = teams::update_flags(lifecycle.flags_mut(), tanks, now);
+    for event in &events {
+        if event.kind == FlagEventKind::Capture {
+            lifecycle.add_team_score(event.flag_team.opponent(), 1);
+        }
+    }
+    events
+}
+
+/// Moves capture progress on every zone and, once `score_elapsed` (seconds
+/// since held zones last scored) reaches the scoring interval, gives each
+/// team its held zones' points.
+pub fn update_zones(
+    zones: &mut [CaptureZone],
+    score_elapsed: &mut f32,
+    lifecycle: &mut MatchLifecycle,
+    tanks: &[Tank],
+    dt: f32,
+) -> Vec<ZoneEvent> {
+    let events = zones::update_zones(zones, tanks, dt);
+    *score_elapsed += dt;
+    if *score_elapsed >= ZONE_SCORE_INTERVAL_SECS {
+        *score_elapsed -= ZONE_SCORE_INTERVAL_SECS;
+        for (team, points) in held_zone_points(zones) {
+            lifecycle.add_team_score(team, points);
+        }
+    }
+    events
+}
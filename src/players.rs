@@ -0,0 +1,73 @@
+//! The server's record of who is playing: each player's statistics for the
+//! current match next to their lifetime profile, both credited from the
+//! shots and hits the simulation reports.
+
+use std::collections::HashMap;
+
+use crate::profiles::Profile;
+use crate::{HitEvent, PlayerStats, ScoreboardEntry, Team};
+
+pub struct Player {
+    pub name: String,
+    pub user_id: String,
+    pub team: Option<Team>,
+    /// This match only; cleared when the next one starts
+    pub stats: PlayerStats,
+    /// Server time the connection dropped; the tank sits frozen until the
+    /// player rejoins or the grace period runs out
+    pub disconnected_at: Option<u64>,
+    /// Lifetime record, saved to the profile store
+    pub profile: Profile,
+}
+
+impl Player {
+    pub fn record_shot(&mut self) {
+        self.stats.shots_fired += 1;
+        self.profile.shots_fired += 1;
+    }
+}
+
+/// Credits hits and kills to attackers and deaths to their targets, keyed
+/// by tank id. NPCs have no stats, but kills of and by them still count for
+/// players.
+pub fn record_hits(players: &mut HashMap<String, Player>, hits: &[HitEvent]) {
+    for hit in hits {
+        if let Some(attacker) = players.get_mut(&hit.attacker_id) {
+            attacker.stats.hits += 1;
+            attacker.profile.hits += 1;
+            if hit.killed {
+                attacker.stats.kills += 1;
+                attacker.profile.kills += 1;
+            }
+        }
+        if hit.killed {
+            if let Some(target) = players.get_mut(&hit.target_id) {
+                target.stats.deaths += 1;
+                target.profile.deaths += 1;
+            }
+        }
+    }
+}
+
+/// Every player's statistics, most kills first, then fewest deaths, then
+/// by name.
+pub fn scoreboard_entries(players: &HashMap<String, Player>) -> Vec<ScoreboardEntry> {
+    let mut entries: Vec<ScoreboardEntry> = players
+        .iter()
+        .map(|(player_id, player)| ScoreboardEntry {
+            player_id: player_id.clone(),
+            name: player.name.clone(),
+            team: player.team,
+            stats: player.stats.clone(),
+            accuracy: player.stats.accuracy(),
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.stats
+            .kills
+            .cmp(&a.stats.kills)
+            .then(a.stats.deaths.cmp(&b.stats.deaths))
+            .then(a.name.cmp(&b.name))
+    });
+    entries
+}
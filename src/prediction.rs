@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use battlexone_shared::*;
+use battlexone_shared::client_prediction::Prediction;
+use crate::game_state::{GameStateResource, PlayerInfo};
+
+/// Prediction for the local tank, started over on every join.
+#[derive(Resource, Default)]
+pub struct PredictionState {
+    prediction: Prediction,
+    // The join this prediction belongs to
+    joins: u64,
+}
+
+impl PredictionState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_input(&mut self, seq: u32, input: u16) {
+        self.prediction.record_input(seq, input);
+    }
+
+    /// Replaces the local tank in `tanks` with its predicted state.
+    pub fn apply_to(&self, tanks: &mut [Tank]) {
+        self.prediction.apply_to(tanks);
+    }
+}
+
+/// Runs on `FixedUpdate` after `send_input` so the input sent this tick is
+/// already part of the replay.
+pub fn reconcile_prediction(
+    mut prediction: ResMut<PredictionState>,
+    game_state: Res<GameStateResource>,
+    player_info: Res<PlayerInfo>,
+) {
+    // Drop everything predicted on an earlier connection, at the tick
+    // length of the server we're on now
+    let joins = *game_state.get_joins().lock().unwrap();
+    if joins != prediction.joins {
+        let tick_rate = *game_state.get_tick_rate().lock().unwrap();
+        prediction.prediction = Prediction::new(tick_rate);
+        prediction.joins = joins;
+    }
+
+    let Some(player_id) = player_info.get_player_id() else {
+        return;
+    };
+
+    let game_data = game_state.get_data();
+    let state = game_data.lock().unwrap();
+    let Some(tank) = state.tanks.iter().find(|t| t.id == player_id) else {
+        return;
+    };
+    let acked = state.last_processed_input.get(&player_id).copied().unwrap_or(0);
+
+    let map = game_state.get_map();
+    let map = map.lock().unwrap();
+    prediction.prediction.reconcile(tank, acked, &map);
+}
@@ -0,0 +1,178 @@
+//! Player profiles that outlive the server: display name, lifetime stats and
+//! preferred settings, keyed by user id. Stores are pluggable; the one the
+//! server uses on disk is an append-only file of JSON lines, one profile per
+//! line with the latest line for a user winning.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rating::DEFAULT_RATING;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub user_id: String,
+    /// Name the player last joined with
+    pub name: String,
+    #[serde(default)]
+    pub kills: u64,
+    #[serde(default)]
+    pub deaths: u64,
+    #[serde(default)]
+    pub shots_fired: u64,
+    #[serde(default)]
+    pub hits: u64,
+    /// Matches the player was around for the end of
+    #[serde(default)]
+    pub matches_played: u64,
+    /// Whatever the client asks to have remembered
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+    /// Elo skill rating
+    #[serde(default = "default_rating")]
+    pub rating: f64,
+    /// Matches that counted towards the rating
+    #[serde(default)]
+    pub rated_matches: u64,
+}
+
+fn default_rating() -> f64 {
+    DEFAULT_RATING
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            user_id: String::new(),
+            name: String::new(),
+            kills: 0,
+            deaths: 0,
+            shots_fired: 0,
+            hits: 0,
+            matches_played: 0,
+            settings: BTreeMap::new(),
+            rating: DEFAULT_RATING,
+            rated_matches: 0,
+        }
+    }
+}
+
+impl Profile {
+    pub fn new(user_id: &str, name: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Somewhere profiles are kept between runs.
+pub trait ProfileStore: Send {
+    /// The saved profile for `user_id`, if there is one.
+    fn load(&self, user_id: &str) -> Option<Profile>;
+
+    fn save(&mut self, profile: &Profile) -> io::Result<()>;
+
+    /// Every saved profile, in no particular order.
+    fn all(&self) -> Vec<Profile>;
+}
+
+/// Profiles kept only for as long as the server runs.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    profiles: HashMap<String, Profile>,
+}
+
+impl ProfileStore for MemoryStore {
+    fn load(&self, user_id: &str) -> Option<Profile> {
+        self.profiles.get(user_id).cloned()
+    }
+
+    fn save(&mut self, profile: &Profile) -> io::Result<()> {
+        self.profiles.insert(profile.user_id.clone(), profile.clone());
+        Ok(())
+    }
+
+    fn all(&self) -> Vec<Profile> {
+        self.profiles.values().cloned().collect()
+    }
+}
+
+/// Profiles appended to a file of JSON lines. Opening it compacts the file
+/// down to the latest line per user.
+#[derive(Debug)]
+pub struct JsonLinesStore {
+    profiles: HashMap<String, Profile>,
+    file: File,
+}
+
+impl JsonLinesStore {
+    /// Opens the store at `path`, creating it if it doesn't exist. Lines that
+    /// don't parse, such as one cut short by a crash, are skipped.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut profiles = HashMap::new();
+        match File::open(path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    if let Ok(profile) = serde_json::from_str::<Profile>(&line?) {
+                        profiles.insert(profile.user_id.clone(), profile);
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        // Rewrite beside the old file and swap, so a crash leaves one whole
+        let mut compacted = PathBuf::from(path);
+        compacted.as_mut_os_string().push(".tmp");
+        {
+            let mut out = File::create(&compacted)?;
+            let mut sorted: Vec<&Profile> = profiles.values().collect();
+            sorted.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+            for profile in sorted {
+                writeln!(out, "{}", serde_json::to_string(profile)?)?;
+            }
+            out.sync_all()?;
+        }
+        fs::rename(&compacted, path)?;
+
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(Self { profiles, file })
+    }
+
+    /// Number of users with a profile.
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+}
+
+impl ProfileStore for JsonLinesStore {
+    fn load(&self, user_id: &str) -> Option<Profile> {
+        self.profiles.get(user_id).cloned()
+    }
+
+    /// Appends `profile` unless it's unchanged since it was last saved.
+    fn save(&mut self, profile: &Profile) -> io::Result<()> {
+        if self.profiles.get(&profile.user_id) == Some(profile) {
+            return Ok(());
+        }
+        writeln!(self.file, "{}", serde_json::to_string(profile)?)?;
+        self.file.flush()?;
+        self.profiles.insert(profile.user_id.clone(), profile.clone());
+        Ok(())
+    }
+
+    fn all(&self) -> Vec<Profile> {
+        self.profiles.values().cloned().collect()
+    }
+}
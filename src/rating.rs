@@ -0,0 +1,155 @@
+//! Elo skill ratings from match results. A free-for-all counts as a round
+//! of one-on-one games between every pair of players, decided by who
+//! finished higher; a team match is one game between the teams' average
+//! ratings, with every member moving by their team's change.
+
+use std::collections::HashMap;
+
+use crate::players::Player;
+use crate::profiles::Profile;
+use crate::{LeaderboardEntry, MatchState, Team};
+
+/// Rating a new player starts on
+pub const DEFAULT_RATING: f64 = 1500.0;
+/// Largest change one game can make
+pub const K_FACTOR: f64 = 32.0;
+
+/// Chance a player rated `rating` beats one rated `opponent`, counting a
+/// draw as half a win.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Change to `rating` after `games`, each an opponent's rating and the
+/// score against them (1 win, 0.5 draw, 0 loss).
+pub fn rating_change(rating: f64, games: &[(f64, f64)], k: f64) -> f64 {
+    k * games
+        .iter()
+        .map(|&(opponent, score)| score - expected_score(rating, opponent))
+        .sum::<f64>()
+}
+
+/// New ratings after a free-for-all. `players` are each one's rating and
+/// finishing place (0 first, equal places a tie), and come back in the same
+/// order. The K-factor is shared out over the pairings, so a big match
+/// moves ratings no more than a duel does.
+pub fn free_for_all(players: &[(f64, u32)]) -> Vec<f64> {
+    if players.len() < 2 {
+        return players.iter().map(|&(rating, _)| rating).collect();
+    }
+    let k = K_FACTOR / (players.len() - 1) as f64;
+    players
+        .iter()
+        .enumerate()
+        .map(|(i, &(rating, place))| {
+            let games: Vec<(f64, f64)> = players
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, &(opponent, their_place))| {
+                    let score = match place.cmp(&their_place) {
+                        std::cmp::Ordering::Less => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Greater => 0.0,
+                    };
+                    (opponent, score)
+                })
+                .collect();
+            rating + rating_change(rating, &games, k)
+        })
+        .collect()
+}
+
+/// New ratings for both sides after a team match that `first` scored
+/// `score` in (1 win, 0.5 draw, 0 loss). Nothing changes if a side is
+/// empty.
+pub fn team_match(first: &[f64], second: &[f64], score: f64) -> (Vec<f64>, Vec<f64>) {
+    if first.is_empty() || second.is_empty() {
+        return (first.to_vec(), second.to_vec());
+    }
+    let average = |team: &[f64]| team.iter().sum::<f64>() / team.len() as f64;
+    let (a, b) = (average(first), average(second));
+    let first_change = rating_change(a, &[(b, score)], K_FACTOR);
+    let second_change = rating_change(b, &[(a, 1.0 - score)], K_FACTOR);
+    (
+        first.iter().map(|r| r + first_change).collect(),
+        second.iter().map(|r| r + second_change).collect(),
+    )
+}
+
+/// New ratings, by player id, for everyone still on the server at the end
+/// of the match in `state`: by finishing place in free-for-all, by the team
+/// result in team modes. Nobody is rated without an opponent.
+pub fn rate_match(state: &MatchState, players: &HashMap<String, Player>) -> Vec<(String, f64)> {
+    if state.mode.has_teams() {
+        let score_of = |team| state.team_scores.iter().find(|s| s.team == team).map_or(0, |s| s.score);
+        let outcome = match score_of(Team::Red).cmp(&score_of(Team::Blue)) {
+            std::cmp::Ordering::Greater => 1.0,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Less => 0.0,
+        };
+        let members = |team| {
+            players
+                .iter()
+                .filter(|(_, p)| p.team == Some(team))
+                .map(|(id, p)| (id.clone(), p.profile.rating))
+                .collect::<Vec<_>>()
+        };
+        let (red, blue) = (members(Team::Red), members(Team::Blue));
+        if red.is_empty() || blue.is_empty() {
+            return Vec::new();
+        }
+        let ratings = |team: &[(String, f64)]| team.iter().map(|(_, r)| *r).collect::<Vec<_>>();
+        let (new_red, new_blue) = team_match(&ratings(&red), &ratings(&blue), outcome);
+        return red
+            .into_iter()
+            .map(|(id, _)| id)
+            .zip(new_red)
+            .chain(blue.into_iter().map(|(id, _)| id).zip(new_blue))
+            .collect();
+    }
+
+    // Final standings are best first; equal kills and deaths tie
+    let standings: Vec<(String, f64, (u32, u32))> = state
+        .results
+        .iter()
+        .filter_map(|entry| {
+            let player = players.get(&entry.player_id)?;
+            Some((entry.player_id.clone(), player.profile.rating, (entry.stats.kills, entry.stats.deaths)))
+        })
+        .collect();
+    if standings.len() < 2 {
+        return Vec::new();
+    }
+    let mut places = Vec::with_capacity(standings.len());
+    for (i, (_, rating, record)) in standings.iter().enumerate() {
+        let place = match places.last() {
+            Some(&(_, last_place)) if standings[i - 1].2 == *record => last_place,
+            _ => i as u32,
+        };
+        places.push((*rating, place));
+    }
+    standings.into_iter().map(|(id, _, _)| id).zip(free_for_all(&places)).collect()
+}
+
+/// The top `size` rated players. `live` are the profiles of players still
+/// on the server, which are newer than their `saved` copies.
+pub fn leaderboard(saved: Vec<Profile>, live: Vec<Profile>, size: usize) -> Vec<LeaderboardEntry> {
+    let mut profiles: HashMap<String, Profile> = saved.into_iter().map(|p| (p.user_id.clone(), p)).collect();
+    profiles.extend(live.into_iter().map(|p| (p.user_id.clone(), p)));
+    let mut profiles: Vec<Profile> = profiles.into_values().filter(|p| p.rated_matches > 0).collect();
+    profiles.sort_by(|a, b| b.rating.total_cmp(&a.rating).then(a.name.cmp(&b.name)));
+    profiles
+        .into_iter()
+        .take(size)
+        .enumerate()
+        .map(|(i, p)| LeaderboardEntry {
+            rank: i as u32 + 1,
+            name: p.name,
+            rating: p.rating,
+            rated_matches: p.rated_matches,
+            kills: p.kills,
+            deaths: p.deaths,
+        })
+        .collect()
+}
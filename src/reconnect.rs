@@ -0,0 +1,56 @@
+//! Picking up where a dropped connection left off. The server holds on to a
+//! disconnected player's tank, team and score for a grace period, and a
+//! rejoin carrying the same user id gets them back; clients keep retrying
+//! with a growing delay in the meantime.
+
+use std::time::Duration;
+
+/// How long a disconnected player's tank is kept, in milliseconds
+pub const DEFAULT_RECONNECT_GRACE_MS: u64 = 30_000;
+/// First retry delay after losing the connection, in milliseconds
+pub const RECONNECT_INITIAL_DELAY_MS: u64 = 500;
+/// Retry delays stop growing here, in milliseconds
+pub const RECONNECT_MAX_DELAY_MS: u64 = 10_000;
+
+/// Whether a player who disconnected at server time `disconnected_at` has
+/// been gone longer than `grace_ms` by `now`.
+pub fn grace_expired(disconnected_at: u64, now: u64, grace_ms: u64) -> bool {
+    now.saturating_sub(disconnected_at) >= grace_ms
+}
+
+/// Delays between reconnection attempts, doubling after each failure up to
+/// a ceiling.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial_ms: u64,
+    max_ms: u64,
+    next_ms: u64,
+}
+
+impl Backoff {
+    pub fn new(initial_ms: u64, max_ms: u64) -> Self {
+        Self {
+            initial_ms,
+            max_ms,
+            next_ms: initial_ms.min(max_ms),
+        }
+    }
+
+    /// How long to wait before the next attempt.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next_ms;
+        self.next_ms = self.next_ms.saturating_mul(2).clamp(1, self.max_ms);
+        Duration::from_millis(delay)
+    }
+
+    /// Back to the shortest delay, after a connection that worked.
+    pub fn reset(&mut self) {
+        self.next_ms = self.initial_ms.min(self.max_ms);
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(RECONNECT_INITIAL_DELAY_MS, RECONNECT_MAX_DELAY_MS)
+    }
+}
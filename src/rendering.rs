@@ -1,8 +1,11 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use std::collections::HashMap;
 use battlexone_shared::*;
+use battlexone_shared::map::Map;
 use crate::game_state::{GameStateResource, PlayerInfo};
 use crate::tank_model::{TankEntity, TankTurret, spawn_tank, TankModel};
+use crate::prediction::PredictionState;
 
 #[derive(Component)]
 pub struct BulletEntity {
@@ -14,6 +17,18 @@ pub struct PlayerIndicator {
     pub tank_id: String,
 }
 
+#[derive(Component)]
+pub struct FlagMarker {
+    pub team: Team,
+}
+
+fn team_color(team: Team) -> Color {
+    match team {
+        Team::Red => Color::srgb(1.0, 0.25, 0.25),
+        Team::Blue => Color::srgb(0.25, 0.45, 1.0),
+    }
+}
+
 pub fn setup_rendering(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -52,25 +67,44 @@ pub fn setup_rendering(
     });
 }
 
+type BulletFilter = (Without<TankEntity>, Without<TankTurret>);
+type IndicatorFilter = (Without<TankEntity>, Without<BulletEntity>, Without<TankTurret>);
+
+/// The entities drawn for tanks and bullets; the filters keep the
+/// `Transform` borrows apart.
+#[derive(SystemParam)]
+pub struct GameEntities<'w, 's> {
+    tanks: Query<'w, 's, (Entity, &'static mut Transform, &'static TankEntity)>,
+    turrets: Query<'w, 's, (Entity, &'static mut Transform, &'static TankTurret), Without<TankEntity>>,
+    bullets: Query<'w, 's, (Entity, &'static mut Transform, &'static BulletEntity), BulletFilter>,
+    indicators: Query<'w, 's, (Entity, &'static mut Transform, &'static PlayerIndicator), IndicatorFilter>,
+}
+
+/// What new tanks, bullets and indicators are built from.
+#[derive(SystemParam)]
+pub struct EntityAssets<'w> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    tank_model: Res<'w, TankModel>,
+}
+
 pub fn update_game_entities(
     mut commands: Commands,
     game_state: Res<GameStateResource>,
     player_info: Res<PlayerInfo>,
-    mut tank_query: Query<(Entity, &mut Transform, &TankEntity)>,
-    mut turret_query: Query<(Entity, &mut Transform, &TankTurret), Without<TankEntity>>,
-    mut bullet_query: Query<(Entity, &mut Transform, &BulletEntity), (Without<TankEntity>, Without<TankTurret>)>,
-    mut indicator_query: Query<(Entity, &mut Transform, &PlayerIndicator), (Without<TankEntity>, Without<BulletEntity>, Without<TankTurret>)>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    tank_model: Res<TankModel>,
+    prediction: Res<PredictionState>,
+    mut entities: GameEntities,
+    mut assets: EntityAssets,
 ) {
-    // Get current game state
-    let game_data = game_state.get_data();
-    let (tanks, bullets) = {
-        let data = game_data.lock().unwrap();
-        data.clone()
+    // Remote entities are drawn interpolated between buffered snapshots
+    let snapshots = game_state.get_snapshots();
+    let Some((mut tanks, bullets)) = snapshots.lock().unwrap().sample() else {
+        return; // No data yet
     };
     
+    // Draw our own tank where prediction says it is, not a round-trip behind
+    prediction.apply_to(&mut tanks);
+    
     if tanks.is_empty() {
         return; // No data yet
     }
@@ -84,16 +118,16 @@ pub fn update_game_entities(
     let mut existing_indicators: HashMap<String, Entity> = HashMap::new();
     
     // Collect existing entities
-    for (entity, _transform, tank_entity) in tank_query.iter() {
+    for (entity, _transform, tank_entity) in entities.tanks.iter() {
         existing_tanks.insert(tank_entity.tank_id.clone(), entity);
     }
-    for (entity, _transform, turret_entity) in turret_query.iter() {
+    for (entity, _transform, turret_entity) in entities.turrets.iter() {
         existing_turrets.insert(turret_entity.tank_id.clone(), entity);
     }
-    for (entity, _transform, bullet_entity) in bullet_query.iter() {
+    for (entity, _transform, bullet_entity) in entities.bullets.iter() {
         existing_bullets.insert(bullet_entity.bullet_id.clone(), entity);
     }
-    for (entity, _transform, indicator) in indicator_query.iter() {
+    for (entity, _transform, indicator) in entities.indicators.iter() {
         existing_indicators.insert(indicator.tank_id.clone(), entity);
     }
     
@@ -114,7 +148,9 @@ pub fn update_game_entities(
             continue; // Move to the next tank
         }
 
-        let color = if tank.is_player && current_player_id.as_ref() == Some(&tank.id) {
+        let color = if let Some(team) = tank.team {
+            team_color(team) // Team colours in team modes
+        } else if tank.is_player && current_player_id.as_ref() == Some(&tank.id) {
             Color::srgb(0.2, 0.6, 1.0) // Blue for player
         } else if tank.is_player {
             Color::srgb(0.2, 1.0, 0.2) // Green for other players
@@ -124,14 +160,14 @@ pub fn update_game_entities(
         
         if let Some(entity) = existing_tanks.get(&tank.id) {
             // Update existing tank body
-            if let Ok((_, mut transform, _)) = tank_query.get_mut(*entity) {
+            if let Ok((_, mut transform, _)) = entities.tanks.get_mut(*entity) {
                 transform.translation = Vec3::new(tank.position.x, 0.0, tank.position.y);
                 transform.rotation = Quat::from_rotation_y(-tank.rotation + std::f32::consts::FRAC_PI_2);
             }
             
             // Update existing turret rotation and position
             if let Some(turret_entity) = existing_turrets.get(&tank.id) {
-                if let Ok((_, mut turret_transform, _)) = turret_query.get_mut(*turret_entity) {
+                if let Ok((_, mut turret_transform, _)) = entities.turrets.get_mut(*turret_entity) {
                     // Update turret position to match body
                     turret_transform.translation = Vec3::new(tank.position.x, 0.0, tank.position.y);
                     // Set turret rotation independently
@@ -142,7 +178,7 @@ pub fn update_game_entities(
             // Create new tank with 3D model
             let (body, turret) = spawn_tank(
                 &mut commands,
-                &tank_model,
+                &assets.tank_model,
                 Vec3::new(tank.position.x, 0.0, tank.position.y),
                 tank.rotation,
                 tank.turret_rotation,
@@ -159,15 +195,15 @@ pub fn update_game_entities(
         
         if let Some(entity) = existing_indicators.get(&tank.id) {
             // Update existing indicator
-            if let Ok((_, mut transform, _)) = indicator_query.get_mut(*entity) {
+            if let Ok((_, mut transform, _)) = entities.indicators.get_mut(*entity) {
                 *transform = indicator_transform;
             }
         } else {
             // Create new indicator
             commands.spawn((
                 PbrBundle {
-                    mesh: meshes.add(Sphere::new(0.8)),
-                    material: materials.add(StandardMaterial {
+                    mesh: assets.meshes.add(Sphere::new(0.8)),
+                    material: assets.materials.add(StandardMaterial {
                         base_color: color,
                         emissive: LinearRgba::rgb(color.to_srgba().red * 0.5, color.to_srgba().green * 0.5, color.to_srgba().blue * 0.5),
                         ..default()
@@ -188,15 +224,15 @@ pub fn update_game_entities(
         
         if let Some(entity) = existing_bullets.get(&bullet.id) {
             // Update existing bullet
-            if let Ok((_, mut transform, _)) = bullet_query.get_mut(*entity) {
+            if let Ok((_, mut transform, _)) = entities.bullets.get_mut(*entity) {
                 *transform = bullet_transform;
             }
         } else {
             // Create new bullet
             commands.spawn((
                 PbrBundle {
-                    mesh: meshes.add(Sphere::new(1.5)), // Larger bullets
-                    material: materials.add(StandardMaterial {
+                    mesh: assets.meshes.add(Sphere::new(1.5)), // Larger bullets
+                    material: assets.materials.add(StandardMaterial {
                         base_color: Color::srgb(1.0, 1.0, 0.0),
                         emissive: LinearRgba::rgb(1.0, 1.0, 0.0), // Brighter glow
                         ..default()
@@ -216,10 +252,10 @@ pub fn update_game_entities(
         &mut commands,
         &tanks,
         &bullets,
-        &tank_query.iter().collect::<Vec<_>>(),
-        &turret_query.iter().collect::<Vec<_>>(),
-        &bullet_query.iter().collect::<Vec<_>>(),
-        &indicator_query.iter().collect::<Vec<_>>(),
+        &entities.tanks.iter().collect::<Vec<_>>(),
+        &entities.turrets.iter().collect::<Vec<_>>(),
+        &entities.bullets.iter().collect::<Vec<_>>(),
+        &entities.indicators.iter().collect::<Vec<_>>(),
     );
 }
 
@@ -266,13 +302,15 @@ fn cleanup_entities(
 pub fn update_camera(
     game_state: Res<GameStateResource>,
     player_info: Res<PlayerInfo>,
+    prediction: Res<PredictionState>,
     mut camera_query: Query<&mut Transform, With<Camera3d>>,
 ) {
     let game_data = game_state.get_data();
-    let (tanks, _) = {
+    let mut tanks = {
         let data = game_data.lock().unwrap();
-        data.clone()
+        data.tanks.clone()
     };
+    prediction.apply_to(&mut tanks);
     
     if tanks.is_empty() {
         return;
@@ -311,4 +349,240 @@ pub fn update_camera(
             }
         }
     }
-} 
\ No newline at end of file
+} 
+/// Draws capture-the-flag flags, carried ones above their carrier.
+pub fn update_flag_markers(
+    mut commands: Commands,
+    game_state: Res<GameStateResource>,
+    prediction: Res<PredictionState>,
+    mut flag_query: Query<(Entity, &mut Transform, &FlagMarker)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let flags = game_state
+        .get_match_state()
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|state| state.flags.clone())
+        .unwrap_or_default();
+    let mut tanks = game_state.get_data().lock().unwrap().tanks.clone();
+    prediction.apply_to(&mut tanks);
+
+    for (entity, _, marker) in flag_query.iter() {
+        if !flags.iter().any(|flag| flag.team == marker.team) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for flag in &flags {
+        let position = flag
+            .carrier_id
+            .as_ref()
+            .and_then(|carrier_id| tanks.iter().find(|t| &t.id == carrier_id))
+            .map_or(&flag.position, |carrier| &carrier.position);
+        let transform = Transform::from_xyz(position.x, 10.0, position.y);
+
+        match flag_query.iter_mut().find(|(_, _, marker)| marker.team == flag.team) {
+            Some((_, mut existing, _)) => *existing = transform,
+            None => {
+                let color = team_color(flag.team);
+                commands.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(Cuboid::new(3.0, 20.0, 3.0)),
+                        material: materials.add(StandardMaterial {
+                            base_color: color,
+                            emissive: color.to_linear() * 0.8,
+                            ..default()
+                        }),
+                        transform,
+                        ..default()
+                    },
+                    FlagMarker { team: flag.team },
+                ));
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ZoneRing {
+    pub name: String,
+}
+
+/// Disc inside a zone's ring that grows with the capturing team's progress
+#[derive(Component)]
+pub struct ZoneFill {
+    pub name: String,
+}
+
+fn zone_ring_color(zone: &CaptureZone) -> Color {
+    if zone.contested {
+        Color::srgb(1.0, 0.85, 0.2)
+    } else {
+        zone.owner.map_or(Color::srgb(0.8, 0.8, 0.8), team_color)
+    }
+}
+
+/// The rings and fills drawn for capture zones.
+#[derive(SystemParam)]
+pub struct ZoneEntities<'w, 's> {
+    rings: Query<'w, 's, (Entity, &'static Handle<StandardMaterial>, &'static ZoneRing)>,
+    fills: Query<'w, 's, (Entity, &'static mut Transform, &'static Handle<StandardMaterial>, &'static ZoneFill), Without<ZoneRing>>,
+}
+
+/// Draws king-of-the-hill and domination zones as rings on the ground,
+/// coloured by owner, with a fill showing capture progress.
+pub fn update_zone_rings(
+    mut commands: Commands,
+    game_state: Res<GameStateResource>,
+    mut entities: ZoneEntities,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let zones = game_state.get_data().lock().unwrap().zones.clone();
+
+    for (entity, _, ring) in entities.rings.iter() {
+        if !zones.iter().any(|zone| zone.name == ring.name) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for (entity, _, _, fill) in entities.fills.iter() {
+        if !zones.iter().any(|zone| zone.name == fill.name) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let flat = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+    for zone in &zones {
+        let ring_color = zone_ring_color(zone);
+        let fill_color = zone
+            .capturing_team
+            .map_or(Color::NONE, |team| team_color(team).with_alpha(0.35));
+        // Scaling to exactly zero makes the transform singular
+        let fill_scale = Vec3::splat(zone.progress.max(0.01));
+
+        match entities.rings.iter().find(|(_, _, ring)| ring.name == zone.name) {
+            Some((_, material, _)) => {
+                if let Some(material) = materials.get_mut(material) {
+                    material.base_color = ring_color;
+                    material.emissive = ring_color.to_linear() * 0.5;
+                }
+            }
+            None => {
+                commands.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(Annulus::new(zone.radius - 3.0, zone.radius)),
+                        material: materials.add(StandardMaterial {
+                            base_color: ring_color,
+                            emissive: ring_color.to_linear() * 0.5,
+                            ..default()
+                        }),
+                        transform: Transform::from_xyz(zone.position.x, 0.2, zone.position.y).with_rotation(flat),
+                        ..default()
+                    },
+                    ZoneRing { name: zone.name.clone() },
+                ));
+            }
+        }
+
+        match entities.fills.iter_mut().find(|(_, _, _, fill)| fill.name == zone.name) {
+            Some((_, mut transform, material, _)) => {
+                transform.scale = fill_scale;
+                if let Some(material) = materials.get_mut(material) {
+                    material.base_color = fill_color;
+                }
+            }
+            None => {
+                commands.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(Circle::new(zone.radius - 3.0)),
+                        material: materials.add(StandardMaterial {
+                            base_color: fill_color,
+                            alpha_mode: AlphaMode::Blend,
+                            unlit: true,
+                            ..default()
+                        }),
+                        transform: Transform::from_xyz(zone.position.x, 0.1, zone.position.y)
+                            .with_rotation(flat)
+                            .with_scale(fill_scale),
+                        ..default()
+                    },
+                    ZoneFill { name: zone.name.clone() },
+                ));
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MapObstacle;
+
+const WALL_HEIGHT: f32 = 12.0;
+const BOX_HEIGHT: f32 = 16.0;
+
+/// Spawns simple meshes for the walls and boxes of the map the server sent,
+/// replacing them if a different map arrives.
+pub fn update_map_obstacles(
+    mut commands: Commands,
+    game_state: Res<GameStateResource>,
+    mut spawned: Local<Option<Map>>,
+    obstacle_query: Query<Entity, With<MapObstacle>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let map = game_state.get_map().lock().unwrap().clone();
+    if spawned.as_ref() == Some(&map) {
+        return;
+    }
+    for entity in obstacle_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let wall_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.45, 0.45, 0.5),
+        ..default()
+    });
+    let box_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.55, 0.4, 0.25),
+        ..default()
+    });
+
+    for wall in &map.walls {
+        let (dx, dy) = (wall.to.x - wall.from.x, wall.to.y - wall.from.y);
+        let length = (dx * dx + dy * dy).sqrt() + wall.thickness; // Rounded ends become square
+        let centre = Vec3::new(
+            (wall.from.x + wall.to.x) / 2.0,
+            WALL_HEIGHT / 2.0,
+            (wall.from.y + wall.to.y) / 2.0,
+        );
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Cuboid::new(length, WALL_HEIGHT, wall.thickness)),
+                material: wall_material.clone(),
+                transform: Transform::from_translation(centre).with_rotation(Quat::from_rotation_y(-dy.atan2(dx))),
+                ..default()
+            },
+            MapObstacle,
+        ));
+    }
+
+    for map_box in &map.boxes {
+        let size = Vec3::new(map_box.max.x - map_box.min.x, BOX_HEIGHT, map_box.max.y - map_box.min.y);
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Cuboid::from_size(size)),
+                material: box_material.clone(),
+                transform: Transform::from_xyz(
+                    (map_box.min.x + map_box.max.x) / 2.0,
+                    BOX_HEIGHT / 2.0,
+                    (map_box.min.y + map_box.max.y) / 2.0,
+                ),
+                ..default()
+            },
+            MapObstacle,
+        ));
+    }
+
+    *spawned = Some(map);
+}
@@ -0,0 +1,39 @@
+//! Choosing where tanks spawn.
+//!
+//! The server scores each candidate spawn point by how far it is from the
+//! nearest living enemy and the nearest recent death, and picks the safest.
+
+use crate::Position;
+
+fn distance(a: &Position, b: &Position) -> f32 {
+    let dx = a.x - b.x;
+    let dy = a.y - b.y;
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// Distance from `point` to the closest of `others`, or infinity if there
+/// are none.
+fn clearance(point: &Position, others: &[Position]) -> f32 {
+    others
+        .iter()
+        .map(|other| distance(point, other))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Picks the candidate furthest from both `enemies` and `recent_deaths`.
+/// Ties go to the earlier candidate, so callers shuffle the candidates to
+/// spread out spawns on an empty map. Returns `None` without candidates.
+pub fn choose_spawn_point(
+    candidates: &[Position],
+    enemies: &[Position],
+    recent_deaths: &[Position],
+) -> Option<Position> {
+    let mut best: Option<(&Position, f32)> = None;
+    for candidate in candidates {
+        let score = clearance(candidate, enemies).min(clearance(candidate, recent_deaths));
+        if best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((candidate, score));
+        }
+    }
+    best.map(|(position, _)| position.clone())
+}
@@ -0,0 +1,64 @@
+//! Lag compensation. The server remembers where every tank stood over the
+//! last few ticks, so a shot can be tested against the world as the shooter
+//! saw it: the snapshot they last acknowledged, rather than the present,
+//! which their screen hasn't caught up with yet.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{Position, Tank};
+
+/// Tank positions over a window of recent ticks, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct TankHistory {
+    capacity: usize,
+    frames: VecDeque<(u64, HashMap<String, Position>)>,
+}
+
+impl TankHistory {
+    /// A history keeping the last `capacity` ticks.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Remembers where `tanks` are at the end of `tick`, dropping the oldest
+    /// tick once the window is full.
+    pub fn record(&mut self, tick: u64, tanks: &[Tank]) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        let positions = tanks.iter().map(|t| (t.id.clone(), t.position.clone())).collect();
+        self.frames.push_back((tick, positions));
+    }
+
+    /// `tanks` moved back to where they stood at `tick`, in the same order.
+    /// Tanks that weren't around then stay where they are now. `None` when
+    /// `tick` has already left the window.
+    pub fn rewound(&self, tanks: &[Tank], tick: u64) -> Option<Vec<Tank>> {
+        let (_, positions) = self.frames.iter().find(|(t, _)| *t == tick)?;
+        Some(
+            tanks
+                .iter()
+                .map(|tank| {
+                    let mut tank = tank.clone();
+                    if let Some(position) = positions.get(&tank.id) {
+                        tank.position = position.clone();
+                    }
+                    tank
+                })
+                .collect(),
+        )
+    }
+}
+
+/// How many ticks to rewind a shot fired at `current_tick` by a client whose
+/// latest acknowledged snapshot is `acked_tick`, capped at `max_ticks`. No
+/// acknowledgement means nothing to rewind to.
+pub fn rewind_ticks(current_tick: u64, acked_tick: Option<u64>, max_ticks: u64) -> u64 {
+    acked_tick.map_or(0, |acked| current_tick.saturating_sub(acked).min(max_ticks))
+}
@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use battlexone_shared::{ClientMessage, LeaderboardEntry, MatchPhase, ScoreboardEntry, Team};
+use crate::game_state::{GameStateResource, PlayerInfo};
+use crate::network::WebSocketSender;
+
+#[derive(Component)]
+pub struct ScoreboardOverlay;
+
+#[derive(Component)]
+pub struct LeaderboardOverlay;
+
+pub fn setup_scoreboard(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(80.0),
+            left: Val::Px(200.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            ..default()
+        })
+        .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+        Visibility::Hidden,
+        ScoreboardOverlay,
+    ));
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(80.0),
+            left: Val::Px(200.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            ..default()
+        })
+        .with_background_color(Color::srgba(0.0, 0.0, 0.2, 0.85)),
+        Visibility::Hidden,
+        LeaderboardOverlay,
+    ));
+}
+
+/// Shows the scoreboard while Tab is held, and the final results at the end
+/// of a match.
+pub fn update_scoreboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    game_state: Res<GameStateResource>,
+    player_info: Res<PlayerInfo>,
+    mut query: Query<(&mut Text, &mut Visibility), With<ScoreboardOverlay>>,
+) {
+    let Ok((mut text, mut visibility)) = query.get_single_mut() else {
+        return;
+    };
+
+    let player_id = player_info.get_player_id();
+    let server_time = game_state.get_data().lock().unwrap().server_time;
+    let match_state = game_state.get_match_state();
+    let match_state = match_state.lock().unwrap();
+
+    let contents = match match_state.as_ref() {
+        Some(state) if state.phase == MatchPhase::Results => {
+            let next_in = state
+                .phase_ends_at
+                .map_or(0, |ends_at| ends_at.saturating_sub(server_time).div_ceil(1000));
+            format!(
+                "Match {} over - {} wins!\n\n{}\nNext match in {}...",
+                state.match_number,
+                state.winner.as_deref().unwrap_or("nobody"),
+                format_table(&state.results, player_id.as_deref()),
+                next_in
+            )
+        }
+        _ if keyboard_input.pressed(KeyCode::Tab) => {
+            format_table(&game_state.get_scoreboard().lock().unwrap(), player_id.as_deref())
+        }
+        _ => {
+            *visibility = Visibility::Hidden;
+            return;
+        }
+    };
+    *visibility = Visibility::Inherited;
+
+    if text.sections[0].value != contents {
+        text.sections[0].value = contents;
+    }
+}
+
+fn format_table(entries: &[ScoreboardEntry], player_id: Option<&str>) -> String {
+    let mut table = format!(
+        "{:<20} {:>6} {:>6} {:>6} {:>6} {:>8}\n",
+        "Player", "Kills", "Deaths", "Shots", "Hits", "Accuracy"
+    );
+    for entry in entries {
+        let marker = if player_id == Some(entry.player_id.as_str()) { "> " } else { "  " };
+        let team = match entry.team {
+            Some(Team::Red) => "[R] ",
+            Some(Team::Blue) => "[B] ",
+            None => "",
+        };
+        let name: String = team.chars().chain(entry.name.chars()).take(18).collect();
+        table.push_str(&format!(
+            "{}{:<18} {:>6} {:>6} {:>6} {:>6} {:>7.0}%\n",
+            marker,
+            name,
+            entry.stats.kills,
+            entry.stats.deaths,
+            entry.stats.shots_fired,
+            entry.stats.hits,
+            entry.accuracy * 100.0
+        ));
+    }
+    table
+}
+
+/// Toggles the skill leaderboard with L, asking the server for the latest
+/// standings each time it opens.
+pub fn update_leaderboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    game_state: Res<GameStateResource>,
+    ws_sender: Res<WebSocketSender>,
+    mut open: Local<bool>,
+    mut query: Query<(&mut Text, &mut Visibility), With<LeaderboardOverlay>>,
+) {
+    let Ok((mut text, mut visibility)) = query.get_single_mut() else {
+        return;
+    };
+
+    if keyboard_input.just_pressed(KeyCode::KeyL) {
+        *open = !*open;
+        if *open {
+            if let Some(sender) = ws_sender.sender.lock().unwrap().as_ref() {
+                let _ = sender.send(ClientMessage::RequestLeaderboard);
+            }
+        }
+    }
+    if !*open {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let contents = format_leaderboard(&game_state.get_leaderboard().lock().unwrap());
+    if text.sections[0].value != contents {
+        text.sections[0].value = contents;
+    }
+}
+
+fn format_leaderboard(entries: &[LeaderboardEntry]) -> String {
+    let mut table = format!(
+        "Leaderboard (L to close)\n\n{:>4}  {:<18} {:>6} {:>7} {:>6} {:>6}\n",
+        "Rank", "Player", "Rating", "Matches", "Kills", "Deaths"
+    );
+    if entries.is_empty() {
+        table.push_str("\nNo rated matches yet\n");
+    }
+    for entry in entries {
+        let name: String = entry.name.chars().take(18).collect();
+        table.push_str(&format!(
+            "{:>4}  {:<18} {:>6.0} {:>7} {:>6} {:>6}\n",
+            entry.rank, name, entry.rating, entry.rated_matches, entry.kills, entry.deaths
+        ));
+    }
+    table
+}
@@ -1,297 +1,1098 @@
 use tokio::net::{TcpListener, TcpStream};
 use tokio_tungstenite::tungstenite::Message;
 use futures_util::{StreamExt, SinkExt};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 use battlexone_shared::*;
-use std::time::{SystemTime, UNIX_EPOCH};
+use battlexone_shared::auth::{AuthMode, TokenSigner};
+use battlexone_shared::bullets::{bullet_from, fire, update_bullets, BulletResults, BulletWorld};
+use battlexone_shared::collision::separate_tanks;
+use battlexone_shared::delta::GameStateDelta;
+use battlexone_shared::input_queue::InputQueue;
+use battlexone_shared::lifecycle::MatchLifecycle;
+use battlexone_shared::map::Map;
+use battlexone_shared::movement::apply_movement;
+use battlexone_shared::nav::NavGrid;
+use battlexone_shared::npc::NpcBrain;
+use battlexone_shared::npcs::{random_npc_target, update_npcs, NpcWorld};
+use battlexone_shared::objectives;
+use battlexone_shared::players::{record_hits, scoreboard_entries, Player};
+use battlexone_shared::profiles::{Profile, ProfileStore};
+use battlexone_shared::rating::{leaderboard, rate_match};
+use battlexone_shared::reconnect::grace_expired;
+use battlexone_shared::respawn::choose_spawn_point;
+use battlexone_shared::rewind::{rewind_ticks, TankHistory};
+use battlexone_shared::server_config::{usage, Args, ServerConfig, Tuning, DEFAULT_CONFIG_PATH};
+use battlexone_shared::teams::{is_enemy, pick_team};
+use battlexone_shared::wire::{self, WireEncoding};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use rand::Rng;
+use rand::seq::SliceRandom;
 
-const NUM_NPCS: usize = 5;
-const NPC_SPAWN_RADIUS: f32 = 500.0;
-const BULLET_LIFETIME: u64 = 5000; // 5 seconds
-const WORLD_BOUNDS: f32 = 1000.0;
+// How often the config file is checked for tuning changes (seconds)
+const CONFIG_POLL_INTERVAL_SECS: u64 = 2;
+
+// Optional protocol features this server can use if a client offers them
+const SERVER_FEATURES: [Feature; 2] = [Feature::BinaryEncoding, Feature::DeltaSnapshots];
+
+// How often everyone is sent the scoreboard and match state (Hz)
+const SCOREBOARD_RATE: u32 = 1;
+
+// Snapshots kept for computing deltas; an ack older than this gets a keyframe
+const SNAPSHOT_HISTORY_LEN: usize = 32;
+
+// Respawning, in milliseconds of server time; delay and protection come
+// from the config
+const RECENT_DEATH_WINDOW_MS: u64 = 10_000; // deaths this recent repel spawns
+const NUM_SPAWN_POINTS: usize = 12;
+const SPAWN_POINT_RADIUS: f32 = 600.0;
+
+// Rows in the skill leaderboard
+const LEADERBOARD_SIZE: usize = 10;
+
+// Profiles are saved this often as well as on disconnect (seconds)
+const PROFILE_SAVE_INTERVAL_SECS: u64 = 30;
+
+struct TickConfig {
+    tick_rate: u32,
+    snapshot_rate: u32,
+}
+
+impl TickConfig {
+    fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            tick_rate: config.tick_rate,
+            snapshot_rate: config.snapshot_rate,
+        }
+    }
+
+    fn tick_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(1.0 / self.tick_rate as f64)
+    }
+
+    fn snapshot_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(1.0 / self.snapshot_rate as f64)
+    }
+}
+
+struct RespawnConfig {
+    delay_ms: u64,
+    protection_ms: u64,
+}
+
+impl RespawnConfig {
+    fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            delay_ms: config.match_.respawn_delay_ms,
+            protection_ms: config.match_.spawn_protection_ms,
+        }
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Who may join and how long they may be gone.
+struct SessionConfig {
+    // How long a disconnected player's tank waits for them to rejoin (ms)
+    reconnect_grace_ms: u64,
+    // Who may join without a session token, and the signer of those tokens
+    auth: AuthMode,
+    tokens: TokenSigner,
+}
+
+impl SessionConfig {
+    /// Only the secrets come from the environment: the server key in
+    /// `BATTLEXONE_SERVER_KEY` and the token secret in
+    /// `BATTLEXONE_TOKEN_SECRET`. A broken accounts file stops the server.
+    fn from_config(config: &ServerConfig) -> Self {
+        let auth = config
+            .auth_mode(std::env::var("BATTLEXONE_SERVER_KEY").ok())
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load accounts {}: {}", config.players.accounts, e);
+                std::process::exit(1);
+            });
+        match &auth {
+            AuthMode::Accounts(_) => println!("Private server, accounts from {}", config.players.accounts),
+            AuthMode::SharedKey(_) => println!("Private server, joining needs the server key"),
+            AuthMode::Open => {}
+        }
+        let secret = std::env::var("BATTLEXONE_TOKEN_SECRET").ok().filter(|s| !s.is_empty());
+        if secret.is_none() {
+            println!("No BATTLEXONE_TOKEN_SECRET; session tokens last until restart");
+        }
+        Self {
+            reconnect_grace_ms: config.players.reconnect_grace_ms,
+            auth,
+            tokens: config.token_signer(secret),
+        }
+    }
+}
 
 struct GameServer {
     players: Arc<Mutex<HashMap<String, Player>>>,
     tanks: Arc<Mutex<Vec<Tank>>>,
     bullets: Arc<Mutex<Vec<Bullet>>>,
-    connections: Arc<Mutex<HashMap<String, tokio::sync::mpsc::UnboundedSender<Message>>>>,
-    npc_targets: Arc<Mutex<HashMap<String, (f32, f32)>>>,
+    connections: Arc<Mutex<HashMap<String, Connection>>>,
+    npc_brains: Arc<Mutex<HashMap<String, NpcBrain>>>,
+    input_queues: Arc<Mutex<HashMap<String, InputQueue>>>,
+    current_tick: Arc<Mutex<u64>>,
+    started_at: Instant,
+    // Recently broadcast snapshots that client acks can refer back to
+    snapshot_history: Arc<Mutex<VecDeque<GameState>>>,
+    snapshot_acks: Arc<Mutex<HashMap<String, SnapshotAck>>>,
+    respawn: RespawnConfig,
+    spawn_points: Vec<Position>,
+    // Where and when (server time) tanks recently died, oldest first
+    recent_deaths: Arc<Mutex<VecDeque<(u64, Position)>>>,
+    match_lifecycle: Arc<Mutex<MatchLifecycle>>,
+    zones: Arc<Mutex<Vec<CaptureZone>>>,
+    // Seconds of play since held zones last scored
+    zone_score_elapsed: Arc<Mutex<f32>>,
+    mode: GameMode,
+    // Whether bullets hurt teammates in team modes
+    friendly_fire: bool,
+    map: Map,
+    // Walkable cells of the map, for NPC routes
+    nav: NavGrid,
+    // Where tanks stood over recent ticks, for lag compensation
+    tank_history: Arc<Mutex<TankHistory>>,
+    // Ticks into the past each player's bullet is tested against; bullets
+    // not listed are tested against the present
+    bullet_rewind: Arc<Mutex<HashMap<String, u64>>>,
+    max_rewind_ticks: u64,
+    sessions: SessionConfig,
+    // Where profiles are kept between runs; locked on its own
+    profiles: Arc<Mutex<Box<dyn ProfileStore>>>,
+    // Weapon and NPC numbers, swapped when the config file changes; locked
+    // on its own and copied out
+    tuning: Arc<Mutex<Tuning>>,
+    max_players: usize,
+    npc_spawn_radius: f32,
+    // Told to clients on join, so they send and predict at the same rate
+    tick_rate: u32,
 }
 
-struct Player {
-    name: String,
-    user_id: String,
-    tank_id: Option<String>,
+/// Which snapshot a client has last confirmed, i.e. what deltas to it are
+/// computed against.
+#[derive(Default)]
+struct SnapshotAck {
+    acked_tick: Option<u64>,
+    needs_keyframe: bool,
+}
+
+struct Connection {
+    sender: tokio::sync::mpsc::UnboundedSender<Message>,
+    encoding: WireEncoding,
+    delta_snapshots: bool,
 }
 
 impl GameServer {
-    fn new() -> Self {
+    fn new(map: Map, sessions: SessionConfig, profiles: Box<dyn ProfileStore>, config: &ServerConfig) -> Self {
         let mut rng = rand::thread_rng();
         let mut tanks = Vec::new();
-        let mut npc_targets = HashMap::new();
+        let mut npc_brains = HashMap::new();
+        let match_settings = config.match_.settings();
+        let mode = match_settings.mode;
+        let max_rewind_ticks = config.max_rewind_ticks();
         
-        // Spawn NPCs
-        for _ in 0..NUM_NPCS {
-            let angle = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
-            let distance = rng.gen_range(100.0..NPC_SPAWN_RADIUS);
-            let x = angle.cos() * distance;
-            let y = angle.sin() * distance;
+        // Spawn NPCs, split evenly between the teams in team modes
+        for i in 0..config.world.npc_count {
+            let patrol_target = random_npc_target(&mut rng, config.world.npc_spawn_radius);
+            let mut position = patrol_target.clone();
+            map.resolve_tank(&mut position);
             
             let npc_id = Uuid::new_v4().to_string();
             let tank = Tank {
                 id: npc_id.clone(),
-                position: Position { x, y },
+                position,
                 rotation: rng.gen_range(0.0..std::f32::consts::PI * 2.0),
                 turret_rotation: 0.0,
                 is_player: false,
-                health: 100,
+                health: TANK_MAX_HEALTH,
                 is_dead: false,
                 respawn_time: None,
                 last_fire_time: 0,
+                spawn_protected_until: None,
+                team: mode.has_teams().then_some(Team::ALL[i % Team::ALL.len()]),
             };
             
             tanks.push(tank);
-            npc_targets.insert(npc_id, (x, y));
+            npc_brains.insert(npc_id, NpcBrain::new(config.world.npc_difficulty, patrol_target));
         }
+
+        // The map's spawn points, or evenly spaced around the centre for maps
+        // that don't list any
+        let spawn_points = if map.spawn_points.is_empty() {
+            (0..NUM_SPAWN_POINTS)
+                .map(|i| {
+                    let angle = i as f32 / NUM_SPAWN_POINTS as f32 * std::f32::consts::PI * 2.0;
+                    Position {
+                        x: angle.cos() * SPAWN_POINT_RADIUS,
+                        y: angle.sin() * SPAWN_POINT_RADIUS,
+                    }
+                })
+                .filter(|point| !map.blocks_tank(point))
+                .collect()
+        } else {
+            map.spawn_points.clone()
+        };
         
         Self {
             players: Arc::new(Mutex::new(HashMap::new())),
             tanks: Arc::new(Mutex::new(tanks)),
             bullets: Arc::new(Mutex::new(Vec::new())),
             connections: Arc::new(Mutex::new(HashMap::new())),
-            npc_targets: Arc::new(Mutex::new(npc_targets)),
+            npc_brains: Arc::new(Mutex::new(npc_brains)),
+            input_queues: Arc::new(Mutex::new(HashMap::new())),
+            current_tick: Arc::new(Mutex::new(0)),
+            started_at: Instant::now(),
+            snapshot_history: Arc::new(Mutex::new(VecDeque::new())),
+            snapshot_acks: Arc::new(Mutex::new(HashMap::new())),
+            respawn: RespawnConfig::from_config(config),
+            spawn_points,
+            recent_deaths: Arc::new(Mutex::new(VecDeque::new())),
+            match_lifecycle: Arc::new(Mutex::new(MatchLifecycle::new(match_settings).with_flag_bases(map.flag_bases()))),
+            zones: Arc::new(Mutex::new(map.zones_for(mode))),
+            zone_score_elapsed: Arc::new(Mutex::new(0.0)),
+            mode,
+            friendly_fire: config.match_.friendly_fire,
+            nav: NavGrid::from_map(&map),
+            map,
+            tank_history: Arc::new(Mutex::new(TankHistory::new(max_rewind_ticks as usize + 1))),
+            bullet_rewind: Arc::new(Mutex::new(HashMap::new())),
+            max_rewind_ticks,
+            sessions,
+            profiles: Arc::new(Mutex::new(profiles)),
+            tuning: Arc::new(Mutex::new(config.tuning)),
+            max_players: config.max_players,
+            tick_rate: config.tick_rate,
+            npc_spawn_radius: config.world.npc_spawn_radius,
         }
     }
 
-    fn update_bullets(&self) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-        
-        let mut bullets = self.bullets.lock().unwrap();
-        
-        // Update bullet positions and remove old bullets
-        bullets.retain_mut(|bullet| {
-            // Check if bullet is too old
-            if now - bullet.created_at > BULLET_LIFETIME {
-                return false;
+    /// Milliseconds since the server started; the clock `GameState::server_time`,
+    /// `Tank::respawn_time` and `Tank::spawn_protected_until` are measured on.
+    fn server_time(&self) -> u64 {
+        self.started_at.elapsed().as_millis() as u64
+    }
+
+    /// Advances the authoritative world by one fixed timestep of `dt` seconds.
+    fn tick(&self, dt: f32) {
+        self.remove_departed_players();
+        self.update_match();
+
+        // Play is frozen while results are shown; inputs are still consumed
+        // so clients' acks keep advancing
+        let live = self.match_lifecycle.lock().unwrap().is_live();
+        self.apply_player_inputs(dt, live);
+        if live {
+            self.update_bullets(dt);
+            self.update_npcs(dt);
+            // After everyone has moved, so players and NPCs collide alike
+            separate_tanks(&mut self.tanks.lock().unwrap(), &self.map);
+            if self.mode == GameMode::CaptureTheFlag {
+                self.update_flags();
             }
-            
-            // Update position
-            bullet.position.x += bullet.velocity.x;
-            bullet.position.y += bullet.velocity.y;
-            
-            // Check if bullet is out of bounds
-            if bullet.position.x.abs() > WORLD_BOUNDS || bullet.position.y.abs() > WORLD_BOUNDS {
-                return false;
+            if self.mode.has_zones() {
+                self.update_zones(dt);
             }
-            
-            // Check for collisions with tanks
-            let mut tanks = self.tanks.lock().unwrap(); // Make tanks mutable
-            let mut collided = false; // Flag to track if bullet collided
-            for tank in tanks.iter_mut() { // Iterate mutably
-                // Diagnostic print
-                // println!("Checking bullet {} (pos: {:.2},{:.2}) against tank {} (pos: {:.2},{:.2}, dead: {})", 
-                //     bullet.id, bullet.position.x, bullet.position.y, 
-                //     tank.id, tank.position.x, tank.position.y, tank.is_dead);
-
-                if tank.id != bullet.owner_id && !tank.is_dead { // Don't hit yourself or already dead tanks
-                    let dx = bullet.position.x - tank.position.x;
-                    let dy = bullet.position.y - tank.position.y;
-                    let distance = (dx * dx + dy * dy).sqrt();
-                    
-                    // More diagnostic print
-                    // if distance < 100.0 { // Print if a bullet is even somewhat close
-                    //     println!("Bullet {} is {} units from tank {}. Owner: {}, Tank Dead: {}", 
-                    //         bullet.id, distance, tank.id, bullet.owner_id, tank.is_dead);
-                    // }
-
-                    if distance < 30.0 { // Tank hitbox radius
-                        println!("Tank {} hit by bullet {}", tank.id, bullet.id);
-                        tank.is_dead = true; // One-hit kill
-                        // tank.health = 0; // Optional: if you keep health for score or other mechanics
-                        collided = true;
-                        break; // Bullet is consumed by one tank
-                    }
+        }
+        self.respawn_tanks();
+        let tick = {
+            let mut current_tick = self.current_tick.lock().unwrap();
+            *current_tick += 1;
+            *current_tick
+        };
+        // Recorded under the number the next snapshot will carry
+        self.tank_history.lock().unwrap().record(tick, &self.tanks.lock().unwrap());
+    }
+
+    /// Removes players whose grace period ran out without them rejoining,
+    /// along with their tanks.
+    fn remove_departed_players(&self) {
+        let now = self.server_time();
+        let departed: Vec<String> = {
+            let mut players = self.players.lock().unwrap();
+            let departed: Vec<String> = players
+                .iter()
+                .filter(|(_, p)| p.disconnected_at.is_some_and(|at| grace_expired(at, now, self.sessions.reconnect_grace_ms)))
+                .map(|(id, _)| id.clone())
+                .collect();
+            let mut profiles = Vec::new();
+            for id in &departed {
+                if let Some(player) = players.remove(id) {
+                    println!("{} did not come back in time", player.name);
+                    profiles.push(player.profile);
                 }
             }
-            
-            if collided {
-                return false; // Remove bullet if it collided
+            drop(players);
+            self.save_profiles(&profiles);
+            departed
+        };
+        if !departed.is_empty() {
+            self.tanks.lock().unwrap().retain(|t| !departed.contains(&t.id));
+        }
+    }
+
+    /// Writes `profiles` to the store, reporting rather than failing on errors.
+    fn save_profiles(&self, profiles: &[Profile]) {
+        let mut store = self.profiles.lock().unwrap();
+        for profile in profiles {
+            if let Err(e) = store.save(profile) {
+                eprintln!("Failed to save profile {}: {}", profile.user_id, e);
             }
-            
-            true
-        });
+        }
     }
 
-    fn update_npcs(&self) {
-        let mut rng = rand::thread_rng();
+    /// Saves every player's profile, connected or not.
+    fn save_all_profiles(&self) {
+        let profiles: Vec<Profile> = self.players.lock().unwrap().values().map(|p| p.profile.clone()).collect();
+        self.save_profiles(&profiles);
+    }
+
+    /// Moves the match through its phases, resetting the world when a new
+    /// warmup or match begins.
+    fn update_match(&self) {
+        let (players, leader_kills) = {
+            let players = self.players.lock().unwrap();
+            let connected = players.values().filter(|p| p.disconnected_at.is_none()).count();
+            (connected, players.values().map(|p| p.stats.kills).max().unwrap_or(0))
+        };
+        let entered = self
+            .match_lifecycle
+            .lock()
+            .unwrap()
+            .update(self.server_time(), players, leader_kills);
+        let Some(phase) = entered else {
+            return;
+        };
+
+        match phase {
+            MatchPhase::Warmup | MatchPhase::InProgress => self.reset_world(),
+            MatchPhase::Results => {
+                for player in self.players.lock().unwrap().values_mut() {
+                    player.profile.matches_played += 1;
+                }
+                let results = self.scoreboard_entries();
+                self.match_lifecycle.lock().unwrap().record_results(results);
+                self.update_ratings();
+                self.save_all_profiles();
+            }
+            MatchPhase::Lobby => {}
+        }
+
+        let state = self.match_lifecycle.lock().unwrap().state().clone();
+        println!("Match {} is now {:?}", state.match_number, state.phase);
+        if let Some(winner) = &state.winner {
+            println!("Match {} won by {}", state.match_number, winner);
+        }
+        self.send_to_all(&ServerMessage::MatchState(state));
+    }
+
+    /// Puts the finished match's new ratings on the players' profiles.
+    fn update_ratings(&self) {
+        let state = self.match_lifecycle.lock().unwrap().state().clone();
+        let mut players = self.players.lock().unwrap();
+        let rated = rate_match(&state, &players);
+        for (player_id, rating) in rated {
+            if let Some(player) = players.get_mut(&player_id) {
+                println!("{} is now rated {:.0} ({:+.1})", player.name, rating, rating - player.profile.rating);
+                player.profile.rating = rating;
+                player.profile.rated_matches += 1;
+            }
+        }
+    }
+
+    /// The best rated players who have played a rated match, best first.
+    fn leaderboard(&self) -> Vec<LeaderboardEntry> {
+        // Players still here are ahead of the store, which is only written
+        // on disconnect and at the end of a match
+        let live: Vec<Profile> = self.players.lock().unwrap().values().map(|p| p.profile.clone()).collect();
+        let saved = self.profiles.lock().unwrap().all();
+        leaderboard(saved, live, LEADERBOARD_SIZE)
+    }
+
+    /// Moves flags with their carriers and applies pickups, drops, returns
+    /// and captures, announcing each.
+    fn update_flags(&self) {
+        let now = self.server_time();
+        let (events, state) = {
+            let tanks = self.tanks.lock().unwrap();
+            let mut lifecycle = self.match_lifecycle.lock().unwrap();
+            let events = objectives::update_flags(&mut lifecycle, &tanks, now);
+            (events, lifecycle.state().clone())
+        };
+        if events.is_empty() {
+            return;
+        }
+
+        for event in events {
+            println!("{} flag {:?} by {:?}", event.flag_team.name(), event.kind, event.tank_id);
+            self.send_to_all(&ServerMessage::Flag(event));
+        }
+        self.send_to_all(&ServerMessage::MatchState(state));
+    }
+
+    /// Moves capture progress on every zone, announces zones changing hands
+    /// and scores held zones once per interval.
+    fn update_zones(&self, dt: f32) {
+        let events = {
+            let tanks = self.tanks.lock().unwrap();
+            let mut zones = self.zones.lock().unwrap();
+            let mut elapsed = self.zone_score_elapsed.lock().unwrap();
+            let mut lifecycle = self.match_lifecycle.lock().unwrap();
+            objectives::update_zones(&mut zones, &mut elapsed, &mut lifecycle, &tanks, dt)
+        };
+
+        for event in events {
+            println!("Zone {} {:?} by {} team", event.zone, event.kind, event.team.name());
+            self.send_to_all(&ServerMessage::Zone(event));
+        }
+    }
+
+    /// Clears bullets, scores, zones and recent deaths and puts every tank
+    /// back at full health on a spawn point.
+    fn reset_world(&self) {
+        *self.zones.lock().unwrap() = self.map.zones_for(self.mode);
+        *self.zone_score_elapsed.lock().unwrap() = 0.0;
+
+        let now = self.server_time();
         let mut tanks = self.tanks.lock().unwrap();
-        let mut npc_targets = self.npc_targets.lock().unwrap();
-        
+        self.bullets.lock().unwrap().clear();
+        let mut recent_deaths = self.recent_deaths.lock().unwrap();
+        recent_deaths.clear();
+
+        // Place tanks one by one, each avoiding the ones already placed
         for tank in tanks.iter_mut() {
+            tank.is_dead = true;
+        }
+        for i in 0..tanks.len() {
+            let position = self.spawn_position(&tanks[i], &tanks, &recent_deaths);
+            let tank = &mut tanks[i];
+            self.reset_tank(tank, position, now);
+            tank.last_fire_time = 0;
             if !tank.is_player {
-                // If NPC is dead, skip all logic for it
-                if tank.is_dead {
-                    continue;
-                }
+                self.forget_npc_target(&tank.id, &tank.position);
+            }
+        }
 
-                let (target_x, target_y) = npc_targets.get(&tank.id).unwrap_or(&(0.0, 0.0));
-                
-                // Calculate direction to target
-                let dx = target_x - tank.position.x;
-                let dy = target_y - tank.position.y;
-                let distance = (dx * dx + dy * dy).sqrt();
-                
-                // If we're close to target, pick a new one
-                if distance < 50.0 {
-                    let angle = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
-                    let distance = rng.gen_range(100.0..NPC_SPAWN_RADIUS);
-                    let new_x = angle.cos() * distance;
-                    let new_y = angle.sin() * distance;
-                    npc_targets.insert(tank.id.clone(), (new_x, new_y));
-                } else {
-                    // Move towards target
-                    let speed = 2.0;
-                    let target_angle = dy.atan2(dx);
-                    tank.rotation = target_angle;
-                    tank.position.x += target_angle.cos() * speed;
-                    tank.position.y += target_angle.sin() * speed;
-                    
-                    // Keep tanks within bounds
-                    tank.position.x = tank.position.x.clamp(-WORLD_BOUNDS, WORLD_BOUNDS);
-                    tank.position.y = tank.position.y.clamp(-WORLD_BOUNDS, WORLD_BOUNDS);
-                    
-                    // Randomly rotate turret
-                    if rng.gen_bool(0.1) {
-                        tank.turret_rotation += rng.gen_range(-0.1..0.1);
-                    }
-                    
-                    // Randomly fire
-                    if rng.gen_bool(0.01) {
-                        let now = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_millis() as u64;
-                        
-                        if now - tank.last_fire_time > 1000 { // 1 second cooldown
-                            tank.last_fire_time = now;
-                            
-                            let bullet = Bullet {
-                                id: Uuid::new_v4().to_string(),
-                                position: Position {
-                                    x: tank.position.x + tank.turret_rotation.cos() * 30.0,
-                                    y: tank.position.y + tank.turret_rotation.sin() * 30.0,
-                                },
-                                velocity: Velocity {
-                                    x: tank.turret_rotation.cos() * 10.0,
-                                    y: tank.turret_rotation.sin() * 10.0,
-                                },
-                                owner_id: tank.id.clone(),
-                                created_at: now,
-                            };
-                            
-                            self.bullets.lock().unwrap().push(bullet);
-                        }
-                    }
-                }
+        for player in self.players.lock().unwrap().values_mut() {
+            player.stats = PlayerStats::default();
+        }
+    }
+
+    /// Picks the safest spawn point for `tank`, away from living enemies and
+    /// from recent deaths. In team modes teams spawn on their own half.
+    fn spawn_position(&self, tank: &Tank, tanks: &[Tank], recent_deaths: &VecDeque<(u64, Position)>) -> Position {
+        let enemies: Vec<Position> = tanks
+            .iter()
+            .filter(|t| t.id != tank.id && !t.is_dead && is_enemy(tank.team, t.team))
+            .map(|t| t.position.clone())
+            .collect();
+        let deaths: Vec<Position> = recent_deaths.iter().map(|(_, position)| position.clone()).collect();
+
+        let mut candidates: Vec<Position> = self
+            .spawn_points
+            .iter()
+            .filter(|point| match tank.team {
+                Some(Team::Red) => point.x < -1.0,
+                Some(Team::Blue) => point.x > 1.0,
+                None => true,
+            })
+            .cloned()
+            .collect();
+        candidates.shuffle(&mut rand::thread_rng());
+        choose_spawn_point(&candidates, &enemies, &deaths).unwrap_or(Position { x: 0.0, y: 0.0 })
+    }
+
+    /// Brings back dead tanks whose respawn time has passed and ends
+    /// expired spawn protection.
+    fn respawn_tanks(&self) {
+        let now = self.server_time();
+        let mut tanks = self.tanks.lock().unwrap();
+        let mut recent_deaths = self.recent_deaths.lock().unwrap();
+        while recent_deaths
+            .front()
+            .is_some_and(|(died_at, _)| now.saturating_sub(*died_at) > RECENT_DEATH_WINDOW_MS)
+        {
+            recent_deaths.pop_front();
+        }
+
+        for i in 0..tanks.len() {
+            if tanks[i].spawn_protected_until.is_some_and(|until| now >= until) {
+                tanks[i].spawn_protected_until = None;
+            }
+            if !tanks[i].is_dead || tanks[i].respawn_time.is_none_or(|at| now < at) {
+                continue;
+            }
+
+            let position = self.spawn_position(&tanks[i], &tanks, &recent_deaths);
+            let tank = &mut tanks[i];
+            self.reset_tank(tank, position, now);
+            println!("Tank {} respawned at ({:.0}, {:.0})", tank.id, tank.position.x, tank.position.y);
+
+            if !tank.is_player {
+                self.forget_npc_target(&tank.id, &tank.position);
             }
         }
     }
 
+    /// Clears what a respawned NPC was doing. Patrolling to where it already
+    /// is makes it pick a fresh patrol target from its new spot.
+    fn forget_npc_target(&self, id: &str, position: &Position) {
+        if let Some(brain) = self.npc_brains.lock().unwrap().get_mut(id) {
+            *brain = NpcBrain::new(brain.difficulty, position.clone());
+        }
+    }
+
+    /// Puts a tank back into play at `position`, facing the centre of the map
+    /// and protected from damage for a moment.
+    fn reset_tank(&self, tank: &mut Tank, position: Position, now: u64) {
+        let facing = (-position.y).atan2(-position.x);
+        tank.position = position;
+        tank.rotation = facing;
+        tank.turret_rotation = facing;
+        tank.health = TANK_MAX_HEALTH;
+        tank.is_dead = false;
+        tank.respawn_time = None;
+        tank.spawn_protected_until = (self.respawn.protection_ms > 0).then_some(now + self.respawn.protection_ms);
+    }
+
+    fn update_bullets(&self, dt: f32) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let server_time = self.server_time();
+        let tick = *self.current_tick.lock().unwrap();
+        let tuning = *self.tuning.lock().unwrap();
+
+        // Lock tanks before bullets, the same order the broadcast uses
+        let results = {
+            let mut tanks = self.tanks.lock().unwrap();
+            let mut bullets = self.bullets.lock().unwrap();
+            let history = self.tank_history.lock().unwrap();
+            let world = self.bullet_world(tuning, &history, tick);
+            let mut bullet_rewind = self.bullet_rewind.lock().unwrap();
+            update_bullets(&mut bullets, &mut tanks, &mut bullet_rewind, &world, dt, server_time, now)
+        };
+        self.report_bullet_results(results);
+    }
+
+    fn bullet_world<'a>(&'a self, tuning: Tuning, history: &'a TankHistory, tick: u64) -> BulletWorld<'a> {
+        BulletWorld {
+            map: &self.map,
+            tuning,
+            friendly_fire: self.friendly_fire,
+            respawn_delay_ms: self.respawn.delay_ms,
+            history,
+            tick,
+        }
+    }
+
+    /// Records the deaths, scores the team kills and announces the hits of
+    /// bullets flying or just fired.
+    fn report_bullet_results(&self, results: BulletResults) {
+        self.recent_deaths.lock().unwrap().extend(results.deaths.iter().cloned());
+        if self.mode == GameMode::TeamDeathmatch {
+            let mut lifecycle = self.match_lifecycle.lock().unwrap();
+            for &team in &results.team_kills {
+                lifecycle.add_team_score(team, 1);
+            }
+        }
+        record_hits(&mut self.players.lock().unwrap(), &results.hits);
+        for hit in results.hits {
+            println!(
+                "Tank {} hit by {} for {} ({:?}), {} health left",
+                hit.target_id, hit.attacker_id, hit.damage, hit.side, hit.health
+            );
+            self.send_to_all(&ServerMessage::Hit(hit));
+        }
+    }
+
+    /// Every player's statistics, best first.
+    fn scoreboard_entries(&self) -> Vec<ScoreboardEntry> {
+        scoreboard_entries(&self.players.lock().unwrap())
+    }
+
+    fn broadcast_scoreboard(&self) {
+        let entries = self.scoreboard_entries();
+        self.send_to_all(&ServerMessage::Scoreboard { entries });
+        let state = self.match_lifecycle.lock().unwrap().state().clone();
+        self.send_to_all(&ServerMessage::MatchState(state));
+    }
+
+    /// Sends an event to every connected client in its own encoding.
+    fn send_to(&self, player_id: &str, msg: &ServerMessage) {
+        if let Some(connection) = self.connections.lock().unwrap().get(player_id) {
+            let _ = connection.sender.send(encode_message(msg, connection.encoding, None));
+        }
+    }
+
+    fn send_to_all(&self, msg: &ServerMessage) {
+        let connections = self.connections.lock().unwrap();
+        let mut encoded: HashMap<WireEncoding, Message> = HashMap::new();
+        for connection in connections.values() {
+            let msg = encoded
+                .entry(connection.encoding)
+                .or_insert_with(|| encode_message(msg, connection.encoding, None));
+            let _ = connection.sender.send(msg.clone());
+        }
+    }
+
+    fn update_npcs(&self, dt: f32) {
+        let world = NpcWorld {
+            map: &self.map,
+            nav: &self.nav,
+            tuning: *self.tuning.lock().unwrap(),
+            patrol_radius: self.npc_spawn_radius,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let mut tanks = self.tanks.lock().unwrap();
+        let mut npc_brains = self.npc_brains.lock().unwrap();
+        let fired = update_npcs(
+            &mut tanks,
+            &mut npc_brains,
+            &world,
+            dt,
+            self.server_time(),
+            now,
+            &mut rand::thread_rng(),
+        );
+        drop(npc_brains);
+
+        let tick = *self.current_tick.lock().unwrap();
+        let mut results = BulletResults::default();
+        {
+            let mut bullets = self.bullets.lock().unwrap();
+            let history = self.tank_history.lock().unwrap();
+            let bullet_world = self.bullet_world(world.tuning, &history, tick);
+            let mut bullet_rewind = self.bullet_rewind.lock().unwrap();
+            for bullet in fired {
+                let server_time = self.server_time();
+                results.append(fire(bullet, 0, &mut bullets, &mut bullet_rewind, &mut tanks, &bullet_world, server_time));
+            }
+        }
+        drop(tanks);
+        self.report_bullet_results(results);
+    }
+
     async fn broadcast_game_state(&self) {
-        // Clone the current state FIRST
         let game_state = {
             let tanks = self.tanks.lock().unwrap();
             let bullets = self.bullets.lock().unwrap();
+            let input_queues = self.input_queues.lock().unwrap();
             GameState {
+                tick: *self.current_tick.lock().unwrap(),
+                server_time: self.server_time(),
                 tanks: tanks.clone(),
                 bullets: bullets.clone(),
+                last_processed_input: input_queues
+                    .iter()
+                    .map(|(player_id, queue)| (player_id.clone(), queue.last_processed_seq()))
+                    .collect(),
+                zones: self.zones.lock().unwrap().clone(),
             }
         };
 
-        // Then, update the canonical game state for the next tick
-        self.update_bullets();
-        self.update_npcs();
-        
-        if let Ok(msg_str) = serde_json::to_string(&ServerMessage::GameState(game_state)) {
-            let connections = self.connections.lock().unwrap();
-            for sender in connections.values() {
-                let _ = sender.send(Message::Text(msg_str.clone()));
-            }
+        let mut history = self.snapshot_history.lock().unwrap();
+        let mut acks = self.snapshot_acks.lock().unwrap();
+        let connections = self.connections.lock().unwrap();
+
+        // Clients that acknowledged the same snapshot get the same delta, so
+        // encode each distinct message once. `None` is the full keyframe.
+        let mut encoded: HashMap<(WireEncoding, Option<u64>), Message> = HashMap::new();
+        for (player_id, connection) in connections.iter() {
+            let ack = acks.entry(player_id.clone()).or_default();
+            let base = match ack.acked_tick {
+                Some(tick) if connection.delta_snapshots && !ack.needs_keyframe => {
+                    history.iter().find(|s| s.tick == tick)
+                }
+                _ => None,
+            };
+
+            let key = (connection.encoding, base.map(|b| b.tick));
+            let msg = encoded.entry(key).or_insert_with(|| {
+                let msg = match base {
+                    Some(base) => ServerMessage::GameStateDelta(GameStateDelta::between(base, &game_state)),
+                    None => ServerMessage::GameState(game_state.clone()),
+                };
+                encode_message(&msg, connection.encoding, base)
+            });
+            let _ = connection.sender.send(msg.clone());
+            ack.needs_keyframe = false;
+        }
+
+        history.push_back(game_state);
+        while history.len() > SNAPSHOT_HISTORY_LEN {
+            history.pop_front();
         }
     }
 
-    fn handle_input(&self, player_id: &str, input: u16) {
+    fn handle_snapshot_ack(&self, player_id: &str, tick: u64) {
+        let mut acks = self.snapshot_acks.lock().unwrap();
+        let ack = acks.entry(player_id.to_string()).or_default();
+        ack.acked_tick = Some(ack.acked_tick.map_or(tick, |acked| acked.max(tick)));
+    }
+
+    fn handle_keyframe_request(&self, player_id: &str) {
+        let mut acks = self.snapshot_acks.lock().unwrap();
+        acks.entry(player_id.to_string()).or_default().needs_keyframe = true;
+    }
+
+    fn handle_update_settings(&self, player_id: &str, settings: BTreeMap<String, String>) {
+        let profile = self.players.lock().unwrap().get_mut(player_id).map(|player| {
+            player.profile.settings.extend(settings);
+            player.profile.clone()
+        });
+        self.save_profiles(profile.as_slice());
+    }
+
+    fn handle_input(&self, player_id: &str, seq: u32, input: u16) {
+        // Only queue the input here; the simulation tick applies it
+        self.input_queues
+            .lock()
+            .unwrap()
+            .entry(player_id.to_string())
+            .or_default()
+            .push(seq, input);
+    }
+
+    fn apply_player_inputs(&self, dt: f32, live: bool) {
+        // One input per player per tick, the last one again if the next
+        // hasn't arrived
+        let inputs: Vec<(String, u16)> = self
+            .input_queues
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter_map(|(player_id, queue)| queue.pop().map(|input| (player_id.clone(), input)))
+            .collect();
+
+        if !live {
+            return;
+        }
+        for (player_id, input) in inputs {
+            self.apply_input(&player_id, input, dt);
+        }
+    }
+
+    fn apply_input(&self, player_id: &str, input: u16, dt: f32) {
+        let tuning = *self.tuning.lock().unwrap();
         let mut tanks = self.tanks.lock().unwrap();
-        if let Some(tank) = tanks.iter_mut().find(|t| t.id == player_id) {
-            // If player's tank is dead, ignore input
-            if tank.is_dead {
-                return;
-            }
+        let Some(tank) = tanks.iter_mut().find(|t| t.id == player_id) else {
+            return;
+        };
+        // If player's tank is dead, ignore input
+        if tank.is_dead {
+            return;
+        }
 
-            // Update tank position based on input
-            let speed = 5.0;
-            let rotation_speed = 0.1;
-            
-            // Movement
-            if input & (1 << 0) != 0 { // W
-                tank.position.x += tank.rotation.cos() * speed;
-                tank.position.y += tank.rotation.sin() * speed;
-            }
-            if input & (1 << 1) != 0 { // A
-                tank.rotation -= rotation_speed;
+        apply_movement(tank, input, dt, &self.map);
+
+        // Fire
+        if !ClientMessage::decode_input(input).space {
+            return;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        if now - tank.last_fire_time <= tuning.player_fire_cooldown_ms {
+            return;
+        }
+        tank.last_fire_time = now;
+        tank.spawn_protected_until = None; // Firing ends spawn protection
+        let bullet = bullet_from(tank, &tuning, now);
+
+        // Aimed at the world as of the last snapshot this player saw, so
+        // hits land where they looked on screen
+        let acked_tick = self.snapshot_acks.lock().unwrap().get(player_id).and_then(|ack| ack.acked_tick);
+        let tick = *self.current_tick.lock().unwrap();
+        let rewind = rewind_ticks(tick, acked_tick, self.max_rewind_ticks);
+
+        let results = {
+            let mut bullets = self.bullets.lock().unwrap();
+            let history = self.tank_history.lock().unwrap();
+            let world = self.bullet_world(tuning, &history, tick);
+            let mut bullet_rewind = self.bullet_rewind.lock().unwrap();
+            fire(bullet, rewind, &mut bullets, &mut bullet_rewind, &mut tanks, &world, self.server_time())
+        };
+        drop(tanks);
+        if let Some(player) = self.players.lock().unwrap().get_mut(player_id) {
+            player.record_shot();
+        }
+        self.report_bullet_results(results);
+    }
+
+    fn handle_client_message(
+        &self,
+        client_msg: ClientMessage,
+        player_id: &mut Option<String>,
+        tx: &tokio::sync::mpsc::UnboundedSender<Message>,
+    ) {
+        match client_msg {
+            ClientMessage::Join { name, user_id: _, token, password, protocol_version, client_kind, features } => {
+                // One player per connection; another tank would hold a
+                // second slot until the connection closed
+                if let Some(pid) = player_id.as_deref() {
+                    println!("Ignoring a second join from {} as {}", pid, name);
+                    return;
+                }
+                if protocol_version != PROTOCOL_VERSION {
+                    send_rejection(
+                        tx,
+                        RejectReason::UnsupportedProtocolVersion,
+                        format!(
+                            "Client speaks protocol version {} but this server requires version {}. Please update or reload your client.",
+                            protocol_version, PROTOCOL_VERSION
+                        ),
+                    );
+                    return;
+                }
+
+                let accepted: Vec<Feature> = features
+                    .into_iter()
+                    .filter(|feature| SERVER_FEATURES.contains(feature))
+                    .collect();
+                // A genuine token resumes its identity; anyone else has to
+                // get past the server's auth mode
+                let resumed = token.and_then(|token| match self.sessions.tokens.verify(&token, unix_secs()) {
+                    Ok(user_id) => Some(user_id),
+                    Err(e) => {
+                        println!("Ignoring {}'s session token: {}", name, e);
+                        None
+                    }
+                });
+                let user_id = match resumed {
+                    Some(user_id) => Some(user_id),
+                    None => match self.sessions.auth.admit(&name, password.as_deref()) {
+                        Ok(user_id) => user_id,
+                        Err(e) => {
+                            send_rejection(tx, RejectReason::Unauthorized, format!("Could not join: {}.", e));
+                            return;
+                        }
+                    },
+                };
+                println!("{} joining with a {:?} client, features {:?}", name, client_kind, accepted);
+                *player_id = self.handle_join(name, user_id, accepted, tx);
             }
-            if input & (1 << 2) != 0 { // S
-                tank.position.x -= tank.rotation.cos() * speed;
-                tank.position.y -= tank.rotation.sin() * speed;
+            // Stragglers from a connection a rejoin has taken over are
+            // dropped, so they can't upset the new one's inputs and acks
+            ClientMessage::Input { seq, tick: _, input } => {
+                if let Some(pid) = player_id.as_deref().filter(|pid| self.owns_connection(pid, tx)) {
+                    self.handle_input(pid, seq, input);
+                }
             }
-            if input & (1 << 3) != 0 { // D
-                tank.rotation += rotation_speed;
+            ClientMessage::Ack { tick } => {
+                if let Some(pid) = player_id.as_deref().filter(|pid| self.owns_connection(pid, tx)) {
+                    self.handle_snapshot_ack(pid, tick);
+                }
             }
-            
-            // Turret rotation
-            if input & (1 << 4) != 0 { // Left arrow
-                tank.turret_rotation -= rotation_speed;
+            ClientMessage::RequestKeyframe => {
+                if let Some(pid) = player_id.as_deref().filter(|pid| self.owns_connection(pid, tx)) {
+                    self.handle_keyframe_request(pid);
+                }
             }
-            if input & (1 << 5) != 0 { // Right arrow
-                tank.turret_rotation += rotation_speed;
+            ClientMessage::RequestLeaderboard => {
+                if let Some(pid) = player_id.as_deref().filter(|pid| self.owns_connection(pid, tx)) {
+                    let entries = self.leaderboard();
+                    self.send_to(pid, &ServerMessage::Leaderboard { entries });
+                }
             }
-            
-            // Fire
-            if input & (1 << 8) != 0 { // Space
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64;
-                
-                if now - tank.last_fire_time > 500 { // 500ms cooldown
-                    tank.last_fire_time = now;
-                    
-                    let bullet = Bullet {
-                        id: Uuid::new_v4().to_string(),
-                        position: Position {
-                            x: tank.position.x + tank.turret_rotation.cos() * 30.0,
-                            y: tank.position.y + tank.turret_rotation.sin() * 30.0,
-                        },
-                        velocity: Velocity {
-                            x: tank.turret_rotation.cos() * 10.0,
-                            y: tank.turret_rotation.sin() * 10.0,
-                        },
-                        owner_id: player_id.to_string(),
-                        created_at: now,
-                    };
-                    
-                    self.bullets.lock().unwrap().push(bullet);
+            ClientMessage::UpdateSettings { settings } => {
+                if let Some(pid) = player_id.as_deref().filter(|pid| self.owns_connection(pid, tx)) {
+                    self.handle_update_settings(pid, settings);
                 }
             }
         }
     }
 
+    /// Whether `tx` is still the connection `player_id`'s updates go to.
+    fn owns_connection(&self, player_id: &str, tx: &tokio::sync::mpsc::UnboundedSender<Message>) -> bool {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(player_id)
+            .is_some_and(|c| c.sender.same_channel(tx))
+    }
+
+    /// Rereads the config at `path` after it changed and switches to its
+    /// tuning. Anything else that changed waits for a restart; a config that
+    /// doesn't load leaves everything as it was.
+    fn reload_config(&self, path: &Path, overrides: &[(String, String)], running: &mut ServerConfig) {
+        let reloaded = match ServerConfig::load(Some(path), overrides) {
+            Ok(reloaded) => reloaded,
+            Err(e) => {
+                eprintln!("Keeping the current config, {} didn't reload: {}", path.display(), e);
+                return;
+            }
+        };
+        let restart = running.restart_needed(&reloaded);
+        if !restart.is_empty() {
+            println!("Restart the server to apply changes to {}", restart.join(", "));
+        }
+        if reloaded.tuning != running.tuning {
+            running.tuning = reloaded.tuning;
+            *self.tuning.lock().unwrap() = reloaded.tuning;
+            println!("Reloaded tuning from {}: {:?}", path.display(), reloaded.tuning);
+        }
+    }
+
+    /// Seats a joining player and returns their id, or turns them away if
+    /// the server is full.
+    fn handle_join(
+        &self,
+        name: String,
+        user_id: Option<String>,
+        features: Vec<Feature>,
+        tx: &tokio::sync::mpsc::UnboundedSender<Message>,
+    ) -> Option<String> {
+        let user_id = user_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let stored = self.profiles.lock().unwrap().load(&user_id);
+        let mut players = self.players.lock().unwrap();
+
+        // Someone we still remember picks up their old tank, team and score
+        let returning = players.iter_mut().find(|(_, p)| p.user_id == user_id);
+        if let Some((player_id, player)) = returning {
+            let player_id = player_id.clone();
+            println!("{} is back as {}", player.name, name);
+            player.profile.name = name.clone();
+            player.name = name;
+            player.disconnected_at = None;
+            drop(players);
+            self.attach_connection(&player_id, user_id, features, tx);
+            return Some(player_id);
+        }
+
+        // Players holding a tank while they reconnect keep their place
+        if players.len() >= self.max_players {
+            drop(players);
+            send_rejection(
+                tx,
+                RejectReason::ServerFull,
+                format!("The server is full ({} players). Try again later.", self.max_players),
+            );
+            return None;
+        }
+
+        let new_player_id = Uuid::new_v4().to_string();
+        let mut profile = stored.unwrap_or_else(|| Profile::new(&user_id, &name));
+        if profile.matches_played > 0 {
+            println!("Welcome back {} ({} kills, {} deaths over {} matches)", name, profile.kills, profile.deaths, profile.matches_played);
+        }
+        profile.name = name.clone();
+        // Balance teams by putting the newcomer on the smaller one
+        let team = self.mode.has_teams().then(|| {
+            let on_team = |team| players.values().filter(|p| p.team == Some(team)).count();
+            pick_team(on_team(Team::Red), on_team(Team::Blue))
+        });
+        
+        // Create a new tank for the player
+        let mut tank = Tank {
+            id: new_player_id.clone(),
+            position: Position { x: 0.0, y: 0.0 },
+            rotation: 0.0,
+            turret_rotation: 0.0,
+            is_player: true,
+            health: TANK_MAX_HEALTH,
+            is_dead: false,
+            respawn_time: None,
+            last_fire_time: 0,
+            spawn_protected_until: None,
+            team,
+        };
+        
+        // Add player and tank
+        if let Some(team) = team {
+            println!("{} joins the {} team", name, team.name());
+        }
+        players.insert(new_player_id.clone(), Player {
+            name,
+            user_id: user_id.clone(),
+            team,
+            stats: PlayerStats::default(),
+            disconnected_at: None,
+            profile,
+        });
+        drop(players);
+        {
+            let mut tanks = self.tanks.lock().unwrap();
+            let position = self.spawn_position(&tank, &tanks, &self.recent_deaths.lock().unwrap());
+            self.reset_tank(&mut tank, position, self.server_time());
+            tanks.push(tank);
+        }
+        self.attach_connection(&new_player_id, user_id, features, tx);
+        Some(new_player_id)
+    }
+
+    /// Sends `player_id`'s updates to `tx` from now on, replacing any
+    /// connection they already had, and confirms the join.
+    fn attach_connection(
+        &self,
+        player_id: &str,
+        user_id: String,
+        features: Vec<Feature>,
+        tx: &tokio::sync::mpsc::UnboundedSender<Message>,
+    ) {
+        // Inputs and acks restart with the new connection
+        self.input_queues.lock().unwrap().remove(player_id);
+        self.snapshot_acks.lock().unwrap().remove(player_id);
+
+        let encoding = if features.contains(&Feature::BinaryEncoding) {
+            WireEncoding::Binary
+        } else {
+            WireEncoding::Json
+        };
+        let previous = self.connections.lock().unwrap().insert(player_id.to_string(), Connection {
+            sender: tx.clone(),
+            encoding,
+            delta_snapshots: features.contains(&Feature::DeltaSnapshots),
+        });
+        // The same user joining from somewhere else takes over
+        if let Some(previous) = previous {
+            send_rejection(
+                &previous.sender,
+                RejectReason::SessionTakenOver,
+                "You joined from another connection.".to_string(),
+            );
+        }
+        
+        // Send join confirmation; always JSON, the accepted features apply
+        // to everything after it
+        let join_msg = ServerMessage::Joined {
+            player_id: player_id.to_string(),
+            token: self.sessions.tokens.issue(&user_id, unix_secs()),
+            settings: self
+                .players
+                .lock()
+                .unwrap()
+                .get(player_id)
+                .map(|p| p.profile.settings.clone())
+                .unwrap_or_default(),
+            user_id,
+            protocol_version: PROTOCOL_VERSION,
+            features,
+            map: self.map.clone(),
+            tick_rate: self.tick_rate,
+        };
+        
+        if let Ok(msg_str) = serde_json::to_string(&join_msg) {
+            let _ = tx.send(Message::Text(msg_str));
+        }
+        let match_state = ServerMessage::MatchState(self.match_lifecycle.lock().unwrap().state().clone());
+        if let Ok(msg_str) = serde_json::to_string(&match_state) {
+            let _ = tx.send(Message::Text(msg_str));
+        }
+    }
+
     async fn handle_connection(&self, stream: TcpStream, addr: std::net::SocketAddr) {
         let ws_stream = tokio_tungstenite::accept_async(stream)
             .await
@@ -319,63 +1120,13 @@ impl GameServer {
             match msg {
                 Ok(Message::Text(text)) => {
                     if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
-                        match client_msg {
-                            ClientMessage::Join { name, user_id } => {
-                                let new_player_id = Uuid::new_v4().to_string();
-                                let user_id = user_id.unwrap_or_else(|| Uuid::new_v4().to_string());
-                                
-                                // Create a new tank for the player
-                                let tank = Tank {
-                                    id: new_player_id.clone(),
-                                    position: Position { x: 0.0, y: 0.0 },
-                                    rotation: 0.0,
-                                    turret_rotation: 0.0,
-                                    is_player: true,
-                                    health: 100,
-                                    is_dead: false,
-                                    respawn_time: None,
-                                    last_fire_time: 0,
-                                };
-                                
-                                // Add player and tank
-                                {
-                                    let mut players = self.players.lock().unwrap();
-                                    players.insert(new_player_id.clone(), Player {
-                                        name,
-                                        user_id: user_id.clone(),
-                                        tank_id: Some(new_player_id.clone()),
-                                    });
-                                }
-                                
-                                {
-                                    let mut tanks = self.tanks.lock().unwrap();
-                                    tanks.push(tank);
-                                }
-                                
-                                // Store the connection
-                                {
-                                    let mut connections = self.connections.lock().unwrap();
-                                    connections.insert(new_player_id.clone(), tx.clone());
-                                }
-                                
-                                player_id = Some(new_player_id.clone());
-                                
-                                // Send join confirmation
-                                let join_msg = ServerMessage::Joined {
-                                    player_id: new_player_id,
-                                    user_id,
-                                };
-                                
-                                if let Ok(msg_str) = serde_json::to_string(&join_msg) {
-                                    let _ = tx.send(Message::Text(msg_str));
-                                }
-                            }
-                            ClientMessage::Input { input } => {
-                                if let Some(pid) = &player_id {
-                                    self.handle_input(pid, input);
-                                }
-                            }
-                        }
+                        self.handle_client_message(client_msg, &mut player_id, &tx);
+                    }
+                }
+                Ok(Message::Binary(bytes)) => {
+                    match wire::decode_client_message(&bytes) {
+                        Ok(client_msg) => self.handle_client_message(client_msg, &mut player_id, &tx),
+                        Err(e) => eprintln!("Invalid binary message from {}: {}", addr, e),
                     }
                 }
                 Ok(Message::Close(_)) => {
@@ -390,44 +1141,209 @@ impl GameServer {
             }
         }
         
-        // Cleanup when connection is closed
+        // The tank stays put for a while in case the player comes back; a
+        // connection that was taken over by a rejoin leaves it alone
+        // (one lock at a time, so this cannot deadlock against the broadcast)
         if let Some(pid) = player_id {
-            let mut players = self.players.lock().unwrap();
-            players.remove(&pid);
-            
-            let mut tanks = self.tanks.lock().unwrap();
-            tanks.retain(|t| t.id != pid);
-            
-            let mut connections = self.connections.lock().unwrap();
-            connections.remove(&pid);
+            let ours = {
+                let mut connections = self.connections.lock().unwrap();
+                let ours = connections.get(&pid).is_some_and(|c| c.sender.same_channel(&tx));
+                if ours {
+                    connections.remove(&pid);
+                }
+                ours
+            };
+            if ours {
+                let profile = self.players.lock().unwrap().get_mut(&pid).map(|player| {
+                    player.disconnected_at = Some(self.server_time());
+                    player.profile.clone()
+                });
+                self.save_profiles(profile.as_slice());
+                self.input_queues.lock().unwrap().remove(&pid);
+                self.snapshot_acks.lock().unwrap().remove(&pid);
+            }
         }
         
         println!("Connection closed: {}", addr);
     }
 }
 
+fn unix_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Tells a client why it can't join and closes the connection.
+fn send_rejection(tx: &tokio::sync::mpsc::UnboundedSender<Message>, reason: RejectReason, message: String) {
+    println!("Rejecting join: {}", message);
+    let rejection = ServerMessage::Rejected {
+        reason,
+        message,
+        protocol_version: PROTOCOL_VERSION,
+    };
+    if let Ok(msg_str) = serde_json::to_string(&rejection) {
+        let _ = tx.send(Message::Text(msg_str));
+    }
+    let _ = tx.send(Message::Close(None));
+}
+
+/// Encodes a message for a connection in its negotiated encoding. `base` is
+/// the snapshot a delta was computed against.
+fn encode_message(msg: &ServerMessage, encoding: WireEncoding, base: Option<&GameState>) -> Message {
+    match encoding {
+        WireEncoding::Json => Message::Text(serde_json::to_string(msg).unwrap_or_default()),
+        WireEncoding::Binary => Message::Binary(wire::encode_server_message(msg, base).unwrap_or_default()),
+    }
+}
+
 #[tokio::main]
 async fn main() {
     // Initialize tracing
     tracing_subscriber::fmt::init();
     
-    let server = Arc::new(GameServer::new());
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, usage());
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        print!("{}", usage());
+        return;
+    }
+    let config_path = args.config_path();
+    let overrides = args.overrides;
+    let config = match ServerConfig::load(config_path.as_deref(), &overrides) {
+        Ok(config) => config,
+        Err(e) => {
+            let source = config_path.as_deref().map_or("the command line".to_string(), |p| p.display().to_string());
+            eprintln!("Failed to load config from {}: {}", source, e);
+            std::process::exit(1);
+        }
+    };
+    match &config_path {
+        Some(path) => println!("Loaded config from {}", path.display()),
+        None => println!("No {}, using the default config", DEFAULT_CONFIG_PATH),
+    }
+    
+    let rules = &config.match_;
+    println!(
+        "Respawning after {} ms with {} ms of spawn protection",
+        rules.respawn_delay_ms, rules.spawn_protection_ms
+    );
+    let match_settings = rules.settings();
+    println!(
+        "Matches last {} s or until {} kills",
+        match_settings.time_limit_ms / 1000,
+        match_settings.score_limit
+    );
+    println!("Game mode {:?}, friendly fire {}", match_settings.mode, if rules.friendly_fire { "on" } else { "off" });
+    let map = config.load_map().unwrap_or_else(|e| {
+        eprintln!("Failed to load map {}: {}", config.world.map, e);
+        std::process::exit(1);
+    });
+    println!(
+        "Playing on {} ({} walls, {} boxes, {} spawn points)",
+        map.name,
+        map.walls.len(),
+        map.boxes.len(),
+        map.spawn_points.len()
+    );
+    let timing = TickConfig::from_config(&config);
+    println!(
+        "Compensating for up to {} ms of latency ({} ticks)",
+        config.max_rewind_ms,
+        config.max_rewind_ticks()
+    );
+    println!("NPCs play on {:?}", config.world.npc_difficulty);
+    let sessions = SessionConfig::from_config(&config);
+    let profiles = config.open_profile_store().unwrap_or_else(|e| {
+        eprintln!("Failed to open profile store {}: {}", config.players.profiles, e);
+        std::process::exit(1);
+    });
+    match config.players.profiles.as_str() {
+        "" => println!("Keeping profiles in memory only"),
+        path => println!("Loaded {} profiles from {}", profiles.all().len(), path),
+    }
+    println!("Holding disconnected players' tanks for {} ms", sessions.reconnect_grace_ms);
+    println!("Up to {} players and {} NPCs", config.max_players, config.world.npc_count);
+    let server = Arc::new(GameServer::new(map, sessions, profiles, &config));
     
     // Start WebSocket server
-    let addr = "127.0.0.1:3001";
-    let listener = TcpListener::bind(addr).await.expect("Failed to bind");
-    println!("WebSocket server listening on: {}", addr);
+    let listener = match TcpListener::bind(&config.bind).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on {}: {}", config.bind, e);
+            std::process::exit(1);
+        }
+    };
+    println!("WebSocket server listening on: {}", config.bind);
+    println!(
+        "Simulating at {} Hz, broadcasting snapshots at {} Hz",
+        timing.tick_rate, timing.snapshot_rate
+    );
+    
+    // Spawn fixed-timestep simulation task
+    let server_clone = server.clone();
+    let tick_interval = timing.tick_interval();
+    tokio::spawn(async move {
+        let dt = tick_interval.as_secs_f32();
+        let mut interval = tokio::time::interval(tick_interval);
+        loop {
+            interval.tick().await;
+            server_clone.tick(dt);
+        }
+    });
     
     // Spawn game state broadcast task
     let server_clone = server.clone();
+    let snapshot_interval = timing.snapshot_interval();
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(50));
+        let mut interval = tokio::time::interval(snapshot_interval);
         loop {
             interval.tick().await;
             server_clone.broadcast_game_state().await;
         }
     });
     
+    // Spawn scoreboard and match state broadcast task
+    let server_clone = server.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs_f64(1.0 / SCOREBOARD_RATE as f64));
+        loop {
+            interval.tick().await;
+            server_clone.broadcast_scoreboard();
+        }
+    });
+    
+    // Spawn profile saving task
+    let server_clone = server.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(PROFILE_SAVE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            server_clone.save_all_profiles();
+        }
+    });
+    
+    // Spawn config reloading task, for tuning while the server runs
+    if let Some(path) = config_path {
+        let server_clone = server.clone();
+        tokio::spawn(async move {
+            let mut running = config;
+            let mut modified = modified_time(&path);
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(CONFIG_POLL_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                let now_modified = modified_time(&path);
+                if now_modified != modified {
+                    modified = now_modified;
+                    server_clone.reload_config(&path, &overrides, &mut running);
+                }
+            }
+        });
+    }
+    
     // Accept connections
     while let Ok((stream, addr)) = listener.accept().await {
         println!("New connection: {}", addr);
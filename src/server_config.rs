@@ -0,0 +1,615 @@
+//! Server settings, read from a TOML file and overridden from the command
+//! line. Everything is checked at startup so a typo stops the server with a
+//! message instead of surfacing mid-match. The `[tuning]` section can be
+//! edited while the server runs; the rest needs a restart. The map,
+//! profile store and accounts the config names are loaded from here too.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::auth::{AuthMode, TokenSigner, DEFAULT_TOKEN_TTL_SECS};
+use crate::lifecycle::MatchSettings;
+use crate::map::{Map, MapError, TANK_RADIUS};
+use crate::npc::Difficulty;
+use crate::profiles::{JsonLinesStore, MemoryStore, ProfileStore};
+use crate::reconnect::DEFAULT_RECONNECT_GRACE_MS;
+use crate::{GameMode, SIMULATION_TICK_RATE};
+
+/// Config read when `--config` isn't given, relative to the working
+/// directory; without it the defaults apply
+pub const DEFAULT_CONFIG_PATH: &str = "server.toml";
+/// Address the WebSocket server listens on
+pub const DEFAULT_BIND: &str = "127.0.0.1:3001";
+pub const DEFAULT_SNAPSHOT_RATE: u32 = 20;
+pub const DEFAULT_MAX_PLAYERS: usize = 16;
+/// Fastest simulation the server will try to run, in Hz
+pub const MAX_TICK_RATE: u32 = 240;
+/// NPCs patrol at least this far from the centre
+pub const NPC_MIN_PATROL_DISTANCE: f32 = 100.0;
+/// Largest arena, as half its side; the NPC navigation grid grows with the
+/// square of it
+pub const MAX_WORLD_SIZE: f32 = 5000.0;
+/// Furthest back lag compensation may reach, in milliseconds
+pub const MAX_REWIND_MS: u64 = 1000;
+/// Longest respawn delay, spawn protection or reconnect grace, in
+/// milliseconds
+pub const MAX_WAIT_MS: u64 = 60 * 60 * 1000;
+/// Longest match, in seconds
+pub const MAX_TIME_LIMIT_SECS: u64 = 24 * 60 * 60;
+/// Longest a session token can be valid, in seconds
+pub const MAX_TOKEN_TTL_SECS: u64 = 365 * 24 * 60 * 60;
+pub const DEFAULT_MAX_REWIND_MS: u64 = 250;
+pub const DEFAULT_MAP_PATH: &str = "maps/default.json";
+pub const DEFAULT_PROFILES_PATH: &str = "profiles.jsonl";
+
+/// Command line flags and the config key each one overrides.
+pub const FLAGS: [(&str, &str, &str); 26] = [
+    ("--bind", "bind", "address to listen on"),
+    ("--tick-rate", "tick_rate", "simulation rate in Hz"),
+    ("--snapshot-rate", "snapshot_rate", "snapshot broadcast rate in Hz"),
+    ("--max-players", "max_players", "players allowed at once"),
+    ("--max-rewind-ms", "max_rewind_ms", "how far back lag compensation reaches, 0 for off"),
+    ("--mode", "match.mode", "ffa, tdm, ctf, koth or dom"),
+    ("--score-limit", "match.score_limit", "score that wins a match"),
+    ("--time-limit-secs", "match.time_limit_secs", "longest a match lasts"),
+    ("--friendly-fire", "match.friendly_fire", "true to let teammates hurt each other"),
+    ("--respawn-delay-ms", "match.respawn_delay_ms", "time dead before respawning"),
+    ("--spawn-protection-ms", "match.spawn_protection_ms", "time invulnerable after respawning"),
+    ("--map", "world.map", "map file to play on"),
+    ("--world-size", "world.size", "half the side of the arena, replacing the map's"),
+    ("--npcs", "world.npc_count", "number of NPC tanks"),
+    ("--npc-spawn-radius", "world.npc_spawn_radius", "how far from the centre NPCs roam"),
+    ("--npc-difficulty", "world.npc_difficulty", "easy, normal or hard"),
+    ("--reconnect-grace-ms", "players.reconnect_grace_ms", "how long a dropped player's tank waits"),
+    ("--token-ttl-secs", "players.token_ttl_secs", "how long a session token is valid"),
+    ("--accounts", "players.accounts", "accounts file; makes the server private"),
+    ("--profiles", "players.profiles", "profile store, empty for memory only"),
+    ("--bullet-speed", "tuning.bullet_speed", "units per second"),
+    ("--bullet-damage", "tuning.bullet_damage", "damage before armour"),
+    ("--bullet-lifetime-ms", "tuning.bullet_lifetime_ms", "how long a bullet flies"),
+    ("--fire-cooldown-ms", "tuning.player_fire_cooldown_ms", "time between a player's shots"),
+    ("--npc-fire-cooldown-ms", "tuning.npc_fire_cooldown_ms", "time between an NPC's shots"),
+    ("--npc-speed", "tuning.npc_speed", "NPC driving speed in units per second"),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address the WebSocket server listens on
+    pub bind: String,
+    /// Simulation rate in Hz
+    pub tick_rate: u32,
+    /// Snapshot broadcast rate in Hz
+    pub snapshot_rate: u32,
+    /// Players, connected or holding a tank while they reconnect, allowed
+    /// at once
+    pub max_players: usize,
+    /// How far back shots are checked against, to cover the shooter's
+    /// latency; 0 turns lag compensation off
+    pub max_rewind_ms: u64,
+    #[serde(rename = "match")]
+    pub match_: MatchConfig,
+    pub world: WorldConfig,
+    pub players: PlayersConfig,
+    pub tuning: Tuning,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: DEFAULT_BIND.to_string(),
+            tick_rate: SIMULATION_TICK_RATE,
+            snapshot_rate: DEFAULT_SNAPSHOT_RATE,
+            max_players: DEFAULT_MAX_PLAYERS,
+            max_rewind_ms: DEFAULT_MAX_REWIND_MS,
+            match_: MatchConfig::default(),
+            world: WorldConfig::default(),
+            players: PlayersConfig::default(),
+            tuning: Tuning::default(),
+        }
+    }
+}
+
+/// What is played and by which rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatchConfig {
+    #[serde(deserialize_with = "game_mode")]
+    pub mode: GameMode,
+    /// Score that wins a match; depends on the mode when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_limit: Option<u32>,
+    pub time_limit_secs: u64,
+    /// Whether bullets hurt teammates in team modes
+    pub friendly_fire: bool,
+    pub respawn_delay_ms: u64,
+    /// Respawned tanks can't be hurt for this long, or until they fire
+    pub spawn_protection_ms: u64,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        let defaults = MatchSettings::default();
+        Self {
+            mode: defaults.mode,
+            score_limit: None,
+            time_limit_secs: defaults.time_limit_ms / 1000,
+            friendly_fire: false,
+            respawn_delay_ms: 5000,
+            spawn_protection_ms: 2000,
+        }
+    }
+}
+
+impl MatchConfig {
+    /// The lifecycle settings for this mode and these limits; warmup and
+    /// results keep their defaults.
+    pub fn settings(&self) -> MatchSettings {
+        let defaults = MatchSettings::default();
+        let default_score_limit = match self.mode {
+            GameMode::FreeForAll => defaults.score_limit,
+            GameMode::TeamDeathmatch => 25,
+            GameMode::CaptureTheFlag => 3,
+            GameMode::KingOfTheHill => 100,
+            GameMode::Domination => 200,
+        };
+        MatchSettings {
+            mode: self.mode,
+            score_limit: self.score_limit.unwrap_or(default_score_limit),
+            time_limit_ms: self.time_limit_secs * 1000,
+            ..defaults
+        }
+    }
+}
+
+/// A game mode by its full name or its short one (`ffa`, `tdm`, `ctf`,
+/// `koth` or `dom`).
+pub fn parse_game_mode(value: &str) -> Option<GameMode> {
+    match value {
+        "ffa" | "free_for_all" => Some(GameMode::FreeForAll),
+        "tdm" | "team_deathmatch" => Some(GameMode::TeamDeathmatch),
+        "ctf" | "capture_the_flag" => Some(GameMode::CaptureTheFlag),
+        "koth" | "king_of_the_hill" => Some(GameMode::KingOfTheHill),
+        "dom" | "domination" => Some(GameMode::Domination),
+        _ => None,
+    }
+}
+
+fn game_mode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<GameMode, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_game_mode(&value).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "unknown game mode {:?}, expected ffa, tdm, ctf, koth or dom (or their full names)",
+            value
+        ))
+    })
+}
+
+/// The arena and who's in it besides the players.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldConfig {
+    /// Map file, relative to the working directory; an open arena when it's
+    /// the default and missing
+    pub map: String,
+    /// Half the side of the arena; the map's own bounds when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<f32>,
+    pub npc_count: usize,
+    /// NPCs start and patrol within this distance of the centre
+    pub npc_spawn_radius: f32,
+    pub npc_difficulty: Difficulty,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            map: DEFAULT_MAP_PATH.to_string(),
+            size: None,
+            npc_count: 5,
+            npc_spawn_radius: 500.0,
+            npc_difficulty: Difficulty::default(),
+        }
+    }
+}
+
+/// Who may join, and what is kept about them. The server key and the token
+/// secret are secrets, so they come from the environment instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayersConfig {
+    /// How long a disconnected player's tank waits for them to rejoin
+    pub reconnect_grace_ms: u64,
+    /// How long a session token is valid
+    pub token_ttl_secs: u64,
+    /// Accounts file, empty for none; joining needs a name and password
+    /// from it when set
+    pub accounts: String,
+    /// Profile store, empty to keep profiles in memory only
+    pub profiles: String,
+}
+
+impl Default for PlayersConfig {
+    fn default() -> Self {
+        Self {
+            reconnect_grace_ms: DEFAULT_RECONNECT_GRACE_MS,
+            token_ttl_secs: DEFAULT_TOKEN_TTL_SECS,
+            accounts: String::new(),
+            profiles: DEFAULT_PROFILES_PATH.to_string(),
+        }
+    }
+}
+
+/// Weapon and NPC numbers that can change while the server runs.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tuning {
+    /// Units per second
+    pub bullet_speed: f32,
+    /// Damage before armour
+    pub bullet_damage: i32,
+    pub bullet_lifetime_ms: u64,
+    /// How close a bullet has to pass to a tank's centre to hit it
+    pub bullet_hit_radius: f32,
+    pub player_fire_cooldown_ms: u64,
+    pub npc_fire_cooldown_ms: u64,
+    /// Units per second
+    pub npc_speed: f32,
+    /// NPCs notice enemies this close
+    pub npc_sight_range: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            bullet_speed: 200.0,
+            bullet_damage: 25,
+            bullet_lifetime_ms: 5000,
+            bullet_hit_radius: 30.0,
+            player_fire_cooldown_ms: 500,
+            npc_fire_cooldown_ms: 1000,
+            npc_speed: 40.0,
+            npc_sight_range: 400.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Io(String),
+    Parse(String),
+    /// Every setting that is out of range, not just the first
+    Invalid(Vec<String>),
+    /// The command line didn't make sense
+    Usage(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read config: {}", e),
+            ConfigError::Parse(e) => write!(f, "invalid config TOML: {}", e),
+            ConfigError::Invalid(problems) => write!(f, "invalid config: {}", problems.join("; ")),
+            ConfigError::Usage(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ServerConfig {
+    /// Parses `toml`, applies `overrides` (config key, raw value) on top and
+    /// validates the result.
+    pub fn from_toml(toml: &str, overrides: &[(String, String)]) -> Result<Self, ConfigError> {
+        // The file on its own first, so its errors point at its own lines
+        toml::from_str::<ServerConfig>(toml).map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        let mut table: toml::Table = toml.parse().map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))?;
+        let defaults = toml::Table::try_from(ServerConfig::default()).expect("defaults serialize");
+        for (key, raw) in overrides {
+            set_key(&mut table, &defaults, key, raw);
+        }
+        let config: ServerConfig = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The config at `path`, or the defaults when there's no file, with
+    /// `overrides` applied.
+    pub fn load(path: Option<&Path>, overrides: &[(String, String)]) -> Result<Self, ConfigError> {
+        let toml = match path {
+            Some(path) => std::fs::read_to_string(path).map_err(|e| ConfigError::Io(e.to_string()))?,
+            None => String::new(),
+        };
+        Self::from_toml(&toml, overrides)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        if self.bind.parse::<std::net::SocketAddr>().is_err() {
+            problems.push(format!("bind must be an address and port like {}, not {:?}", DEFAULT_BIND, self.bind));
+        }
+        if !(1..=MAX_TICK_RATE).contains(&self.tick_rate) {
+            problems.push(format!("tick_rate must be 1 to {} Hz, not {}", MAX_TICK_RATE, self.tick_rate));
+        }
+        if self.snapshot_rate == 0 || self.snapshot_rate > self.tick_rate {
+            problems.push(format!(
+                "snapshot_rate must be 1 Hz up to tick_rate ({} Hz), not {}",
+                self.tick_rate, self.snapshot_rate
+            ));
+        }
+        if self.max_players == 0 {
+            problems.push("max_players must be at least 1".to_string());
+        }
+        if self.max_rewind_ms > MAX_REWIND_MS {
+            problems.push(format!("max_rewind_ms can be at most {}, not {}", MAX_REWIND_MS, self.max_rewind_ms));
+        }
+
+        let rules = &self.match_;
+        if rules.score_limit == Some(0) {
+            problems.push("match.score_limit must be at least 1".to_string());
+        }
+        if !(1..=MAX_TIME_LIMIT_SECS).contains(&rules.time_limit_secs) {
+            problems.push(format!(
+                "match.time_limit_secs must be 1 to {}, not {}",
+                MAX_TIME_LIMIT_SECS, rules.time_limit_secs
+            ));
+        }
+        let waits = [
+            ("match.respawn_delay_ms", rules.respawn_delay_ms),
+            ("match.spawn_protection_ms", rules.spawn_protection_ms),
+            ("players.reconnect_grace_ms", self.players.reconnect_grace_ms),
+        ];
+        for (key, value) in waits {
+            if value > MAX_WAIT_MS {
+                problems.push(format!("{} can be at most {}, not {}", key, MAX_WAIT_MS, value));
+            }
+        }
+        if !(1..=MAX_TOKEN_TTL_SECS).contains(&self.players.token_ttl_secs) {
+            problems.push(format!(
+                "players.token_ttl_secs must be 1 to {}, not {}",
+                MAX_TOKEN_TTL_SECS, self.players.token_ttl_secs
+            ));
+        }
+
+        let world = &self.world;
+        if world.map.is_empty() {
+            problems.push("world.map must name a map file".to_string());
+        }
+        if let Some(size) = world.size {
+            if !size.is_finite() || size <= TANK_RADIUS || size > MAX_WORLD_SIZE {
+                problems.push(format!(
+                    "world.size must be more than {} and at most {}, not {}",
+                    TANK_RADIUS, MAX_WORLD_SIZE, size
+                ));
+            }
+        }
+        if !world.npc_spawn_radius.is_finite() || world.npc_spawn_radius <= NPC_MIN_PATROL_DISTANCE {
+            problems.push(format!(
+                "world.npc_spawn_radius must be more than {}, not {}",
+                NPC_MIN_PATROL_DISTANCE, world.npc_spawn_radius
+            ));
+        }
+
+        let tuning = &self.tuning;
+        let positive = [
+            ("tuning.bullet_speed", tuning.bullet_speed),
+            ("tuning.bullet_hit_radius", tuning.bullet_hit_radius),
+            ("tuning.npc_speed", tuning.npc_speed),
+            ("tuning.npc_sight_range", tuning.npc_sight_range),
+        ];
+        for (key, value) in positive {
+            if !value.is_finite() || value <= 0.0 {
+                problems.push(format!("{} must be more than 0, not {}", key, value));
+            }
+        }
+        if tuning.bullet_damage < 0 {
+            problems.push(format!("tuning.bullet_damage can't be negative, not {}", tuning.bullet_damage));
+        }
+        if tuning.bullet_lifetime_ms == 0 {
+            problems.push("tuning.bullet_lifetime_ms must be more than 0".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// Settings that differ in `newer` but only take effect on a restart.
+    pub fn restart_needed(&self, newer: &ServerConfig) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.bind != newer.bind {
+            changed.push("bind");
+        }
+        if self.tick_rate != newer.tick_rate {
+            changed.push("tick_rate");
+        }
+        if self.snapshot_rate != newer.snapshot_rate {
+            changed.push("snapshot_rate");
+        }
+        if self.max_players != newer.max_players {
+            changed.push("max_players");
+        }
+        if self.max_rewind_ms != newer.max_rewind_ms {
+            changed.push("max_rewind_ms");
+        }
+        if self.match_ != newer.match_ {
+            changed.push("match");
+        }
+        if self.world != newer.world {
+            changed.push("world");
+        }
+        if self.players != newer.players {
+            changed.push("players");
+        }
+        changed
+    }
+
+    /// Ticks lag compensation reaches back at this tick rate.
+    pub fn max_rewind_ticks(&self) -> u64 {
+        self.max_rewind_ms * self.tick_rate as u64 / 1000
+    }
+
+    /// The map at `world.map`, resized to `world.size` if that's set and
+    /// checked against the game mode. A missing default map is an open
+    /// arena; any other missing or broken map is an error.
+    pub fn load_map(&self) -> Result<Map, MapError> {
+        let path = &self.world.map;
+        let mut map = match Map::load(path) {
+            Err(MapError::Io(_)) if path == DEFAULT_MAP_PATH => Map::default(),
+            loaded => loaded?,
+        };
+        let explain = |e: MapError, context: String| match e {
+            MapError::Invalid(why) => MapError::Invalid(format!("{}: {}", context, why)),
+            other => other,
+        };
+        if let Some(size) = self.world.size {
+            let name = map.name.clone();
+            map = map
+                .with_bounds(size)
+                .map_err(|e| explain(e, format!("can't resize {} to world.size {}", name, size)))?;
+        }
+        map.check_mode(self.match_.mode).map_err(|e| {
+            explain(
+                e,
+                format!(
+                    "can't play {:?} on {} at size {}, use a larger world.size or a map that places its own objectives",
+                    self.match_.mode, map.name, map.bounds
+                ),
+            )
+        })?;
+        Ok(map)
+    }
+
+    /// The profile store at `players.profiles`, or one in memory when that's
+    /// empty.
+    pub fn open_profile_store(&self) -> io::Result<Box<dyn ProfileStore>> {
+        let path = &self.players.profiles;
+        if path.is_empty() {
+            return Ok(Box::new(MemoryStore::default()));
+        }
+        Ok(Box::new(JsonLinesStore::open(path)?))
+    }
+
+    /// Accounts from `players.accounts`, else the shared `server_key`, else
+    /// open to all.
+    pub fn auth_mode(&self, server_key: Option<String>) -> Result<AuthMode, String> {
+        let path = &self.players.accounts;
+        if !path.is_empty() {
+            return AuthMode::load_accounts(path);
+        }
+        Ok(match server_key.filter(|key| !key.is_empty()) {
+            Some(key) => AuthMode::SharedKey(key),
+            None => AuthMode::Open,
+        })
+    }
+
+    /// Signs session tokens with `secret`, or a random secret when there's
+    /// none, in which case tokens don't outlive the server.
+    pub fn token_signer(&self, secret: Option<String>) -> TokenSigner {
+        let secret = secret
+            .filter(|secret| !secret.is_empty())
+            .map(String::into_bytes)
+            .unwrap_or_else(|| rand::thread_rng().gen::<[u8; 32]>().to_vec());
+        TokenSigner::new(&secret, self.players.token_ttl_secs)
+    }
+}
+
+/// Sets dotted `key` in `table` to `raw`, read as a TOML value unless the
+/// setting is a string, so `--bind 3001` isn't taken for a number.
+fn set_key(table: &mut toml::Table, defaults: &toml::Table, key: &str, raw: &str) {
+    let path: Vec<&str> = key.split('.').collect();
+    let (last, sections) = path.split_last().expect("keys aren't empty");
+
+    let mut default = Some(defaults);
+    let mut table = table;
+    for section in sections {
+        default = default.and_then(|d| d.get(*section)).and_then(toml::Value::as_table);
+        table = table
+            .entry(section.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .expect("config sections are tables");
+    }
+
+    let is_string = default.and_then(|d| d.get(*last)).is_some_and(toml::Value::is_str);
+    let value = if is_string {
+        toml::Value::String(raw.to_string())
+    } else {
+        format!("value = {}", raw)
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|mut parsed| parsed.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+    };
+    table.insert(last.to_string(), value);
+}
+
+/// What the server was started with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    /// Config file given with `--config`
+    pub config: Option<PathBuf>,
+    /// Config keys set by flags, in the order given
+    pub overrides: Vec<(String, String)>,
+    pub help: bool,
+}
+
+impl Args {
+    /// The config file from `--config`, else `server.toml` if there is one.
+    pub fn config_path(&self) -> Option<PathBuf> {
+        self.config
+            .clone()
+            .or_else(|| Path::new(DEFAULT_CONFIG_PATH).exists().then(|| PathBuf::from(DEFAULT_CONFIG_PATH)))
+    }
+
+    /// Reads flags of the form `--flag value` or `--flag=value`, not
+    /// including the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--help" || arg == "-h" {
+                parsed.help = true;
+                continue;
+            }
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let key = if flag == "--config" {
+                None
+            } else {
+                let known = FLAGS.iter().find(|(name, _, _)| *name == flag);
+                let (_, key, _) = known.ok_or_else(|| ConfigError::Usage(format!("unknown option {}", flag)))?;
+                Some(*key)
+            };
+            let value = inline
+                .or_else(|| args.next())
+                .ok_or_else(|| ConfigError::Usage(format!("{} needs a value", flag)))?;
+            match key {
+                Some(key) => parsed.overrides.push((key.to_string(), value)),
+                None => parsed.config = Some(PathBuf::from(value)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Help text listing every flag.
+pub fn usage() -> String {
+    let mut text = String::from("Usage: server [--config FILE] [OPTIONS]\n\nOptions:\n");
+    text.push_str(&format!("  {:<24}{}\n", "--config FILE", "TOML config file (default server.toml)"));
+    for (flag, key, help) in FLAGS {
+        text.push_str(&format!("  {:<24}{} ({})\n", flag, help, key));
+    }
+    text
+}
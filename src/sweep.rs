@@ -0,0 +1,89 @@
+//! Swept tests for a point moving along a segment, used so fast bullets
+//! can't step over a tank or through a thin wall between two ticks. Each
+//! returns the fraction of the way from `from` to `to` at which the point
+//! first touches the shape: 0 if it starts inside, `None` if it never does.
+
+use crate::Position;
+
+/// Where the segment first enters the circle at `centre`.
+pub fn segment_circle(from: &Position, to: &Position, centre: &Position, radius: f32) -> Option<f32> {
+    let (fx, fy) = (from.x - centre.x, from.y - centre.y);
+    let c = fx * fx + fy * fy - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let a = dx * dx + dy * dy;
+    let b = fx * dx + fy * dy;
+    // Stationary, or heading away from the centre
+    if a <= f32::EPSILON || b >= 0.0 {
+        return None;
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    // The nearer root, in the form that doesn't cancel when c is small
+    let t = c / (-b + discriminant.sqrt());
+    (t <= 1.0).then_some(t.max(0.0))
+}
+
+/// Where the segment first enters the axis-aligned box from `min` to `max`.
+pub fn segment_box(from: &Position, to: &Position, min: &Position, max: &Position) -> Option<f32> {
+    let (mut enter, mut exit) = (0.0f32, 1.0f32);
+    for (start, delta, lo, hi) in [
+        (from.x, to.x - from.x, min.x, max.x),
+        (from.y, to.y - from.y, min.y, max.y),
+    ] {
+        if delta.abs() <= f32::EPSILON {
+            // Parallel to this pair of faces: inside the slab or never
+            if start < lo || start > hi {
+                return None;
+            }
+            continue;
+        }
+        let (t0, t1) = ((lo - start) / delta, (hi - start) / delta);
+        enter = enter.max(t0.min(t1));
+        exit = exit.min(t0.max(t1));
+        if enter > exit {
+            return None;
+        }
+    }
+    Some(enter)
+}
+
+/// Where the segment first comes within `radius` of the segment from `a`
+/// to `b`: a capsule, the shape of a wall.
+pub fn segment_capsule(from: &Position, to: &Position, a: &Position, b: &Position, radius: f32) -> Option<f32> {
+    let ends = [a, b].into_iter().filter_map(|end| segment_circle(from, to, end, radius));
+
+    let (ux, uy) = (b.x - a.x, b.y - a.y);
+    let length = (ux * ux + uy * uy).sqrt();
+    if length <= f32::EPSILON {
+        return ends.reduce(f32::min);
+    }
+    // The straight part, as a box in the wall's own frame
+    let (ux, uy) = (ux / length, uy / length);
+    let local = |p: &Position| {
+        let (px, py) = (p.x - a.x, p.y - a.y);
+        Position {
+            x: px * ux + py * uy,
+            y: py * ux - px * uy,
+        }
+    };
+    let side = segment_box(
+        &local(from),
+        &local(to),
+        &Position { x: 0.0, y: -radius },
+        &Position { x: length, y: radius },
+    );
+    ends.chain(side).reduce(f32::min)
+}
+
+/// The point `t` of the way from `from` to `to`.
+pub fn lerp(from: &Position, to: &Position, t: f32) -> Position {
+    Position {
+        x: from.x + (to.x - from.x) * t,
+        y: from.y + (to.y - from.y) * t,
+    }
+}
@@ -1,17 +1,30 @@
 use bevy::prelude::*;
-use crate::input::{handle_input, send_input};
-use crate::rendering::{update_game_entities, update_camera, setup_rendering};
+use crate::input::{follow_server_tick_rate, handle_input, send_input};
+use crate::prediction::reconcile_prediction;
+use crate::hud::{setup_hud, spawn_hit_feedback, update_announcements, update_hit_feedback, update_match_text, update_status_text};
+use crate::scoreboard::{setup_scoreboard, update_leaderboard, update_scoreboard};
+use crate::rendering::{update_game_entities, update_camera, update_flag_markers, update_zone_rings, update_map_obstacles, setup_rendering};
 use crate::network::{ConnectionState, WebSocketSender, setup_network};
 use crate::game_state::{GameStateResource, PlayerInfo};
 use crate::tank_model::TankModel;
 
 pub fn setup_game_systems(app: &mut App) {
-    app.add_systems(Startup, setup)
+    app.add_systems(Startup, (setup, setup_hud, setup_scoreboard))
+        .add_systems(FixedUpdate, (send_input, reconcile_prediction).chain())
         .add_systems(Update, (
             handle_input,
-            send_input,
+            follow_server_tick_rate,
             update_game_entities,
             update_camera,
+            update_flag_markers,
+            update_zone_rings,
+            update_map_obstacles,
+            update_status_text,
+            update_match_text,
+            update_announcements,
+            (spawn_hit_feedback, update_hit_feedback).chain(),
+            update_scoreboard,
+            update_leaderboard,
         ));
 }
 
@@ -32,6 +45,7 @@ fn setup(
         game_state,
         player_info,
         connection_state.connected.clone(),
+        connection_state.rejection.clone(),
         ws_sender.sender.clone(),
     );
 } 
\ No newline at end of file
@@ -0,0 +1,187 @@
+//! Team assignment and capture-the-flag rules.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Flag, FlagEvent, FlagEventKind, Position, Tank, Team};
+
+/// How close a tank has to get to a flag or base to interact with it
+pub const FLAG_RADIUS: f32 = 30.0;
+/// A dropped flag nobody touches goes home after this long
+pub const FLAG_RETURN_MS: u64 = 30_000;
+/// Bases sit this far left (red) and right (blue) of the centre
+pub const BASE_DISTANCE: f32 = 700.0;
+
+/// Team for a newly joining player: the one with fewer players, red on a tie.
+pub fn pick_team(red_players: usize, blue_players: usize) -> Team {
+    if blue_players < red_players {
+        Team::Blue
+    } else {
+        Team::Red
+    }
+}
+
+/// Whether tanks on teams `a` and `b` fight each other. Tanks without a team
+/// (free-for-all) are everyone's enemy.
+pub fn is_enemy(a: Option<Team>, b: Option<Team>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a != b,
+        _ => true,
+    }
+}
+
+/// Where `team`'s flag and capture point are.
+pub fn base_position(team: Team) -> Position {
+    match team {
+        Team::Red => Position { x: -BASE_DISTANCE, y: 0.0 },
+        Team::Blue => Position { x: BASE_DISTANCE, y: 0.0 },
+    }
+}
+
+/// Where each team's flag and capture point are on a map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlagBases {
+    pub red: Position,
+    pub blue: Position,
+}
+
+impl Default for FlagBases {
+    /// Either side of the centre, `BASE_DISTANCE` out.
+    fn default() -> Self {
+        Self {
+            red: base_position(Team::Red),
+            blue: base_position(Team::Blue),
+        }
+    }
+}
+
+impl FlagBases {
+    pub fn of(&self, team: Team) -> &Position {
+        match team {
+            Team::Red => &self.red,
+            Team::Blue => &self.blue,
+        }
+    }
+}
+
+/// Both flags, at home on `bases`.
+pub fn new_flags(bases: &FlagBases) -> Vec<Flag> {
+    Team::ALL
+        .iter()
+        .map(|&team| Flag {
+            team,
+            base: bases.of(team).clone(),
+            position: bases.of(team).clone(),
+            carrier_id: None,
+            dropped_at: None,
+        })
+        .collect()
+}
+
+fn within_reach(a: &Position, b: &Position) -> bool {
+    let dx = a.x - b.x;
+    let dy = a.y - b.y;
+    dx * dx + dy * dy < FLAG_RADIUS * FLAG_RADIUS
+}
+
+fn is_home(flag: &Flag) -> bool {
+    flag.carrier_id.is_none() && flag.dropped_at.is_none()
+}
+
+fn send_home(flag: &mut Flag) {
+    flag.position = flag.base.clone();
+    flag.carrier_id = None;
+    flag.dropped_at = None;
+}
+
+/// Applies one tick of flag rules at server time `now`: carried flags follow
+/// their carrier and drop when it dies or leaves, enemies pick flags up,
+/// defenders return dropped ones, and a carrier reaching its own base while
+/// its own flag is home captures.
+pub fn update_flags(flags: &mut [Flag], tanks: &[Tank], now: u64) -> Vec<FlagEvent> {
+    let mut events = Vec::new();
+    let event = |kind, flag: &Flag, tank_id: Option<&String>| FlagEvent {
+        kind,
+        flag_team: flag.team,
+        tank_id: tank_id.cloned(),
+        position: flag.position.clone(),
+    };
+
+    // Follow or drop
+    for flag in flags.iter_mut() {
+        let Some(carrier_id) = flag.carrier_id.clone() else {
+            continue;
+        };
+        match tanks.iter().find(|t| t.id == carrier_id) {
+            Some(carrier) if !carrier.is_dead => flag.position = carrier.position.clone(),
+            carrier => {
+                if let Some(carrier) = carrier {
+                    flag.position = carrier.position.clone();
+                }
+                flag.carrier_id = None;
+                flag.dropped_at = Some(now);
+                events.push(event(FlagEventKind::Drop, flag, Some(&carrier_id)));
+            }
+        }
+    }
+
+    // Pick up, return by touch or time out
+    for i in 0..flags.len() {
+        if flags[i].carrier_id.is_some() {
+            continue;
+        }
+        let flag_team = flags[i].team;
+        let carrying: Vec<&String> = flags.iter().filter_map(|f| f.carrier_id.as_ref()).collect();
+        let toucher = tanks.iter().find(|t| {
+            !t.is_dead
+                && t.team.is_some()
+                && within_reach(&t.position, &flags[i].position)
+                && (t.team == Some(flag_team) || !carrying.contains(&&t.id))
+        });
+
+        let flag = &mut flags[i];
+        match toucher {
+            Some(tank) if tank.team == Some(flag_team) => {
+                if !is_home(flag) {
+                    send_home(flag);
+                    events.push(event(FlagEventKind::Return, flag, Some(&tank.id)));
+                }
+            }
+            Some(tank) => {
+                flag.carrier_id = Some(tank.id.clone());
+                flag.dropped_at = None;
+                flag.position = tank.position.clone();
+                events.push(event(FlagEventKind::Pickup, flag, Some(&tank.id)));
+            }
+            None => {
+                if flag.dropped_at.is_some_and(|dropped_at| now.saturating_sub(dropped_at) >= FLAG_RETURN_MS) {
+                    send_home(flag);
+                    events.push(event(FlagEventKind::Return, flag, None));
+                }
+            }
+        }
+    }
+
+    // Capture
+    for i in 0..flags.len() {
+        let Some(carrier_id) = flags[i].carrier_id.clone() else {
+            continue;
+        };
+        let capturing_team = flags[i].team.opponent();
+        let Some(home) = flags.iter().find(|f| f.team == capturing_team && is_home(f)) else {
+            continue;
+        };
+        if within_reach(&flags[i].position, &home.base) {
+            let base = home.base.clone();
+            let flag = &mut flags[i];
+            send_home(flag);
+            events.push(FlagEvent {
+                kind: FlagEventKind::Capture,
+                flag_team: flag.team,
+                tank_id: Some(carrier_id),
+                position: base,
+            });
+        }
+    }
+
+    events
+}
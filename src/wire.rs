@@ -0,0 +1,755 @@
+//! Compact binary encoding for the messages sent every tick or snapshot.
+//!
+//! Every frame starts with `WIRE_VERSION` and a message type byte. Numbers
+//! are LEB128 varints (zigzag for signed values), positions are fixed point
+//! with `POSITION_SCALE` steps per unit and angles are a `u16` fraction of a
+//! full turn. Within a delta, entities are referred to by their index in the
+//! base snapshot instead of by their 36 character UUID. Messages without a
+//! compact form are carried as JSON inside a binary frame.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::fmt;
+
+use crate::delta::{BulletDelta, GameStateDelta, TankDelta};
+use crate::{Bullet, CaptureZone, ClientMessage, GameState, Position, ServerMessage, Tank, Team, Velocity};
+
+pub const WIRE_VERSION: u8 = 5;
+
+/// Fixed point steps per world unit for positions and velocities.
+pub const POSITION_SCALE: f32 = 16.0;
+
+const ANGLE_STEPS: f32 = 65536.0;
+
+// Message type bytes
+const SERVER_GAME_STATE: u8 = 1;
+const SERVER_GAME_STATE_DELTA: u8 = 2;
+const CLIENT_INPUT: u8 = 1;
+const CLIENT_ACK: u8 = 2;
+const CLIENT_REQUEST_KEYFRAME: u8 = 3;
+const JSON_PAYLOAD: u8 = 0xFF;
+
+// Entity id encodings
+const ID_UUID: u8 = 0;
+const ID_STRING: u8 = 1;
+
+// TankDelta field mask, sent as a varint so fields can be added past eight
+const TANK_POSITION: u64 = 1 << 0;
+const TANK_ROTATION: u64 = 1 << 1;
+const TANK_TURRET_ROTATION: u64 = 1 << 2;
+const TANK_HEALTH: u64 = 1 << 3;
+const TANK_IS_DEAD: u64 = 1 << 4;
+const TANK_RESPAWN_TIME: u64 = 1 << 5;
+const TANK_LAST_FIRE_TIME: u64 = 1 << 6;
+const TANK_SPAWN_PROTECTED_UNTIL: u64 = 1 << 7;
+const TANK_TEAM: u64 = 1 << 8;
+
+// Tank flags
+const FLAG_IS_PLAYER: u8 = 1 << 0;
+const FLAG_IS_DEAD: u8 = 1 << 1;
+const FLAG_HAS_RESPAWN_TIME: u8 = 1 << 2;
+const FLAG_HAS_SPAWN_PROTECTION: u8 = 1 << 3;
+const FLAG_TEAM_RED: u8 = 1 << 4;
+const FLAG_TEAM_BLUE: u8 = 1 << 5;
+
+// Team in a TankDelta
+const TEAM_NONE: u8 = 0;
+const TEAM_RED: u8 = 1;
+const TEAM_BLUE: u8 = 2;
+
+// Capture zone progress is sent in steps of 1/PROGRESS_STEPS
+const PROGRESS_STEPS: f32 = 1000.0;
+
+/// How a connection's messages are framed. Binary is used once both sides
+/// agree on `Feature::BinaryEncoding`; JSON text frames remain the default
+/// and a fallback for debugging.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WireEncoding {
+    #[default]
+    Json,
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WireError {
+    UnexpectedEnd,
+    UnsupportedVersion(u8),
+    UnknownMessageType(u8),
+    InvalidId,
+    UnknownEntity(usize),
+    /// A delta arrived whose base snapshot the decoder does not have
+    MissingBase(u64),
+    Json(String),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::UnexpectedEnd => write!(f, "message ended unexpectedly"),
+            WireError::UnsupportedVersion(v) => write!(f, "unsupported wire version {}", v),
+            WireError::UnknownMessageType(t) => write!(f, "unknown message type {}", t),
+            WireError::InvalidId => write!(f, "invalid entity id"),
+            WireError::UnknownEntity(i) => write!(f, "reference to unknown entity {}", i),
+            WireError::MissingBase(tick) => write!(f, "no base snapshot for tick {}", tick),
+            WireError::Json(e) => write!(f, "invalid JSON payload: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+/// Encodes a server message. Deltas are encoded against `base`, the
+/// snapshot they were computed from; other messages ignore it.
+pub fn encode_server_message(message: &ServerMessage, base: Option<&GameState>) -> Result<Vec<u8>, WireError> {
+    let mut w = Writer::new();
+    match message {
+        ServerMessage::GameState(state) => {
+            w.u8(SERVER_GAME_STATE);
+            w.game_state(state);
+        }
+        ServerMessage::GameStateDelta(delta) => {
+            let base = base
+                .filter(|b| b.tick == delta.base_tick)
+                .ok_or(WireError::MissingBase(delta.base_tick))?;
+            w.u8(SERVER_GAME_STATE_DELTA);
+            w.delta(delta, base);
+        }
+        other => w.json(other)?,
+    }
+    Ok(w.finish())
+}
+
+/// Decodes a server message. `base_for_tick` looks up a previously received
+/// snapshot by tick, needed to resolve the entity references in a delta.
+pub fn decode_server_message<'a>(
+    bytes: &[u8],
+    base_for_tick: impl FnOnce(u64) -> Option<&'a GameState>,
+) -> Result<ServerMessage, WireError> {
+    let mut r = Reader::new(bytes)?;
+    match r.u8()? {
+        SERVER_GAME_STATE => Ok(ServerMessage::GameState(r.game_state()?)),
+        SERVER_GAME_STATE_DELTA => {
+            let base_tick = r.varint()?;
+            let base = base_for_tick(base_tick).ok_or(WireError::MissingBase(base_tick))?;
+            Ok(ServerMessage::GameStateDelta(r.delta(base_tick, base)?))
+        }
+        JSON_PAYLOAD => r.json(),
+        other => Err(WireError::UnknownMessageType(other)),
+    }
+}
+
+pub fn encode_client_message(message: &ClientMessage) -> Vec<u8> {
+    let mut w = Writer::new();
+    match message {
+        ClientMessage::Input { seq, tick, input } => {
+            w.u8(CLIENT_INPUT);
+            w.varint(*seq as u64);
+            w.varint(*tick);
+            w.varint(*input as u64);
+        }
+        ClientMessage::Ack { tick } => {
+            w.u8(CLIENT_ACK);
+            w.varint(*tick);
+        }
+        ClientMessage::RequestKeyframe => w.u8(CLIENT_REQUEST_KEYFRAME),
+        // Serializing our own message types to JSON cannot fail
+        other => w.json(other).unwrap_or_default(),
+    }
+    w.finish()
+}
+
+pub fn decode_client_message(bytes: &[u8]) -> Result<ClientMessage, WireError> {
+    let mut r = Reader::new(bytes)?;
+    match r.u8()? {
+        CLIENT_INPUT => Ok(ClientMessage::Input {
+            seq: r.varint()? as u32,
+            tick: r.varint()?,
+            input: r.varint()? as u16,
+        }),
+        CLIENT_ACK => Ok(ClientMessage::Ack { tick: r.varint()? }),
+        CLIENT_REQUEST_KEYFRAME => Ok(ClientMessage::RequestKeyframe),
+        JSON_PAYLOAD => r.json(),
+        other => Err(WireError::UnknownMessageType(other)),
+    }
+}
+
+/// Maps entity ids to their position in a snapshot so they can be sent as
+/// small indices. Index 0 on the wire means "id follows inline".
+struct EntityIndex<'a>(HashMap<&'a str, usize>);
+
+impl<'a> EntityIndex<'a> {
+    fn new(ids: impl Iterator<Item = &'a str>) -> Self {
+        Self(ids.enumerate().map(|(i, id)| (id, i)).collect())
+    }
+}
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Self { buf: vec![WIRE_VERSION] }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn signed(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn fixed(&mut self, value: f32) {
+        self.signed((value * POSITION_SCALE).round() as i64);
+    }
+
+    fn angle(&mut self, angle: f32) {
+        let steps = (angle.rem_euclid(TAU) / TAU * ANGLE_STEPS).round() as u32 % ANGLE_STEPS as u32;
+        self.buf.extend_from_slice(&(steps as u16).to_le_bytes());
+    }
+
+    fn position(&mut self, position: &Position) {
+        self.fixed(position.x);
+        self.fixed(position.y);
+    }
+
+    fn id(&mut self, id: &str) {
+        match uuid::Uuid::parse_str(id) {
+            // Only use the 16 byte form if it reproduces the exact same string
+            Ok(uuid) if uuid.hyphenated().to_string() == id => {
+                self.u8(ID_UUID);
+                self.buf.extend_from_slice(uuid.as_bytes());
+            }
+            _ => {
+                self.u8(ID_STRING);
+                self.varint(id.len() as u64);
+                self.buf.extend_from_slice(id.as_bytes());
+            }
+        }
+    }
+
+    fn entity_ref(&mut self, id: &str, index: &EntityIndex) {
+        match index.0.get(id) {
+            Some(&i) => self.varint(i as u64 + 1),
+            None => {
+                self.varint(0);
+                self.id(id);
+            }
+        }
+    }
+
+    fn json<T: Serialize>(&mut self, message: &T) -> Result<(), WireError> {
+        let json = serde_json::to_vec(message).map_err(|e| WireError::Json(e.to_string()))?;
+        self.u8(JSON_PAYLOAD);
+        self.buf.extend_from_slice(&json);
+        Ok(())
+    }
+
+    fn tank(&mut self, tank: &Tank) {
+        self.id(&tank.id);
+        self.position(&tank.position);
+        self.angle(tank.rotation);
+        self.angle(tank.turret_rotation);
+        let mut flags = 0;
+        if tank.is_player {
+            flags |= FLAG_IS_PLAYER;
+        }
+        if tank.is_dead {
+            flags |= FLAG_IS_DEAD;
+        }
+        if tank.respawn_time.is_some() {
+            flags |= FLAG_HAS_RESPAWN_TIME;
+        }
+        if tank.spawn_protected_until.is_some() {
+            flags |= FLAG_HAS_SPAWN_PROTECTION;
+        }
+        match tank.team {
+            Some(Team::Red) => flags |= FLAG_TEAM_RED,
+            Some(Team::Blue) => flags |= FLAG_TEAM_BLUE,
+            None => {}
+        }
+        self.u8(flags);
+        self.signed(tank.health as i64);
+        if let Some(respawn_time) = tank.respawn_time {
+            self.varint(respawn_time);
+        }
+        self.varint(tank.last_fire_time);
+        if let Some(protected_until) = tank.spawn_protected_until {
+            self.varint(protected_until);
+        }
+    }
+
+    fn team(&mut self, team: Option<Team>) {
+        self.u8(match team {
+            None => TEAM_NONE,
+            Some(Team::Red) => TEAM_RED,
+            Some(Team::Blue) => TEAM_BLUE,
+        });
+    }
+
+    fn zones(&mut self, zones: &[CaptureZone]) {
+        self.varint(zones.len() as u64);
+        for zone in zones {
+            self.varint(zone.name.len() as u64);
+            self.buf.extend_from_slice(zone.name.as_bytes());
+            self.position(&zone.position);
+            self.fixed(zone.radius);
+            self.team(zone.owner);
+            self.team(zone.capturing_team);
+            self.varint((zone.progress.clamp(0.0, 1.0) * PROGRESS_STEPS).round() as u64);
+            self.u8(zone.contested as u8);
+        }
+    }
+
+    fn bullet(&mut self, bullet: &Bullet, tanks: &EntityIndex) {
+        self.id(&bullet.id);
+        self.position(&bullet.position);
+        self.fixed(bullet.velocity.x);
+        self.fixed(bullet.velocity.y);
+        self.entity_ref(&bullet.owner_id, tanks);
+        self.varint(bullet.created_at);
+        self.signed(bullet.damage as i64);
+    }
+
+    fn acks(&mut self, acks: &HashMap<String, u32>, tanks: &EntityIndex) {
+        self.varint(acks.len() as u64);
+        for (player_id, seq) in acks {
+            self.entity_ref(player_id, tanks);
+            self.varint(*seq as u64);
+        }
+    }
+
+    fn game_state(&mut self, state: &GameState) {
+        let tanks = EntityIndex::new(state.tanks.iter().map(|t| t.id.as_str()));
+        self.varint(state.tick);
+        self.varint(state.server_time);
+        self.varint(state.tanks.len() as u64);
+        for tank in &state.tanks {
+            self.tank(tank);
+        }
+        self.varint(state.bullets.len() as u64);
+        for bullet in &state.bullets {
+            self.bullet(bullet, &tanks);
+        }
+        self.acks(&state.last_processed_input, &tanks);
+        self.zones(&state.zones);
+    }
+
+    fn delta(&mut self, delta: &GameStateDelta, base: &GameState) {
+        // Tanks are numbered base first, then this delta's spawns, the same
+        // order `GameStateDelta::apply` produces
+        let tanks = EntityIndex::new(
+            base.tanks
+                .iter()
+                .chain(&delta.spawned_tanks)
+                .map(|t| t.id.as_str()),
+        );
+        let bullets = EntityIndex::new(base.bullets.iter().map(|b| b.id.as_str()));
+
+        self.varint(delta.base_tick);
+        self.varint(delta.tick);
+        self.varint(delta.server_time);
+
+        self.varint(delta.spawned_tanks.len() as u64);
+        for tank in &delta.spawned_tanks {
+            self.tank(tank);
+        }
+        self.varint(delta.changed_tanks.len() as u64);
+        for change in &delta.changed_tanks {
+            self.tank_delta(change, &tanks);
+        }
+        self.varint(delta.despawned_tanks.len() as u64);
+        for id in &delta.despawned_tanks {
+            self.entity_ref(id, &tanks);
+        }
+
+        self.varint(delta.spawned_bullets.len() as u64);
+        for bullet in &delta.spawned_bullets {
+            self.bullet(bullet, &tanks);
+        }
+        self.varint(delta.moved_bullets.len() as u64);
+        for moved in &delta.moved_bullets {
+            self.entity_ref(&moved.id, &bullets);
+            self.position(&moved.position);
+        }
+        self.varint(delta.despawned_bullets.len() as u64);
+        for id in &delta.despawned_bullets {
+            self.entity_ref(id, &bullets);
+        }
+
+        self.acks(&delta.last_processed_input, &tanks);
+        match &delta.zones {
+            Some(zones) => {
+                self.u8(1);
+                self.zones(zones);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn tank_delta(&mut self, change: &TankDelta, tanks: &EntityIndex) {
+        self.entity_ref(&change.id, tanks);
+        let mut mask = 0;
+        for (present, bit) in [
+            (change.position.is_some(), TANK_POSITION),
+            (change.rotation.is_some(), TANK_ROTATION),
+            (change.turret_rotation.is_some(), TANK_TURRET_ROTATION),
+            (change.health.is_some(), TANK_HEALTH),
+            (change.is_dead.is_some(), TANK_IS_DEAD),
+            (change.respawn_time.is_some(), TANK_RESPAWN_TIME),
+            (change.last_fire_time.is_some(), TANK_LAST_FIRE_TIME),
+            (change.spawn_protected_until.is_some(), TANK_SPAWN_PROTECTED_UNTIL),
+            (change.team.is_some(), TANK_TEAM),
+        ] {
+            if present {
+                mask |= bit;
+            }
+        }
+        self.varint(mask);
+
+        if let Some(position) = &change.position {
+            self.position(position);
+        }
+        if let Some(rotation) = change.rotation {
+            self.angle(rotation);
+        }
+        if let Some(turret_rotation) = change.turret_rotation {
+            self.angle(turret_rotation);
+        }
+        if let Some(health) = change.health {
+            self.signed(health as i64);
+        }
+        if let Some(is_dead) = change.is_dead {
+            self.u8(is_dead as u8);
+        }
+        if let Some(respawn_time) = change.respawn_time {
+            self.optional_time(respawn_time);
+        }
+        if let Some(last_fire_time) = change.last_fire_time {
+            self.varint(last_fire_time);
+        }
+        if let Some(protected_until) = change.spawn_protected_until {
+            self.optional_time(protected_until);
+        }
+        if let Some(team) = change.team {
+            self.team(team);
+        }
+    }
+
+    fn optional_time(&mut self, time: Option<u64>) {
+        match time {
+            Some(time) => {
+                self.u8(1);
+                self.varint(time);
+            }
+            None => self.u8(0),
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self, WireError> {
+        let mut reader = Self { bytes, pos: 0 };
+        match reader.u8()? {
+            WIRE_VERSION => Ok(reader),
+            other => Err(WireError::UnsupportedVersion(other)),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], WireError> {
+        let end = self.pos.checked_add(len).ok_or(WireError::UnexpectedEnd)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(WireError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, WireError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, WireError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(WireError::UnexpectedEnd)
+    }
+
+    fn len(&mut self) -> Result<usize, WireError> {
+        let len = self.varint()? as usize;
+        // Every element takes at least one byte, which bounds allocations
+        // made for a corrupt length
+        if len > self.bytes.len() - self.pos {
+            return Err(WireError::UnexpectedEnd);
+        }
+        Ok(len)
+    }
+
+    fn signed(&mut self) -> Result<i64, WireError> {
+        let raw = self.varint()?;
+        Ok(((raw >> 1) as i64) ^ -((raw & 1) as i64))
+    }
+
+    fn fixed(&mut self) -> Result<f32, WireError> {
+        Ok(self.signed()? as f32 / POSITION_SCALE)
+    }
+
+    fn angle(&mut self) -> Result<f32, WireError> {
+        let bytes = self.take(2)?;
+        let steps = u16::from_le_bytes([bytes[0], bytes[1]]);
+        Ok(steps as f32 / ANGLE_STEPS * TAU)
+    }
+
+    fn position(&mut self) -> Result<Position, WireError> {
+        Ok(Position {
+            x: self.fixed()?,
+            y: self.fixed()?,
+        })
+    }
+
+    fn id(&mut self) -> Result<String, WireError> {
+        match self.u8()? {
+            ID_UUID => {
+                let bytes = self.take(16)?;
+                let uuid = uuid::Uuid::from_slice(bytes).map_err(|_| WireError::InvalidId)?;
+                Ok(uuid.hyphenated().to_string())
+            }
+            ID_STRING => {
+                let len = self.len()?;
+                let bytes = self.take(len)?;
+                String::from_utf8(bytes.to_vec()).map_err(|_| WireError::InvalidId)
+            }
+            _ => Err(WireError::InvalidId),
+        }
+    }
+
+    fn entity_ref(&mut self, ids: &[&str]) -> Result<String, WireError> {
+        match self.varint()? as usize {
+            0 => self.id(),
+            i => ids
+                .get(i - 1)
+                .map(|id| id.to_string())
+                .ok_or(WireError::UnknownEntity(i - 1)),
+        }
+    }
+
+    fn json<T: for<'de> Deserialize<'de>>(&mut self) -> Result<T, WireError> {
+        let rest = self.take(self.bytes.len() - self.pos)?;
+        serde_json::from_slice(rest).map_err(|e| WireError::Json(e.to_string()))
+    }
+
+    fn tank(&mut self) -> Result<Tank, WireError> {
+        let id = self.id()?;
+        let position = self.position()?;
+        let rotation = self.angle()?;
+        let turret_rotation = self.angle()?;
+        let flags = self.u8()?;
+        let health = self.signed()? as i32;
+        let respawn_time = if flags & FLAG_HAS_RESPAWN_TIME != 0 {
+            Some(self.varint()?)
+        } else {
+            None
+        };
+        Ok(Tank {
+            id,
+            position,
+            rotation,
+            turret_rotation,
+            is_player: flags & FLAG_IS_PLAYER != 0,
+            health,
+            is_dead: flags & FLAG_IS_DEAD != 0,
+            respawn_time,
+            last_fire_time: self.varint()?,
+            spawn_protected_until: if flags & FLAG_HAS_SPAWN_PROTECTION != 0 {
+                Some(self.varint()?)
+            } else {
+                None
+            },
+            team: if flags & FLAG_TEAM_RED != 0 {
+                Some(Team::Red)
+            } else if flags & FLAG_TEAM_BLUE != 0 {
+                Some(Team::Blue)
+            } else {
+                None
+            },
+        })
+    }
+
+    fn team(&mut self) -> Result<Option<Team>, WireError> {
+        Ok(match self.u8()? {
+            TEAM_RED => Some(Team::Red),
+            TEAM_BLUE => Some(Team::Blue),
+            _ => None,
+        })
+    }
+
+    fn zones(&mut self) -> Result<Vec<CaptureZone>, WireError> {
+        (0..self.len()?)
+            .map(|_| {
+                let len = self.len()?;
+                let name = String::from_utf8(self.take(len)?.to_vec()).map_err(|_| WireError::InvalidId)?;
+                Ok(CaptureZone {
+                    name,
+                    position: self.position()?,
+                    radius: self.fixed()?,
+                    owner: self.team()?,
+                    capturing_team: self.team()?,
+                    progress: self.varint()? as f32 / PROGRESS_STEPS,
+                    contested: self.u8()? != 0,
+                })
+            })
+            .collect()
+    }
+
+    fn bullet(&mut self, tank_ids: &[&str]) -> Result<Bullet, WireError> {
+        Ok(Bullet {
+            id: self.id()?,
+            position: self.position()?,
+            velocity: Velocity {
+                x: self.fixed()?,
+                y: self.fixed()?,
+            },
+            owner_id: self.entity_ref(tank_ids)?,
+            created_at: self.varint()?,
+            damage: self.signed()? as i32,
+        })
+    }
+
+    fn acks(&mut self, tank_ids: &[&str]) -> Result<HashMap<String, u32>, WireError> {
+        let count = self.len()?;
+        let mut acks = HashMap::with_capacity(count);
+        for _ in 0..count {
+            let player_id = self.entity_ref(tank_ids)?;
+            acks.insert(player_id, self.varint()? as u32);
+        }
+        Ok(acks)
+    }
+
+    fn game_state(&mut self) -> Result<GameState, WireError> {
+        let tick = self.varint()?;
+        let server_time = self.varint()?;
+        let tanks = (0..self.len()?).map(|_| self.tank()).collect::<Result<Vec<_>, _>>()?;
+        let tank_ids: Vec<&str> = tanks.iter().map(|t| t.id.as_str()).collect();
+        let bullets = (0..self.len()?)
+            .map(|_| self.bullet(&tank_ids))
+            .collect::<Result<Vec<_>, _>>()?;
+        let last_processed_input = self.acks(&tank_ids)?;
+        Ok(GameState {
+            tick,
+            server_time,
+            tanks,
+            bullets,
+            last_processed_input,
+            zones: self.zones()?,
+        })
+    }
+
+    fn delta(&mut self, base_tick: u64, base: &GameState) -> Result<GameStateDelta, WireError> {
+        let tick = self.varint()?;
+        let server_time = self.varint()?;
+
+        let spawned_tanks = (0..self.len()?).map(|_| self.tank()).collect::<Result<Vec<_>, _>>()?;
+        let tank_ids: Vec<&str> = base
+            .tanks
+            .iter()
+            .chain(&spawned_tanks)
+            .map(|t| t.id.as_str())
+            .collect();
+        let bullet_ids: Vec<&str> = base.bullets.iter().map(|b| b.id.as_str()).collect();
+
+        let changed_tanks = (0..self.len()?)
+            .map(|_| self.tank_delta(&tank_ids))
+            .collect::<Result<Vec<_>, _>>()?;
+        let despawned_tanks = (0..self.len()?)
+            .map(|_| self.entity_ref(&tank_ids))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let spawned_bullets = (0..self.len()?)
+            .map(|_| self.bullet(&tank_ids))
+            .collect::<Result<Vec<_>, _>>()?;
+        let moved_bullets = (0..self.len()?)
+            .map(|_| {
+                Ok(BulletDelta {
+                    id: self.entity_ref(&bullet_ids)?,
+                    position: self.position()?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let despawned_bullets = (0..self.len()?)
+            .map(|_| self.entity_ref(&bullet_ids))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let last_processed_input = self.acks(&tank_ids)?;
+        let zones = match self.u8()? {
+            0 => None,
+            _ => Some(self.zones()?),
+        };
+
+        Ok(GameStateDelta {
+            base_tick,
+            tick,
+            server_time,
+            spawned_tanks,
+            changed_tanks,
+            despawned_tanks,
+            spawned_bullets,
+            moved_bullets,
+            despawned_bullets,
+            last_processed_input,
+            zones,
+        })
+    }
+
+    fn tank_delta(&mut self, tank_ids: &[&str]) -> Result<TankDelta, WireError> {
+        let id = self.entity_ref(tank_ids)?;
+        let mask = self.varint()?;
+        let has = |bit: u64| mask & bit != 0;
+
+        Ok(TankDelta {
+            id,
+            position: if has(TANK_POSITION) { Some(self.position()?) } else { None },
+            rotation: if has(TANK_ROTATION) { Some(self.angle()?) } else { None },
+            turret_rotation: if has(TANK_TURRET_ROTATION) { Some(self.angle()?) } else { None },
+            health: if has(TANK_HEALTH) { Some(self.signed()? as i32) } else { None },
+            is_dead: if has(TANK_IS_DEAD) { Some(self.u8()? != 0) } else { None },
+            respawn_time: if has(TANK_RESPAWN_TIME) { Some(self.optional_time()?) } else { None },
+            last_fire_time: if has(TANK_LAST_FIRE_TIME) { Some(self.varint()?) } else { None },
+            spawn_protected_until: if has(TANK_SPAWN_PROTECTED_UNTIL) {
+                Some(self.optional_time()?)
+            } else {
+                None
+            },
+            team: if has(TANK_TEAM) { Some(self.team()?) } else { None },
+        })
+    }
+
+    fn optional_time(&mut self) -> Result<Option<u64>, WireError> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => Ok(Some(self.varint()?)),
+        }
+    }
+}
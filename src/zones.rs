@@ -0,0 +1,162 @@
+//! King-of-the-hill and domination capture zones.
+//!
+//! A zone's `progress` measures how far `capturing_team` has got towards
+//! owning it. A lone team inside pushes progress up, or first wears down
+//! another team's hold; with both teams inside the zone is contested and
+//! frozen. Empty zones drift back to their owner, or to neutral.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CaptureZone, GameMode, Position, Tank, Team, ZoneEvent, ZoneEventKind};
+
+/// Seconds one tank needs to take a neutral zone
+pub const CAPTURE_SECS: f32 = 5.0;
+/// Extra tanks speed up a capture, up to this many
+pub const MAX_CAPTURERS: usize = 3;
+/// How fast an empty zone recovers, relative to one tank capturing
+pub const RECOVERY_RATE: f32 = 0.5;
+/// Held zones score one team point per this many seconds
+pub const ZONE_SCORE_INTERVAL_SECS: f32 = 1.0;
+
+// Absorbs the rounding left after summing many per-tick steps
+const PROGRESS_EPSILON: f32 = 1e-4;
+
+pub const HILL_RADIUS: f32 = 120.0;
+pub const DOMINATION_RADIUS: f32 = 100.0;
+/// Domination zones sit at the centre and this far either side of it
+pub const DOMINATION_SPACING: f32 = 450.0;
+
+/// Where a map puts a capture zone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneSpot {
+    pub name: String,
+    pub position: Position,
+    pub radius: f32,
+}
+
+impl ZoneSpot {
+    /// The zone, neutral.
+    pub fn zone(&self) -> CaptureZone {
+        neutral(&self.name, self.position.clone(), self.radius)
+    }
+}
+
+fn zone(name: &str, x: f32, radius: f32) -> CaptureZone {
+    neutral(name, Position { x, y: 0.0 }, radius)
+}
+
+fn neutral(name: &str, position: Position, radius: f32) -> CaptureZone {
+    CaptureZone {
+        name: name.to_string(),
+        position,
+        radius,
+        owner: None,
+        capturing_team: None,
+        progress: 0.0,
+        contested: false,
+    }
+}
+
+/// The neutral zones `mode` is played over on a map that doesn't place its
+/// own; none outside zone modes.
+pub fn zones_for_mode(mode: GameMode) -> Vec<CaptureZone> {
+    match mode {
+        GameMode::KingOfTheHill => vec![zone("Hill", 0.0, HILL_RADIUS)],
+        GameMode::Domination => vec![
+            zone("A", -DOMINATION_SPACING, DOMINATION_RADIUS),
+            zone("B", 0.0, DOMINATION_RADIUS),
+            zone("C", DOMINATION_SPACING, DOMINATION_RADIUS),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+fn inside(zone: &CaptureZone, position: &Position) -> bool {
+    let dx = position.x - zone.position.x;
+    let dy = position.y - zone.position.y;
+    dx * dx + dy * dy <= zone.radius * zone.radius
+}
+
+/// Moves `zone` towards `team` by `amount` of a full capture.
+fn push(zone: &mut CaptureZone, team: Team, amount: f32) -> Option<ZoneEventKind> {
+    if zone.capturing_team.is_none_or(|capturing| capturing == team) {
+        zone.capturing_team = Some(team);
+        zone.progress += amount;
+        if zone.progress < 1.0 - PROGRESS_EPSILON {
+            return None;
+        }
+        zone.progress = 1.0;
+        if zone.owner != Some(team) {
+            zone.owner = Some(team);
+            return Some(ZoneEventKind::Captured);
+        }
+        return None;
+    }
+
+    zone.progress -= amount;
+    if zone.progress > PROGRESS_EPSILON {
+        return None;
+    }
+    zone.progress = 0.0;
+    zone.capturing_team = Some(team);
+    if zone.owner.is_some_and(|owner| owner != team) {
+        zone.owner = None;
+        return Some(ZoneEventKind::Neutralized);
+    }
+    None
+}
+
+/// Advances every zone by `dt` seconds given the tanks in play, returning
+/// the zones that changed hands.
+pub fn update_zones(zones: &mut [CaptureZone], tanks: &[Tank], dt: f32) -> Vec<ZoneEvent> {
+    let rate = dt / CAPTURE_SECS;
+    let mut events = Vec::new();
+
+    for zone in zones.iter_mut() {
+        let count = |team: Team| {
+            tanks
+                .iter()
+                .filter(|t| !t.is_dead && t.team == Some(team) && inside(zone, &t.position))
+                .count()
+        };
+        let (red, blue) = (count(Team::Red), count(Team::Blue));
+        zone.contested = red > 0 && blue > 0;
+        if zone.contested {
+            continue;
+        }
+
+        let (team, kind) = match (red, blue) {
+            (0, 0) => match zone.owner {
+                Some(owner) => (owner, push(zone, owner, rate * RECOVERY_RATE)),
+                None => {
+                    zone.progress -= rate * RECOVERY_RATE;
+                    if zone.progress <= PROGRESS_EPSILON {
+                        zone.progress = 0.0;
+                        zone.capturing_team = None;
+                    }
+                    continue;
+                }
+            },
+            (red, 0) => (Team::Red, push(zone, Team::Red, rate * red.min(MAX_CAPTURERS) as f32)),
+            (_, blue) => (Team::Blue, push(zone, Team::Blue, rate * blue.min(MAX_CAPTURERS) as f32)),
+        };
+        if let Some(kind) = kind {
+            events.push(ZoneEvent {
+                kind,
+                zone: zone.name.clone(),
+                team,
+            });
+        }
+    }
+
+    events
+}
+
+/// Team points for one scoring interval: one per zone held.
+pub fn held_zone_points(zones: &[CaptureZone]) -> Vec<(Team, u32)> {
+    Team::ALL
+        .iter()
+        .map(|&team| (team, zones.iter().filter(|z| z.owner == Some(team)).count() as u32))
+        .filter(|&(_, points)| points > 0)
+        .collect()
+}
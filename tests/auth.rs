@@ -0,0 +1,68 @@
+use battlexone_shared::auth::{account_user_id, hash_password, AuthError, AuthMode, TokenSigner};
+use std::collections::HashMap;
+
+const NOW: u64 = 1_700_000_000;
+
+fn signer() -> TokenSigner {
+    TokenSigner::new(b"server secret", 3600)
+}
+
+#[test]
+fn test_when_token_is_presented_again_then_it_resumes_the_same_user() {
+    let signer = signer();
+    let token = signer.issue("window_abc", NOW);
+    assert_eq!(signer.verify(&token, NOW + 10), Ok("window_abc".to_string()));
+
+    // Dots in the user id don't confuse the parser
+    let token = signer.issue("account:a.b", NOW);
+    assert_eq!(signer.verify(&token, NOW), Ok("account:a.b".to_string()));
+}
+
+#[test]
+fn test_when_token_is_tampered_with_then_it_is_refused() {
+    let signer = signer();
+    let token = signer.issue("alice", NOW);
+
+    let claimed = token.replacen("alice", "mallory", 1);
+    assert_eq!(signer.verify(&claimed, NOW), Err(AuthError::InvalidToken));
+    let extended = token.replacen(&(NOW + 3600).to_string(), &(NOW + 999_999).to_string(), 1);
+    assert_eq!(signer.verify(&extended, NOW), Err(AuthError::InvalidToken));
+    assert_eq!(signer.verify("alice", NOW), Err(AuthError::InvalidToken));
+    assert_eq!(signer.verify("alice.1.zz", NOW), Err(AuthError::InvalidToken));
+    // Another server's tokens aren't ours
+    let other = TokenSigner::new(b"other secret", 3600).issue("alice", NOW);
+    assert_eq!(signer.verify(&other, NOW), Err(AuthError::InvalidToken));
+}
+
+#[test]
+fn test_when_token_is_old_then_it_has_expired() {
+    let signer = signer();
+    let token = signer.issue("alice", NOW);
+    assert!(signer.verify(&token, NOW + 3599).is_ok());
+    assert_eq!(signer.verify(&token, NOW + 3600), Err(AuthError::ExpiredToken));
+}
+
+#[test]
+fn test_when_server_is_open_then_anyone_joins_as_a_newcomer() {
+    assert_eq!(AuthMode::Open.admit("Player", None), Ok(None));
+    assert_eq!(AuthMode::Open.admit("Player", Some("anything")), Ok(None));
+}
+
+#[test]
+fn test_when_server_has_a_shared_key_then_joining_needs_it() {
+    let auth = AuthMode::SharedKey("hunter2".to_string());
+    assert_eq!(auth.admit("Player", Some("hunter2")), Ok(None));
+    assert_eq!(auth.admit("Player", Some("hunter3")), Err(AuthError::WrongCredentials));
+    assert_eq!(auth.admit("Player", None), Err(AuthError::MissingCredentials));
+}
+
+#[test]
+fn test_when_server_has_accounts_then_name_and_password_are_checked_and_identity_kept() {
+    let auth = AuthMode::Accounts(HashMap::from([("alice".to_string(), hash_password("wonderland"))]));
+
+    assert_eq!(auth.admit("alice", Some("wonderland")), Ok(Some(account_user_id("alice"))));
+    assert_eq!(auth.admit("alice", Some("looking glass")), Err(AuthError::WrongCredentials));
+    // No account, even with a password that would fit someone else's
+    assert_eq!(auth.admit("bob", Some("wonderland")), Err(AuthError::WrongCredentials));
+    assert_eq!(auth.admit("alice", None), Err(AuthError::MissingCredentials));
+}
@@ -0,0 +1,206 @@
+use battlexone_shared::bullets::{bullet_from, fire, update_bullets, BulletResults, BulletWorld, MUZZLE_DISTANCE};
+use battlexone_shared::map::{Map, Wall};
+use battlexone_shared::rewind::TankHistory;
+use battlexone_shared::server_config::Tuning;
+use battlexone_shared::{Bullet, Position, Tank, Team, Velocity};
+use std::collections::HashMap;
+
+mod common;
+use common::tank;
+
+const DT: f32 = 0.1;
+const NOW: u64 = 1_000_000;
+const SERVER_TIME: u64 = 50_000;
+const RESPAWN_DELAY_MS: u64 = 5000;
+
+/// A bullet from `owner` at (0, -40) heading up at 200 units a second, so
+/// it covers (0, -40) to (0, -20) in one tick of `DT`.
+fn bullet(id: &str, owner: &str) -> Bullet {
+    Bullet {
+        id: id.to_string(),
+        position: Position { x: 0.0, y: -40.0 },
+        velocity: Velocity { x: 0.0, y: 200.0 },
+        owner_id: owner.to_string(),
+        created_at: NOW,
+        damage: 25,
+    }
+}
+
+/// Runs one tick on `map` with an empty history unless `history` is given.
+fn tick(
+    bullets: &mut Vec<Bullet>,
+    tanks: &mut [Tank],
+    map: &Map,
+    friendly_fire: bool,
+    history: Option<(&TankHistory, u64)>,
+    rewind: &mut HashMap<String, u64>,
+) -> BulletResults {
+    let empty = TankHistory::new(0);
+    let (history, tick) = history.unwrap_or((&empty, 1));
+    let world = BulletWorld {
+        map,
+        tuning: Tuning::default(),
+        friendly_fire,
+        respawn_delay_ms: RESPAWN_DELAY_MS,
+        history,
+        tick,
+    };
+    update_bullets(bullets, tanks, rewind, &world, DT, SERVER_TIME, NOW)
+}
+
+fn shooter(team: Option<Team>) -> Tank {
+    let builder = tank("shooter").at(0.0, -300.0);
+    match team {
+        Some(team) => builder.team(team).build(),
+        None => builder.build(),
+    }
+}
+
+#[test]
+fn test_when_bullet_reaches_a_tank_then_it_deals_damage_and_is_used_up() {
+    let mut tanks = vec![shooter(None), tank("target").build()];
+    let mut bullets = vec![bullet("b", "shooter")];
+
+    let results = tick(&mut bullets, &mut tanks, &Map::default(), false, None, &mut HashMap::new());
+
+    assert!(bullets.is_empty());
+    assert_eq!(results.hits.len(), 1);
+    let hit = &results.hits[0];
+    assert_eq!((hit.attacker_id.as_str(), hit.target_id.as_str()), ("shooter", "target"));
+    assert_eq!(hit.damage, 25);
+    assert!(!hit.killed);
+    assert_eq!(tanks[1].health, 75);
+    assert!(results.deaths.is_empty());
+}
+
+#[test]
+fn test_when_a_hit_kills_then_the_tank_waits_to_respawn_and_the_team_scores() {
+    let mut tanks = vec![shooter(Some(Team::Red)), tank("target").team(Team::Blue).build()];
+    tanks[1].health = 10;
+    let mut bullets = vec![bullet("b", "shooter")];
+
+    let results = tick(&mut bullets, &mut tanks, &Map::default(), false, None, &mut HashMap::new());
+
+    assert!(results.hits[0].killed);
+    assert!(tanks[1].is_dead);
+    assert_eq!(tanks[1].respawn_time, Some(SERVER_TIME + RESPAWN_DELAY_MS));
+    assert_eq!(results.deaths, vec![(SERVER_TIME, Position { x: 0.0, y: 0.0 })]);
+    assert_eq!(results.team_kills, vec![Team::Red]);
+}
+
+#[test]
+fn test_when_friendly_fire_is_off_then_bullets_pass_through_teammates() {
+    let tanks = vec![shooter(Some(Team::Red)), tank("mate").team(Team::Red).build()];
+
+    let mut without = tanks.clone();
+    let mut bullets = vec![bullet("b", "shooter")];
+    let results = tick(&mut bullets, &mut without, &Map::default(), false, None, &mut HashMap::new());
+    assert!(results.hits.is_empty());
+    assert_eq!(bullets.len(), 1);
+
+    let mut with = tanks;
+    let mut bullets = vec![bullet("b", "shooter")];
+    let results = tick(&mut bullets, &mut with, &Map::default(), true, None, &mut HashMap::new());
+    assert_eq!(results.hits.len(), 1);
+    assert!(results.team_kills.is_empty());
+}
+
+#[test]
+fn test_when_target_is_spawn_protected_or_behind_a_wall_then_it_is_not_hurt() {
+    let mut tanks = vec![shooter(None), tank("target").build()];
+    tanks[1].spawn_protected_until = Some(SERVER_TIME + 1);
+    let mut bullets = vec![bullet("b", "shooter")];
+    let results = tick(&mut bullets, &mut tanks, &Map::default(), false, None, &mut HashMap::new());
+    assert!(results.hits.is_empty() && bullets.is_empty());
+    assert_eq!(tanks[1].health, 100);
+
+    tanks[1].spawn_protected_until = None;
+    let wall = Map {
+        walls: vec![Wall {
+            from: Position { x: -50.0, y: -35.0 },
+            to: Position { x: 50.0, y: -35.0 },
+            thickness: 4.0,
+        }],
+        ..Map::default()
+    };
+    let mut bullets = vec![bullet("b", "shooter")];
+    let results = tick(&mut bullets, &mut tanks, &wall, false, None, &mut HashMap::new());
+    assert!(results.hits.is_empty() && bullets.is_empty());
+    assert_eq!(tanks[1].health, 100);
+}
+
+#[test]
+fn test_when_bullet_outlives_its_lifetime_then_it_is_removed() {
+    let mut tanks = vec![shooter(None)];
+    let mut old = bullet("old", "shooter");
+    old.created_at = NOW - Tuning::default().bullet_lifetime_ms - 1;
+    let mut bullets = vec![old, bullet("new", "shooter")];
+
+    tick(&mut bullets, &mut tanks, &Map::default(), false, None, &mut HashMap::new());
+
+    let ids: Vec<&str> = bullets.iter().map(|b| b.id.as_str()).collect();
+    assert_eq!(ids, ["new"]);
+}
+
+#[test]
+fn test_when_shooter_lagged_then_bullet_hits_where_the_target_was() {
+    // The target stood on the bullet's path at tick 5 and has moved off it since
+    let mut history = TankHistory::new(8);
+    let mut tanks = vec![shooter(None), tank("target").build()];
+    history.record(5, &tanks);
+    tanks[1].position.x = 100.0;
+    history.record(10, &tanks);
+
+    let mut present = vec![bullet("b", "shooter")];
+    let results = tick(&mut present, &mut tanks.clone(), &Map::default(), false, Some((&history, 10)), &mut HashMap::new());
+    assert!(results.hits.is_empty());
+
+    let mut rewind = HashMap::from([("b".to_string(), 5), ("gone".to_string(), 5)]);
+    let mut lagged = vec![bullet("b", "shooter")];
+    let results = tick(&mut lagged, &mut tanks, &Map::default(), false, Some((&history, 10)), &mut rewind);
+    assert_eq!(results.hits.len(), 1);
+    assert_eq!(tanks[1].health, 75);
+    assert!(rewind.is_empty(), "{:?}", rewind);
+}
+
+#[test]
+fn test_when_tank_fires_point_blank_at_a_thin_wall_then_the_bullet_stops_on_it() {
+    // Pressed against a wall thinner than the barrel, with a target close
+    // behind it that the barrel alone would reach
+    let up = std::f32::consts::FRAC_PI_2;
+    let target_y = MUZZLE_DISTANCE + 20.0;
+    let thin_wall = Map {
+        walls: vec![Wall {
+            from: Position { x: -50.0, y: 18.0 },
+            to: Position { x: 50.0, y: 18.0 },
+            thickness: 4.0,
+        }],
+        ..Map::default()
+    };
+    let history = TankHistory::new(0);
+    let fire_on = |map: &Map| {
+        let mut tanks = vec![tank("shooter").turret(up).build(), tank("target").at(0.0, target_y).build()];
+        let world = BulletWorld {
+            map,
+            tuning: Tuning::default(),
+            friendly_fire: false,
+            respawn_delay_ms: RESPAWN_DELAY_MS,
+            history: &history,
+            tick: 1,
+        };
+        let bullet = bullet_from(&tanks[0], &world.tuning, NOW);
+        let mut bullets = Vec::new();
+        let results = fire(bullet, 0, &mut bullets, &mut HashMap::new(), &mut tanks, &world, SERVER_TIME);
+        (results, bullets, tanks)
+    };
+
+    let (results, bullets, tanks) = fire_on(&Map::default());
+    assert_eq!(results.hits.len(), 1);
+    assert!(bullets.is_empty());
+    assert_eq!(tanks[1].health, 75);
+
+    let (results, bullets, tanks) = fire_on(&thin_wall);
+    assert!(results.hits.is_empty());
+    assert!(bullets.is_empty(), "{:?}", bullets);
+    assert_eq!(tanks[1].health, 100);
+}
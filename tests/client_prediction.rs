@@ -0,0 +1,94 @@
+use battlexone_shared::client_prediction::{Prediction, MAX_PENDING_INPUTS};
+use battlexone_shared::map::Map;
+use battlexone_shared::movement::{apply_movement, TANK_SPEED};
+use battlexone_shared::Tank;
+
+mod common;
+use common::tank;
+
+const FORWARD: u16 = 1;
+const TURN_LEFT: u16 = 1 << 1;
+const EPSILON: f32 = 1e-3;
+
+/// The server's view: `inputs` applied in order at `tick_rate`.
+fn simulate(inputs: &[u16], tick_rate: u32, map: &Map) -> Tank {
+    let mut tank = tank("me").build();
+    for &input in inputs {
+        apply_movement(&mut tank, input, 1.0 / tick_rate as f32, map);
+    }
+    tank
+}
+
+fn assert_same_place(a: &Tank, b: &Tank) {
+    assert!((a.position.x - b.position.x).abs() < EPSILON, "{:?} vs {:?}", a.position, b.position);
+    assert!((a.position.y - b.position.y).abs() < EPSILON, "{:?} vs {:?}", a.position, b.position);
+    assert!((a.rotation - b.rotation).abs() < EPSILON);
+}
+
+#[test]
+fn test_when_server_has_applied_some_inputs_then_the_rest_are_replayed_on_top() {
+    let map = Map::default();
+    let inputs = [FORWARD, FORWARD | TURN_LEFT, FORWARD, TURN_LEFT, FORWARD];
+    for tick_rate in [30, 60] {
+        let mut prediction = Prediction::new(tick_rate);
+        for (seq, &input) in (1..).zip(&inputs) {
+            prediction.record_input(seq, input);
+        }
+
+        let authoritative = simulate(&inputs[..3], tick_rate, &map);
+        prediction.reconcile(&authoritative, 3, &map);
+        assert_eq!(prediction.pending_inputs(), 2);
+        assert_same_place(prediction.predicted_tank().unwrap(), &simulate(&inputs, tick_rate, &map));
+    }
+}
+
+#[test]
+fn test_when_server_ticks_slower_then_each_input_moves_further() {
+    let map = Map::default();
+    let start = tank("me").build();
+    for tick_rate in [20, 60, 120] {
+        let mut prediction = Prediction::new(tick_rate);
+        prediction.record_input(1, FORWARD);
+        prediction.reconcile(&start, 0, &map);
+        let moved = prediction.predicted_tank().unwrap().position.x;
+        assert!((moved - TANK_SPEED / tick_rate as f32).abs() < EPSILON, "{} Hz moved {}", tick_rate, moved);
+        assert!((prediction.dt() - 1.0 / tick_rate as f32).abs() < f32::EPSILON);
+    }
+}
+
+#[test]
+fn test_when_every_input_is_acknowledged_then_the_server_tank_is_shown() {
+    let map = Map::default();
+    let mut prediction = Prediction::new(60);
+    prediction.record_input(1, FORWARD);
+    prediction.record_input(2, FORWARD);
+
+    let authoritative = tank("me").at(40.0, 10.0).build();
+    prediction.reconcile(&authoritative, 2, &map);
+    assert_eq!(prediction.pending_inputs(), 0);
+    assert_eq!(prediction.predicted_tank(), Some(&authoritative));
+}
+
+#[test]
+fn test_when_acks_stop_arriving_then_pending_inputs_are_capped() {
+    let mut prediction = Prediction::new(60);
+    for seq in 1..=(MAX_PENDING_INPUTS as u32 * 2) {
+        prediction.record_input(seq, FORWARD);
+    }
+    assert_eq!(prediction.pending_inputs(), MAX_PENDING_INPUTS);
+}
+
+#[test]
+fn test_when_prediction_is_applied_then_only_the_local_tank_moves() {
+    let map = Map::default();
+    let mut prediction = Prediction::new(60);
+    let mut tanks = vec![tank("me").build(), tank("other").at(5.0, 5.0).build()];
+    prediction.apply_to(&mut tanks);
+    assert_eq!(tanks[0].position.x, 0.0);
+
+    prediction.record_input(1, FORWARD);
+    prediction.reconcile(&tanks[0].clone(), 0, &map);
+    prediction.apply_to(&mut tanks);
+    assert!(tanks[0].position.x > 0.0);
+    assert_eq!(tanks[1], tank("other").at(5.0, 5.0).build());
+}
@@ -0,0 +1,126 @@
+use battlexone_shared::collision::separate_tanks;
+use battlexone_shared::map::{Map, MapBox, TANK_RADIUS};
+use battlexone_shared::movement::{apply_movement, SIMULATION_DT};
+use battlexone_shared::{Position, Tank};
+use std::f32::consts::PI;
+
+mod common;
+use common::tank;
+
+const FORWARD: u16 = 0b1;
+
+fn distance(a: &Tank, b: &Tank) -> f32 {
+    (a.position.x - b.position.x).hypot(a.position.y - b.position.y)
+}
+
+/// Drives every tank forward for `ticks` ticks the way the server does:
+/// everyone moves, then overlaps are resolved.
+fn drive(tanks: &mut [Tank], ticks: usize, map: &Map) {
+    for _ in 0..ticks {
+        for tank in tanks.iter_mut() {
+            apply_movement(tank, FORWARD, SIMULATION_DT, map);
+        }
+        separate_tanks(tanks, map);
+    }
+}
+
+fn assert_no_overlaps(tanks: &[Tank]) {
+    for (i, a) in tanks.iter().enumerate() {
+        for b in &tanks[i + 1..] {
+            assert!(
+                distance(a, b) >= TANK_RADIUS * 2.0 - 0.01,
+                "{} and {} overlap: {:?} vs {:?}",
+                a.id,
+                b.id,
+                a.position,
+                b.position
+            );
+        }
+    }
+}
+
+#[test]
+fn test_when_tanks_drive_head_on_then_they_stop_touching() {
+    let map = Map::default();
+    let mut tanks = [tank("left").at(-100.0, 0.0).build(), tank("right").at(100.0, 0.0).facing(PI).build()];
+
+    // They meet after about 34 ticks and keep pushing
+    drive(&mut tanks, 60, &map);
+
+    assert!((distance(&tanks[0], &tanks[1]) - TANK_RADIUS * 2.0).abs() < 0.01);
+    // Equal and opposite, so they meet in the middle and neither gives way
+    assert!((tanks[0].position.x + TANK_RADIUS).abs() < 0.01, "{:?}", tanks[0].position);
+    assert!((tanks[1].position.x - TANK_RADIUS).abs() < 0.01, "{:?}", tanks[1].position);
+    assert!(tanks[0].position.y.abs() < 0.01 && tanks[1].position.y.abs() < 0.01);
+}
+
+#[test]
+fn test_when_tank_clips_a_parked_tank_then_it_slides_past() {
+    let map = Map::default();
+    // Offset by less than a tank's width, heading right past a parked tank
+    let mut moving = tank("moving").at(-100.0, 10.0).build();
+    let mut parked = tank("parked").build();
+
+    for _ in 0..120 {
+        apply_movement(&mut moving, FORWARD, SIMULATION_DT, &map);
+        let mut pair = [moving, parked];
+        separate_tanks(&mut pair, &map);
+        [moving, parked] = pair;
+        assert!(distance(&moving, &parked) >= TANK_RADIUS * 2.0 - 0.01);
+    }
+
+    assert!(moving.position.x > 50.0, "moving tank got stuck at {:?}", moving.position);
+    assert!(moving.position.y > 10.0, "moving tank should be deflected sideways");
+    assert!(parked.position.y < 0.0, "parked tank should be nudged the other way");
+}
+
+#[test]
+fn test_when_many_tanks_pile_up_then_all_are_separated() {
+    let map = Map::default();
+    let mut tanks: Vec<Tank> = (0..6).map(|i| tank(&format!("t{}", i)).build()).collect();
+    tanks.extend((0..4).map(|i| {
+        let angle = i as f32 * PI / 2.0;
+        tank(&format!("r{}", i)).at(-angle.cos() * 80.0, -angle.sin() * 80.0).facing(angle).build()
+    }));
+
+    // Four tanks drive into a stack of six for a second
+    for _ in 0..60 {
+        for tank in tanks[6..].iter_mut() {
+            apply_movement(tank, FORWARD, SIMULATION_DT, &map);
+        }
+        separate_tanks(&mut tanks, &map);
+    }
+
+    assert_no_overlaps(&tanks);
+}
+
+#[test]
+fn test_when_tank_is_shoved_into_a_box_then_it_stays_out_of_it() {
+    let map = Map {
+        boxes: vec![MapBox {
+            min: Position { x: 20.0, y: -100.0 },
+            max: Position { x: 100.0, y: 100.0 },
+        }],
+        ..Map::default()
+    };
+    // The pusher drives right, pinning the other tank against the box
+    let mut tanks = [tank("pusher").at(-60.0, 0.0).build(), tank("pinned").at(-10.0, 0.0).build()];
+
+    for _ in 0..60 {
+        apply_movement(&mut tanks[0], FORWARD, SIMULATION_DT, &map);
+        separate_tanks(&mut tanks, &map);
+    }
+
+    assert!(!map.blocks_tank(&tanks[1].position), "pinned tank ended up in the box at {:?}", tanks[1].position);
+    assert!(tanks[0].position.x < tanks[1].position.x);
+}
+
+#[test]
+fn test_when_a_tank_is_dead_then_nothing_collides_with_it() {
+    let map = Map::default();
+    let mut tanks = [tank("alive").build(), tank("dead").at(5.0, 0.0).dead().build()];
+
+    assert!(!separate_tanks(&mut tanks, &map));
+    assert_eq!(tanks[0].position, Position { x: 0.0, y: 0.0 });
+    assert_eq!(tanks[1].position, Position { x: 5.0, y: 0.0 });
+}
@@ -0,0 +1,72 @@
+//! Fixtures shared by the integration tests.
+
+// Each test crate includes this module and uses only some of it
+#![allow(dead_code)]
+
+use battlexone_shared::{Position, Tank, Team, TANK_MAX_HEALTH};
+
+/// The point (`x`, `y`).
+pub fn pos(x: f32, y: f32) -> Position {
+    Position { x, y }
+}
+
+/// A living player tank called `id` at the origin, facing +x with no team,
+/// to adjust with the builder methods.
+pub fn tank(id: &str) -> TankBuilder {
+    TankBuilder(Tank {
+        id: id.to_string(),
+        position: Position { x: 0.0, y: 0.0 },
+        rotation: 0.0,
+        turret_rotation: 0.0,
+        is_player: true,
+        health: TANK_MAX_HEALTH,
+        is_dead: false,
+        respawn_time: None,
+        last_fire_time: 0,
+        spawn_protected_until: None,
+        team: None,
+    })
+}
+
+pub struct TankBuilder(Tank);
+
+impl TankBuilder {
+    pub fn at(self, x: f32, y: f32) -> Self {
+        self.position(pos(x, y))
+    }
+
+    pub fn position(mut self, position: Position) -> Self {
+        self.0.position = position;
+        self
+    }
+
+    /// Hull rotation in radians
+    pub fn facing(mut self, rotation: f32) -> Self {
+        self.0.rotation = rotation;
+        self
+    }
+
+    pub fn turret(mut self, turret_rotation: f32) -> Self {
+        self.0.turret_rotation = turret_rotation;
+        self
+    }
+
+    pub fn team(mut self, team: Team) -> Self {
+        self.0.team = Some(team);
+        self
+    }
+
+    pub fn npc(mut self) -> Self {
+        self.0.is_player = false;
+        self
+    }
+
+    pub fn dead(mut self) -> Self {
+        self.0.is_dead = true;
+        self
+    }
+
+    pub fn build(self) -> Tank {
+        self.0
+    }
+}
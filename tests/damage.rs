@@ -0,0 +1,56 @@
+use battlexone_shared::damage::{damage_from, hit_side, HitSide};
+use battlexone_shared::{Bullet, Position, Velocity};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+mod common;
+use common::tank;
+
+fn bullet_heading(heading: f32) -> Bullet {
+    Bullet {
+        id: "bullet".to_string(),
+        position: Position { x: 0.0, y: 0.0 },
+        velocity: Velocity {
+            x: heading.cos() * 200.0,
+            y: heading.sin() * 200.0,
+        },
+        owner_id: "attacker".to_string(),
+        created_at: 0,
+        damage: 25,
+    }
+}
+
+#[test]
+fn test_when_bullet_flies_into_the_hull_facing_then_front_armour_applies() {
+    // Tank faces +x, bullet travels -x towards its nose
+    assert_eq!(hit_side(0.0, PI), HitSide::Front);
+    assert_eq!(damage_from(&bullet_heading(PI), &tank("target").build()), (15, HitSide::Front));
+}
+
+#[test]
+fn test_when_bullet_flies_in_the_hull_direction_then_it_hits_the_rear() {
+    assert_eq!(hit_side(0.0, 0.0), HitSide::Rear);
+    assert_eq!(damage_from(&bullet_heading(0.0), &tank("target").build()), (38, HitSide::Rear));
+}
+
+#[test]
+fn test_when_bullet_crosses_the_hull_then_it_hits_the_side() {
+    assert_eq!(hit_side(0.0, FRAC_PI_2), HitSide::Side);
+    assert_eq!(hit_side(0.0, -FRAC_PI_2), HitSide::Side);
+    assert_eq!(damage_from(&bullet_heading(FRAC_PI_2), &tank("target").build()), (25, HitSide::Side));
+}
+
+#[test]
+fn test_when_hull_has_turned_whole_circles_then_hit_side_is_unchanged() {
+    // Hull rotation accumulates without wrapping while steering
+    assert_eq!(hit_side(4.0 * PI, PI), HitSide::Front);
+    assert_eq!(hit_side(-6.0 * PI + FRAC_PI_2, FRAC_PI_2), HitSide::Rear);
+    assert_eq!(hit_side(3.0 * PI, PI), HitSide::Rear);
+}
+
+#[test]
+fn test_when_hit_is_within_45_degrees_of_the_nose_then_it_counts_as_front() {
+    let just_inside = PI + 0.7; // ~40° off the nose
+    let just_outside = PI + 0.9; // ~52° off the nose
+    assert_eq!(hit_side(0.0, just_inside), HitSide::Front);
+    assert_eq!(hit_side(0.0, just_outside), HitSide::Side);
+}
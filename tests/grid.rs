@@ -0,0 +1,73 @@
+use battlexone_shared::grid::{SpatialGrid, CELL_SIZE};
+use battlexone_shared::{Position, Tank};
+use proptest::prelude::*;
+
+mod common;
+use common::tank;
+
+fn within(a: &Position, b: &Position, radius: f32) -> bool {
+    (a.x - b.x).hypot(a.y - b.y) < radius
+}
+
+#[test]
+fn test_when_tanks_straddle_cell_edges_then_query_finds_them() {
+    // Either side of the origin, where cells change sign
+    let tanks = [tank("a").at(-1.0, -1.0).build(), tank("b").at(1.0, 1.0).build(), tank("c").at(3.0 * CELL_SIZE, 0.0).build()];
+    let grid = SpatialGrid::from_tanks(&tanks);
+
+    let origin = Position { x: 0.0, y: 0.0 };
+    assert_eq!(grid.query(&origin, 5.0), vec![0, 1]);
+    assert_eq!(grid.query(&Position { x: 3.0 * CELL_SIZE - 1.0, y: 0.0 }, 5.0), vec![2]);
+}
+
+#[test]
+fn test_when_tanks_are_far_or_dead_then_query_skips_them() {
+    let tanks = [tank("near").at(10.0, 0.0).build(), tank("dead").at(0.0, 10.0).dead().build(), tank("far").at(500.0, 0.0).build()];
+    let grid = SpatialGrid::from_tanks(&tanks);
+
+    assert_eq!(grid.query(&Position { x: 0.0, y: 0.0 }, 30.0), vec![0]);
+}
+
+#[test]
+fn test_when_a_tank_is_rejected_then_first_hit_skips_it() {
+    let tanks = [tank("owner").build(), tank("target").at(20.0, 0.0).build(), tank("other").at(-20.0, 0.0).build()];
+    let grid = SpatialGrid::from_tanks(&tanks);
+    let bullet = Position { x: 5.0, y: 0.0 };
+
+    assert_eq!(grid.first_hit(&tanks, &bullet, &bullet, 30.0, |_| true).map(|(i, _)| i), Some(0));
+    assert_eq!(grid.first_hit(&tanks, &bullet, &bullet, 30.0, |t| t.id != "owner").map(|(i, _)| i), Some(1));
+    assert_eq!(grid.first_hit(&tanks, &bullet, &bullet, 10.0, |t| t.id != "owner").map(|(i, _)| i), None);
+}
+
+#[test]
+fn test_when_several_tanks_are_accepted_then_nearest_picks_the_closest() {
+    let tanks = [tank("self").build(), tank("far").at(300.0, 0.0).build(), tank("close").at(0.0, -150.0).build()];
+    let grid = SpatialGrid::from_tanks(&tanks);
+    let origin = Position { x: 0.0, y: 0.0 };
+
+    assert_eq!(grid.nearest(&tanks, &origin, 400.0, |t| t.id != "self"), Some(2));
+    assert_eq!(grid.nearest(&tanks, &origin, 100.0, |t| t.id != "self"), None);
+}
+
+proptest! {
+    #[test]
+    fn test_when_tanks_are_anywhere_then_grid_queries_match_brute_force(
+        points in prop::collection::vec((-1000.0f32..1000.0, -1000.0f32..1000.0), 0..60),
+        (x, y) in (-1000.0f32..1000.0, -1000.0f32..1000.0),
+        radius in 1.0f32..400.0,
+    ) {
+        let tanks: Vec<Tank> = points.iter().enumerate().map(|(i, &(x, y))| tank(&i.to_string()).at(x, y).build()).collect();
+        let grid = SpatialGrid::from_tanks(&tanks);
+        let centre = Position { x, y };
+
+        let candidates = grid.query(&centre, radius);
+        for (i, t) in tanks.iter().enumerate() {
+            if within(&t.position, &centre, radius) {
+                prop_assert!(candidates.contains(&i), "missed tank {} at {:?}", i, t.position);
+            }
+        }
+
+        let expected = tanks.iter().position(|t| within(&t.position, &centre, radius));
+        prop_assert_eq!(grid.first_hit(&tanks, &centre, &centre, radius, |_| true).map(|(i, _)| i), expected);
+    }
+}
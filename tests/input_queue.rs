@@ -0,0 +1,68 @@
+use battlexone_shared::input_queue::{InputQueue, MAX_BUFFERED_INPUTS, MAX_REPEATED_INPUTS};
+use battlexone_shared::INPUT_FIRE;
+
+const FORWARD: u16 = 1;
+const LEFT: u16 = 1 << 1;
+
+#[test]
+fn test_when_inputs_arrive_in_order_then_they_are_applied_one_per_tick() {
+    let mut queue = InputQueue::default();
+    queue.push(1, FORWARD);
+    queue.push(2, LEFT);
+    assert_eq!(queue.pop(), Some(FORWARD));
+    assert_eq!(queue.last_processed_seq(), 1);
+    assert_eq!(queue.pop(), Some(LEFT));
+    assert_eq!(queue.last_processed_seq(), 2);
+}
+
+#[test]
+fn test_when_an_input_is_repeated_or_late_then_it_is_ignored() {
+    let mut queue = InputQueue::default();
+    queue.push(5, FORWARD);
+    queue.push(5, LEFT);
+    queue.push(3, LEFT);
+    assert_eq!(queue.len(), 1);
+    queue.push(6, LEFT);
+    assert_eq!(queue.pop(), Some(FORWARD));
+    assert_eq!(queue.pop(), Some(LEFT));
+    assert_eq!(queue.last_processed_seq(), 6);
+}
+
+#[test]
+fn test_when_client_floods_inputs_then_only_the_newest_are_kept() {
+    let mut queue = InputQueue::default();
+    let flood = MAX_BUFFERED_INPUTS as u32 + 4;
+    for seq in 1..=flood {
+        queue.push(seq, seq as u16);
+    }
+    assert_eq!(queue.len(), MAX_BUFFERED_INPUTS);
+    assert_eq!(queue.pop(), Some(5));
+    assert_eq!(queue.last_processed_seq(), 5);
+}
+
+#[test]
+fn test_when_next_input_is_late_then_the_last_is_repeated_without_firing() {
+    let mut queue = InputQueue::default();
+    assert_eq!(queue.pop(), None);
+
+    queue.push(1, FORWARD | INPUT_FIRE);
+    assert_eq!(queue.pop(), Some(FORWARD | INPUT_FIRE));
+    assert_eq!(queue.pop(), Some(FORWARD));
+    assert_eq!(queue.last_processed_seq(), 1);
+
+    // The late input is still applied when it turns up
+    queue.push(2, LEFT);
+    assert_eq!(queue.pop(), Some(LEFT));
+    assert_eq!(queue.last_processed_seq(), 2);
+}
+
+#[test]
+fn test_when_client_stops_sending_then_repeating_gives_up() {
+    let mut queue = InputQueue::default();
+    queue.push(1, FORWARD);
+    queue.pop();
+    for _ in 0..MAX_REPEATED_INPUTS {
+        assert_eq!(queue.pop(), Some(FORWARD));
+    }
+    assert_eq!(queue.pop(), None);
+}
@@ -0,0 +1,139 @@
+use battlexone_shared::interpolation::{
+    angle_delta, lerp_angle, SnapshotBuffer, CLOCK_SMOOTHING, INTERPOLATION_DELAY_MS, MAX_EXTRAPOLATION_MS,
+};
+use battlexone_shared::{Bullet, GameState, Position, Tank, Velocity};
+use std::f32::consts::{PI, TAU};
+
+mod common;
+use common::tank;
+
+const EPSILON: f32 = 1e-3;
+
+fn snapshot(tick: u64, server_time: u64, tanks: Vec<Tank>) -> GameState {
+    GameState { tick, server_time, tanks, ..GameState::default() }
+}
+
+fn bullet_at(x: f32, speed: f32) -> Bullet {
+    Bullet {
+        id: "bullet".to_string(),
+        position: Position { x, y: 0.0 },
+        velocity: Velocity { x: speed, y: 0.0 },
+        owner_id: "a".to_string(),
+        created_at: 0,
+        damage: 25,
+    }
+}
+
+/// Two snapshots 50 ms apart with the tank moving 100 units along x,
+/// arriving on a local clock 1000 ms behind the server's.
+fn moving_tank() -> SnapshotBuffer {
+    let mut buffer = SnapshotBuffer::new();
+    buffer.push_at(snapshot(1, 1000, vec![tank("a").build()]), 0.0);
+    buffer.push_at(snapshot(2, 1050, vec![tank("a").at(100.0, 0.0).build()]), 50.0);
+    buffer
+}
+
+/// Where tank "a" is drawn at server time `server_time`.
+fn x_at(buffer: &SnapshotBuffer, server_time: f64) -> f32 {
+    let local_ms = server_time - 1000.0 + INTERPOLATION_DELAY_MS;
+    let (tanks, _) = buffer.sample_at(local_ms).unwrap();
+    tanks[0].position.x
+}
+
+#[test]
+fn test_when_nothing_has_arrived_then_there_is_nothing_to_draw() {
+    assert!(SnapshotBuffer::new().sample_at(0.0).is_none());
+}
+
+#[test]
+fn test_when_render_time_falls_between_snapshots_then_the_pair_is_interpolated() {
+    let buffer = moving_tank();
+    assert!((x_at(&buffer, 1000.0) - 0.0).abs() < EPSILON);
+    assert!((x_at(&buffer, 1025.0) - 50.0).abs() < EPSILON);
+    assert!((x_at(&buffer, 1040.0) - 80.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_when_render_time_is_before_the_oldest_snapshot_then_it_is_drawn_as_is() {
+    let buffer = moving_tank();
+    assert_eq!(x_at(&buffer, 900.0), 0.0);
+}
+
+#[test]
+fn test_when_a_tank_respawns_then_it_does_not_slide_across_the_map() {
+    let mut buffer = SnapshotBuffer::new();
+    buffer.push_at(snapshot(1, 1000, vec![tank("a").dead().build()]), 0.0);
+    buffer.push_at(snapshot(2, 1050, vec![tank("a").at(500.0, 0.0).build()]), 50.0);
+    assert_eq!(x_at(&buffer, 1025.0), 500.0);
+}
+
+#[test]
+fn test_when_snapshots_stop_then_extrapolation_is_clamped() {
+    let mut buffer = moving_tank();
+    // Another snapshot with a bullet, still 100 units per 50 ms
+    let mut newest = snapshot(3, 1100, vec![tank("a").at(200.0, 0.0).build()]);
+    newest.bullets.push(bullet_at(0.0, 200.0));
+    buffer.push_at(newest, 100.0);
+
+    let (tanks, bullets) = buffer.sample_at(100.0 + INTERPOLATION_DELAY_MS + 50.0).unwrap();
+    assert!((tanks[0].position.x - 300.0).abs() < EPSILON);
+    assert!((bullets[0].position.x - 10.0).abs() < EPSILON);
+
+    let ceiling = MAX_EXTRAPOLATION_MS as f32;
+    for late_ms in [ceiling as f64, 10_000.0] {
+        let (tanks, bullets) = buffer.sample_at(100.0 + INTERPOLATION_DELAY_MS + late_ms).unwrap();
+        assert!((tanks[0].position.x - (200.0 + 2.0 * ceiling)).abs() < EPSILON);
+        assert!((bullets[0].position.x - 0.2 * ceiling).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn test_when_a_snapshot_arrives_late_then_it_is_dropped() {
+    let mut buffer = moving_tank();
+    // A duplicate and a stale snapshot, on the same clock as the others
+    buffer.push_at(snapshot(2, 1050, vec![tank("a").at(-400.0, 0.0).build()]), 50.0);
+    buffer.push_at(snapshot(1, 1000, vec![tank("a").at(-400.0, 0.0).build()]), 0.0);
+    assert!((x_at(&buffer, 1025.0) - 50.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_when_arrival_times_jitter_then_the_clock_offset_moves_gradually() {
+    let mut buffer = moving_tank();
+    assert_eq!(buffer.clock_offset_ms(), Some(1000.0));
+
+    // A snapshot that took 100 ms longer than usual to arrive
+    buffer.push_at(snapshot(3, 1100, vec![tank("a").build()]), 200.0);
+    let expected = 1000.0 - 100.0 * CLOCK_SMOOTHING;
+    assert!((buffer.clock_offset_ms().unwrap() - expected).abs() < 1e-9);
+}
+
+#[test]
+fn test_when_angles_straddle_zero_then_they_turn_the_short_way() {
+    let halfway = lerp_angle(TAU - 0.1, 0.1, 0.5);
+    assert!(angle_delta(halfway, 0.0).abs() < EPSILON, "{}", halfway);
+
+    let halfway = lerp_angle(3.0, -3.0, 0.5);
+    assert!(angle_delta(halfway, PI).abs() < EPSILON, "{}", halfway);
+
+    assert!((angle_delta(0.1, TAU - 0.1) + 0.2).abs() < EPSILON);
+    assert!((lerp_angle(1.0, 2.0, 0.25) - 1.25).abs() < EPSILON);
+}
+
+#[test]
+fn test_when_server_restarts_and_the_buffer_is_cleared_then_new_snapshots_are_drawn() {
+    let mut buffer = SnapshotBuffer::new();
+    buffer.push_at(snapshot(500, 25_000, vec![tank("a").build()]), 0.0);
+
+    // Ticks and time start over on the restarted server
+    let restarted = || snapshot(1, 50, vec![tank("a").at(300.0, 0.0).build()]);
+    buffer.push_at(restarted(), 1000.0);
+    let (tanks, _) = buffer.sample_at(1000.0).unwrap();
+    assert_eq!(tanks[0].position.x, 0.0);
+
+    buffer.clear();
+    assert_eq!(buffer.clock_offset_ms(), None);
+    buffer.push_at(restarted(), 1000.0);
+    assert_eq!(buffer.clock_offset_ms(), Some(-950.0));
+    let (tanks, _) = buffer.sample_at(1000.0).unwrap();
+    assert_eq!(tanks[0].position.x, 300.0);
+}
@@ -0,0 +1,200 @@
+use battlexone_shared::map::{Map, MapBox, MapError, Wall, TANK_RADIUS};
+use battlexone_shared::movement::{apply_movement, SIMULATION_DT};
+use battlexone_shared::teams::base_position;
+use battlexone_shared::zones::zones_for_mode;
+use battlexone_shared::{GameMode, Team};
+
+mod common;
+use common::{pos, tank};
+
+/// A wall along x = 0 from y = -100 to 100, and a box to its right.
+fn sample_map() -> Map {
+    Map {
+        walls: vec![Wall {
+            from: pos(0.0, -100.0),
+            to: pos(0.0, 100.0),
+            thickness: 20.0,
+        }],
+        boxes: vec![MapBox {
+            min: pos(200.0, -50.0),
+            max: pos(300.0, 50.0),
+        }],
+        ..Map::default()
+    }
+}
+
+#[test]
+fn test_when_default_map_is_loaded_then_it_is_valid() {
+    let map = Map::load(concat!(env!("CARGO_MANIFEST_DIR"), "/maps/default.json")).unwrap();
+    assert!(!map.walls.is_empty());
+    assert!(!map.boxes.is_empty());
+    assert!(!map.spawn_points.is_empty());
+}
+
+#[test]
+fn test_when_map_is_broken_then_loading_says_why() {
+    assert!(matches!(Map::from_json("{"), Err(MapError::Parse(_))));
+    assert!(matches!(
+        Map::from_json(r#"{"name": "tiny", "bounds": 5.0}"#),
+        Err(MapError::Invalid(_))
+    ));
+    // The spawn point is inside the box
+    let json = r#"{
+        "name": "blocked",
+        "bounds": 500.0,
+        "boxes": [{"min": {"x": -10.0, "y": -10.0}, "max": {"x": 10.0, "y": 10.0}}],
+        "spawn_points": [{"x": 0.0, "y": 0.0}]
+    }"#;
+    assert!(matches!(Map::from_json(json), Err(MapError::Invalid(_))));
+    assert!(matches!(Map::load("no/such/map.json"), Err(MapError::Io(_))));
+}
+
+#[test]
+fn test_when_map_has_a_value_that_is_not_finite_then_it_is_refused() {
+    let refused = |map: Map| match map.with_bounds(500.0) {
+        Err(MapError::Invalid(message)) => message,
+        other => panic!("should be refused, got {:?}", other),
+    };
+
+    let mut nan_corner = sample_map();
+    nan_corner.boxes[0].min.x = f32::NAN;
+    assert!(refused(nan_corner).contains("box 0 needs finite coordinates"));
+
+    let mut infinite_thickness = sample_map();
+    infinite_thickness.walls[0].thickness = f32::INFINITY;
+    assert!(refused(infinite_thickness).contains("wall 0 needs a positive thickness"));
+
+    let mut infinite_end = sample_map();
+    infinite_end.walls[0].to.y = f32::NEG_INFINITY;
+    assert!(refused(infinite_end).contains("wall 0 needs finite coordinates"));
+
+    let nan_spawn = Map {
+        spawn_points: vec![pos(f32::NAN, 0.0)],
+        ..Map::default()
+    };
+    assert!(refused(nan_spawn).contains("spawn point 0 needs finite coordinates"));
+}
+
+#[test]
+fn test_when_tank_drives_diagonally_into_wall_then_it_slides_along() {
+    let map = sample_map();
+    // Heading up and to the right, into the wall's left face
+    let mut tank = tank("tank").position(pos(-40.0, 0.0)).facing(std::f32::consts::FRAC_PI_4).build();
+
+    for _ in 0..40 {
+        apply_movement(&mut tank, 0b1, SIMULATION_DT, &map);
+    }
+
+    assert!((tank.position.x - (-10.0 - TANK_RADIUS)).abs() < 0.01);
+    assert!(tank.position.y > 60.0, "tank should have slid up the wall, got {:?}", tank.position);
+}
+
+#[test]
+fn test_when_tank_overlaps_a_box_or_the_edge_then_it_is_pushed_back() {
+    let map = sample_map();
+
+    let mut touching_box = pos(190.0, 0.0);
+    map.resolve_tank(&mut touching_box);
+    assert_eq!(touching_box, pos(200.0 - TANK_RADIUS, 0.0));
+
+    let mut inside_box = pos(290.0, 10.0);
+    map.resolve_tank(&mut inside_box);
+    assert_eq!(inside_box, pos(300.0 + TANK_RADIUS, 10.0));
+
+    let mut outside = pos(5000.0, -5000.0);
+    map.resolve_tank(&mut outside);
+    assert_eq!(outside, pos(map.bounds - TANK_RADIUS, -(map.bounds - TANK_RADIUS)));
+}
+
+#[test]
+fn test_when_bullet_path_crosses_an_obstacle_or_the_edge_then_it_stops() {
+    let map = sample_map();
+
+    assert!(map.blocks_bullet(&pos(5.0, 50.0)));
+    assert!(map.blocks_bullet(&pos(250.0, 0.0)));
+    assert!(map.blocks_bullet(&pos(0.0, 1001.0)));
+    assert!(!map.blocks_bullet(&pos(15.0, 50.0)));
+    assert!(!map.blocks_bullet(&pos(0.0, 120.0)));
+}
+
+#[test]
+fn test_when_map_places_no_objectives_then_the_standard_layout_is_used() {
+    let map = Map::default();
+    assert_eq!(map.flag_bases().of(Team::Red), &base_position(Team::Red));
+    assert_eq!(map.flag_bases().of(Team::Blue), &base_position(Team::Blue));
+    for mode in [GameMode::KingOfTheHill, GameMode::Domination, GameMode::FreeForAll] {
+        assert_eq!(map.zones_for(mode), zones_for_mode(mode));
+    }
+}
+
+#[test]
+fn test_when_map_places_objectives_then_the_modes_use_them() {
+    let json = r#"{
+        "name": "corners",
+        "bounds": 500.0,
+        "flag_bases": {"red": {"x": -400.0, "y": -400.0}, "blue": {"x": 400.0, "y": 400.0}},
+        "zones": [
+            {"name": "North", "position": {"x": 0.0, "y": 300.0}, "radius": 80.0},
+            {"name": "South", "position": {"x": 0.0, "y": -300.0}, "radius": 80.0}
+        ]
+    }"#;
+    let map = Map::from_json(json).unwrap();
+    assert_eq!(map.flag_bases().of(Team::Blue), &pos(400.0, 400.0));
+
+    let zones = map.zones_for(GameMode::Domination);
+    assert_eq!(zones.len(), 2);
+    assert_eq!((zones[0].name.as_str(), zones[0].radius), ("North", 80.0));
+    assert_eq!(zones[1].position, pos(0.0, -300.0));
+    assert!(zones.iter().all(|z| z.owner.is_none() && z.progress == 0.0));
+    assert_eq!(map.zones_for(GameMode::KingOfTheHill), zones);
+    assert!(map.zones_for(GameMode::CaptureTheFlag).is_empty());
+
+    // Sent to clients and read back unchanged
+    let sent = serde_json::to_string(&map).unwrap();
+    assert_eq!(Map::from_json(&sent).unwrap(), map);
+}
+
+#[test]
+fn test_when_objectives_are_misplaced_then_loading_says_why() {
+    let with = |objectives: &str| {
+        let json = format!(
+            r#"{{"name": "m", "bounds": 500.0,
+                "boxes": [{{"min": {{"x": -10.0, "y": -10.0}}, "max": {{"x": 10.0, "y": 10.0}}}}],
+                {}}}"#,
+            objectives
+        );
+        match Map::from_json(&json) {
+            Err(MapError::Invalid(message)) => message,
+            other => panic!("{} should be refused, got {:?}", objectives, other),
+        }
+    };
+    let bases = |red: &str, blue: &str| with(&format!(r#""flag_bases": {{"red": {}, "blue": {}}}"#, red, blue));
+    let zone = |name: &str, x: f32, radius: f32| {
+        format!(r#"{{"name": "{}", "position": {{"x": {:.1}, "y": 0.0}}, "radius": {:.1}}}"#, name, x, radius)
+    };
+
+    assert!(bases(r#"{"x": -700.0, "y": 0.0}"#, r#"{"x": 300.0, "y": 0.0}"#).contains("Red flag base"));
+    assert!(bases(r#"{"x": -300.0, "y": 0.0}"#, r#"{"x": 0.0, "y": 0.0}"#).contains("Blue flag base"));
+    assert!(bases(r#"{"x": 300.0, "y": 0.0}"#, r#"{"x": 300.0, "y": 0.0}"#).contains("same place"));
+    assert!(with(r#""zones": []"#).contains("at least one zone"));
+    assert!(with(&format!(r#""zones": [{}]"#, zone("A", 0.0, 0.0))).contains("positive radius"));
+    assert!(with(&format!(r#""zones": [{}]"#, zone("A", 600.0, 50.0))).contains("out of bounds"));
+    assert!(with(&format!(r#""zones": [{}, {}]"#, zone("A", 100.0, 50.0), zone("A", -100.0, 50.0))).contains("name"));
+}
+
+#[test]
+fn test_when_arena_is_too_small_for_the_standard_layout_then_the_mode_is_refused() {
+    let small = Map::default().with_bounds(600.0).unwrap();
+    assert_eq!(small.check_mode(GameMode::FreeForAll), Ok(()));
+    assert_eq!(small.check_mode(GameMode::KingOfTheHill), Ok(()));
+    assert_eq!(small.check_mode(GameMode::Domination), Ok(()));
+    assert!(matches!(small.check_mode(GameMode::CaptureTheFlag), Err(MapError::Invalid(m)) if m.contains("flag base")));
+
+    let tiny = Map::default().with_bounds(400.0).unwrap();
+    assert!(matches!(tiny.check_mode(GameMode::Domination), Err(MapError::Invalid(m)) if m.contains("zone")));
+
+    // A map that places its own objectives can be smaller
+    let json = r#"{"name": "m", "bounds": 400.0,
+        "flag_bases": {"red": {"x": -300.0, "y": 0.0}, "blue": {"x": 300.0, "y": 0.0}}}"#;
+    assert_eq!(Map::from_json(json).unwrap().check_mode(GameMode::CaptureTheFlag), Ok(()));
+}
@@ -0,0 +1,138 @@
+use battlexone_shared::lifecycle::{MatchLifecycle, MatchSettings};
+use battlexone_shared::{GameMode, MatchPhase, PlayerStats, ScoreboardEntry, Team};
+
+fn settings() -> MatchSettings {
+    MatchSettings {
+        mode: GameMode::FreeForAll,
+        score_limit: 5,
+        time_limit_ms: 60_000,
+        warmup_ms: 3_000,
+        results_ms: 5_000,
+        min_players: 1,
+    }
+}
+
+/// A lifecycle that has just started its first match at server time 3000.
+fn in_progress() -> MatchLifecycle {
+    let mut lifecycle = MatchLifecycle::new(settings());
+    lifecycle.update(0, 1, 0);
+    lifecycle.update(3_000, 1, 0);
+    assert_eq!(lifecycle.state().phase, MatchPhase::InProgress);
+    lifecycle
+}
+
+#[test]
+fn test_when_first_player_joins_then_lobby_moves_to_warmup() {
+    let mut lifecycle = MatchLifecycle::new(settings());
+    assert_eq!(lifecycle.update(100, 0, 0), None);
+    assert_eq!(lifecycle.state().phase, MatchPhase::Lobby);
+
+    assert_eq!(lifecycle.update(200, 1, 0), Some(MatchPhase::Warmup));
+    assert_eq!(lifecycle.state().phase_ends_at, Some(3_200));
+}
+
+#[test]
+fn test_when_warmup_time_passes_then_match_starts_with_time_limit() {
+    let mut lifecycle = MatchLifecycle::new(settings());
+    lifecycle.update(0, 1, 0);
+
+    assert_eq!(lifecycle.update(2_999, 1, 0), None);
+    assert_eq!(lifecycle.update(3_000, 1, 0), Some(MatchPhase::InProgress));
+    assert_eq!(lifecycle.state().phase_ends_at, Some(63_000));
+}
+
+#[test]
+fn test_when_leader_reaches_score_limit_then_match_ends() {
+    let mut lifecycle = in_progress();
+
+    assert_eq!(lifecycle.update(10_000, 2, 4), None);
+    assert_eq!(lifecycle.update(10_016, 2, 5), Some(MatchPhase::Results));
+    assert!(!lifecycle.is_live());
+}
+
+#[test]
+fn test_when_time_limit_passes_then_match_ends() {
+    let mut lifecycle = in_progress();
+
+    assert_eq!(lifecycle.update(63_000, 1, 0), Some(MatchPhase::Results));
+    assert_eq!(lifecycle.state().phase_ends_at, Some(68_000));
+}
+
+#[test]
+fn test_when_results_end_then_next_match_warms_up_and_forgets_winner() {
+    let mut lifecycle = in_progress();
+    lifecycle.update(63_000, 1, 0);
+    lifecycle.record_results(vec![ScoreboardEntry {
+        player_id: "p1".to_string(),
+        name: "Alice".to_string(),
+        team: None,
+        stats: PlayerStats { kills: 3, ..PlayerStats::default() },
+        accuracy: 0.5,
+    }]);
+    assert_eq!(lifecycle.state().winner.as_deref(), Some("Alice"));
+
+    assert_eq!(lifecycle.update(68_000, 1, 0), Some(MatchPhase::Warmup));
+    assert_eq!(lifecycle.state().match_number, 2);
+    assert_eq!(lifecycle.state().winner, None);
+    assert!(lifecycle.state().results.is_empty());
+}
+
+#[test]
+fn test_when_everyone_leaves_then_match_returns_to_lobby() {
+    let mut lifecycle = in_progress();
+
+    assert_eq!(lifecycle.update(5_000, 0, 0), Some(MatchPhase::Lobby));
+    assert_eq!(lifecycle.state().phase_ends_at, None);
+}
+
+#[test]
+fn test_when_a_team_reaches_score_limit_then_it_wins() {
+    let mut lifecycle = MatchLifecycle::new(MatchSettings {
+        mode: GameMode::TeamDeathmatch,
+        ..settings()
+    });
+    lifecycle.update(0, 2, 0);
+    lifecycle.update(3_000, 2, 0);
+
+    for _ in 0..4 {
+        lifecycle.add_team_score(Team::Blue, 1);
+    }
+    lifecycle.add_team_score(Team::Red, 1);
+    // Individual kills don't end a team match
+    assert_eq!(lifecycle.update(4_000, 2, 9), None);
+
+    lifecycle.add_team_score(Team::Blue, 1);
+    assert_eq!(lifecycle.update(4_016, 2, 0), Some(MatchPhase::Results));
+    lifecycle.record_results(Vec::new());
+    assert_eq!(lifecycle.state().winner.as_deref(), Some("Blue team"));
+}
+
+#[test]
+fn test_when_team_scores_are_level_at_time_limit_then_match_is_a_draw() {
+    let mut lifecycle = MatchLifecycle::new(MatchSettings {
+        mode: GameMode::CaptureTheFlag,
+        ..settings()
+    });
+    lifecycle.update(0, 2, 0);
+    lifecycle.update(3_000, 2, 0);
+    assert_eq!(lifecycle.state().flags.len(), 2);
+
+    lifecycle.add_team_score(Team::Red, 2);
+    lifecycle.add_team_score(Team::Blue, 2);
+    assert_eq!(lifecycle.update(63_000, 2, 0), Some(MatchPhase::Results));
+    lifecycle.record_results(Vec::new());
+    assert_eq!(lifecycle.state().winner, None);
+}
+
+#[test]
+fn test_when_match_is_not_in_progress_then_team_scores_do_not_count() {
+    let mut lifecycle = MatchLifecycle::new(MatchSettings {
+        mode: GameMode::TeamDeathmatch,
+        ..settings()
+    });
+    lifecycle.update(0, 2, 0);
+    lifecycle.add_team_score(Team::Red, 3); // warmup
+
+    lifecycle.update(3_000, 2, 0);
+    assert!(lifecycle.state().team_scores.iter().all(|s| s.score == 0));
+}
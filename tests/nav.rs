@@ -0,0 +1,121 @@
+use battlexone_shared::map::{Map, MapBox, Wall};
+use battlexone_shared::nav::{NavGrid, PathFollower, REPATH_INTERVAL_MS};
+use battlexone_shared::Position;
+
+mod common;
+use common::pos;
+
+fn wall(from: Position, to: Position) -> Wall {
+    Wall { from, to, thickness: 20.0 }
+}
+
+fn small_map(walls: Vec<Wall>, boxes: Vec<MapBox>) -> Map {
+    Map {
+        name: "Test".to_string(),
+        bounds: 200.0,
+        walls,
+        boxes,
+        ..Map::default()
+    }
+}
+
+/// Every leg of the route from `from` stays clear of the map's obstacles.
+fn assert_clear_route(map: &Map, from: &Position, path: &[Position]) {
+    let mut at = from;
+    for waypoint in path {
+        assert!(
+            map.bullet_impact(at, waypoint).is_none(),
+            "leg {:?} -> {:?} runs into an obstacle",
+            at,
+            waypoint
+        );
+        at = waypoint;
+    }
+}
+
+#[test]
+fn test_when_nothing_is_in_the_way_then_path_is_straight_to_the_goal() {
+    let map = small_map(Vec::new(), Vec::new());
+    let nav = NavGrid::from_map(&map);
+
+    let path = nav.find_path(&pos(-100.0, -100.0), &pos(100.0, 120.0)).unwrap();
+    assert_eq!(path, vec![pos(100.0, 120.0)]);
+}
+
+#[test]
+fn test_when_a_wall_is_in_the_way_then_path_goes_round_the_gap() {
+    // A wall down the middle with a gap only at the bottom
+    let map = small_map(vec![wall(pos(0.0, -120.0), pos(0.0, 200.0))], Vec::new());
+    let nav = NavGrid::from_map(&map);
+    let (from, to) = (pos(-100.0, 0.0), pos(100.0, 0.0));
+
+    let path = nav.find_path(&from, &to).unwrap();
+    assert_eq!(path.last(), Some(&to));
+    assert!(path.iter().any(|p| p.y < -120.0), "{:?}", path);
+    assert_clear_route(&map, &from, &path);
+    // Smoothed down to the corners rather than one point per cell
+    assert!(path.len() <= 3, "{:?}", path);
+}
+
+#[test]
+fn test_when_boxes_leave_a_gap_then_path_threads_through_it() {
+    let map = small_map(
+        Vec::new(),
+        vec![
+            MapBox { min: pos(-60.0, -200.0), max: pos(-40.0, 100.0) },
+            MapBox { min: pos(40.0, -100.0), max: pos(60.0, 200.0) },
+        ],
+    );
+    let nav = NavGrid::from_map(&map);
+    let (from, to) = (pos(-150.0, -150.0), pos(150.0, 150.0));
+
+    let path = nav.find_path(&from, &to).unwrap();
+    assert_clear_route(&map, &from, &path);
+    assert!(path.iter().all(|p| !map.blocks_tank(p)));
+}
+
+#[test]
+fn test_when_goal_is_walled_in_then_there_is_no_path() {
+    let room = vec![
+        wall(pos(40.0, 40.0), pos(160.0, 40.0)),
+        wall(pos(160.0, 40.0), pos(160.0, 160.0)),
+        wall(pos(160.0, 160.0), pos(40.0, 160.0)),
+        wall(pos(40.0, 160.0), pos(40.0, 40.0)),
+    ];
+    let nav = NavGrid::from_map(&small_map(room, Vec::new()));
+
+    assert!(nav.is_walkable_at(&pos(100.0, 100.0)));
+    assert_eq!(nav.find_path(&pos(-100.0, -100.0), &pos(100.0, 100.0)), None);
+}
+
+#[test]
+fn test_when_goal_is_inside_a_box_then_path_ends_beside_it() {
+    let map = small_map(Vec::new(), vec![MapBox { min: pos(50.0, -30.0), max: pos(110.0, 30.0) }]);
+    let nav = NavGrid::from_map(&map);
+
+    let path = nav.find_path(&pos(-100.0, 0.0), &pos(80.0, 0.0)).unwrap();
+    let end = path.last().unwrap();
+    assert!(!map.blocks_tank(end), "{:?}", end);
+    assert!((end.x - 80.0).hypot(end.y) < 100.0, "{:?}", end);
+}
+
+#[test]
+fn test_when_goal_moves_far_or_route_is_stale_then_follower_replans() {
+    let nav = NavGrid::from_map(&small_map(Vec::new(), Vec::new()));
+    let mut follower = PathFollower::default();
+    let here = pos(-100.0, 0.0);
+
+    assert_eq!(follower.next_waypoint(&nav, &here, &pos(100.0, 0.0), 0), Some(pos(100.0, 0.0)));
+    // A small move keeps the current route
+    assert_eq!(follower.next_waypoint(&nav, &here, &pos(100.0, 20.0), 100), Some(pos(100.0, 0.0)));
+    // Until it's old enough
+    assert_eq!(
+        follower.next_waypoint(&nav, &here, &pos(100.0, 20.0), REPATH_INTERVAL_MS),
+        Some(pos(100.0, 20.0))
+    );
+    // A big move replans straight away
+    assert_eq!(
+        follower.next_waypoint(&nav, &here, &pos(100.0, 120.0), REPATH_INTERVAL_MS + 1),
+        Some(pos(100.0, 120.0))
+    );
+}
@@ -0,0 +1,126 @@
+use battlexone_shared::npc::{lead_angle, turn_towards, Difficulty, NpcAction, NpcBrain, ENGAGE_DISTANCE};
+use battlexone_shared::Position;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+mod common;
+use common::tank;
+
+const BULLET_SPEED: f32 = 200.0;
+
+fn brain(difficulty: Difficulty) -> NpcBrain {
+    NpcBrain::new(difficulty, Position { x: 0.0, y: 500.0 })
+}
+
+#[test]
+fn test_when_nothing_is_in_sight_then_npc_patrols() {
+    let npc = tank("npc").npc().build();
+    let mut brain = brain(Difficulty::Normal);
+    brain.perceive(None, 0, &mut StdRng::seed_from_u64(1));
+
+    assert_eq!(brain.decide(&npc, 0), NpcAction::Patrol);
+    assert_eq!(brain.destination(&npc, NpcAction::Patrol), Some(Position { x: 0.0, y: 500.0 }));
+    assert_eq!(brain.aim(&npc, BULLET_SPEED, 0), None);
+}
+
+#[test]
+fn test_when_enemy_appears_then_npc_reacts_only_after_its_reaction_time() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let npc = tank("npc").npc().build();
+    let enemy = tank("enemy").at(200.0, 0.0).npc().build();
+
+    for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+        let mut brain = brain(difficulty);
+        brain.perceive(Some(&enemy), 1000, &mut rng);
+        let reacts_at = 1000 + difficulty.reaction_ms();
+
+        brain.perceive(Some(&enemy), reacts_at - 1, &mut rng);
+        assert_eq!(brain.decide(&npc, reacts_at - 1), NpcAction::Patrol, "{:?}", difficulty);
+        assert_eq!(brain.aim(&npc, BULLET_SPEED, reacts_at - 1), None);
+
+        brain.perceive(Some(&enemy), reacts_at, &mut rng);
+        assert_eq!(brain.decide(&npc, reacts_at), NpcAction::Engage, "{:?}", difficulty);
+        assert_eq!(brain.target_id(reacts_at), Some("enemy"));
+    }
+    assert!(Difficulty::Hard.reaction_ms() < Difficulty::Easy.reaction_ms());
+}
+
+#[test]
+fn test_when_badly_damaged_then_npc_retreats_away_from_the_enemy() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut npc = tank("npc").npc().build();
+    let enemy = tank("enemy").at(200.0, 0.0).npc().build();
+    let mut brain = brain(Difficulty::Hard);
+    brain.perceive(Some(&enemy), 0, &mut rng);
+    let now = Difficulty::Hard.reaction_ms();
+
+    npc.health = 60;
+    assert_eq!(brain.decide(&npc, now), NpcAction::Engage);
+    npc.health = 30;
+    assert_eq!(brain.decide(&npc, now), NpcAction::Retreat);
+
+    let away = brain.destination(&npc, NpcAction::Retreat).unwrap();
+    assert!(away.x < -100.0 && away.y.abs() < 0.01, "{:?}", away);
+}
+
+#[test]
+fn test_when_engaged_then_npc_closes_in_and_holds_range() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let npc = tank("npc").npc().build();
+    let mut brain = brain(Difficulty::Normal);
+
+    brain.perceive(Some(&tank("far").at(ENGAGE_DISTANCE + 100.0, 0.0).npc().build()), 0, &mut rng);
+    assert!(brain.destination(&npc, NpcAction::Engage).is_some());
+    brain.perceive(Some(&tank("near").at(ENGAGE_DISTANCE - 100.0, 0.0).npc().build()), 0, &mut rng);
+    assert_eq!(brain.destination(&npc, NpcAction::Engage), None);
+}
+
+#[test]
+fn test_when_target_moves_then_lead_angle_aims_ahead_of_it() {
+    let shooter = Position { x: 0.0, y: 0.0 };
+    let target = Position { x: 200.0, y: 0.0 };
+
+    assert!(lead_angle(&shooter, &target, (0.0, 0.0), BULLET_SPEED).abs() < 1e-6);
+    // Crossing upwards at half bullet speed: 30° ahead
+    let angle = lead_angle(&shooter, &target, (0.0, 100.0), BULLET_SPEED);
+    assert!((angle - PI / 6.0).abs() < 1e-4, "{}", angle);
+    // Running away faster than the bullet: aim straight at it
+    assert!(lead_angle(&shooter, &target, (300.0, 0.0), BULLET_SPEED).abs() < 1e-6);
+}
+
+#[test]
+fn test_when_npc_aims_then_it_uses_observed_movement_within_its_error() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let npc = tank("npc").npc().build();
+    let exact = lead_angle(&npc.position, &Position { x: 200.0, y: 20.0 }, (0.0, 100.0), BULLET_SPEED);
+
+    for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+        let mut brain = brain(difficulty);
+        let mut enemy = tank("enemy").at(200.0, 0.0).npc().build();
+        brain.perceive(Some(&enemy), 0, &mut rng);
+        // 100 units a second upwards
+        enemy.position.y = 20.0;
+        let now = 200;
+        brain.perceive(Some(&enemy), now, &mut rng);
+
+        for _ in 0..20 {
+            let aim = brain.aim(&npc, BULLET_SPEED, now.max(difficulty.reaction_ms())).unwrap();
+            assert!((aim - exact).abs() <= difficulty.aim_error() + 1e-4, "{:?}: {} vs {}", difficulty, aim, exact);
+            brain.fired(&mut rng);
+        }
+    }
+}
+
+#[test]
+fn test_when_turret_turns_then_it_takes_the_short_way_and_fires_only_on_target() {
+    assert!((turn_towards(0.0, FRAC_PI_2, 0.1) - 0.1).abs() < 1e-6);
+    // From just below +PI to just above -PI is a short turn across the seam
+    let turned = turn_towards(PI - 0.05, -PI + 0.05, 1.0);
+    assert!((turned - (PI + 0.05)).abs() < 1e-5, "{}", turned);
+
+    let brain = brain(Difficulty::Normal);
+    assert!(brain.ready_to_fire(1.0, 1.05));
+    assert!(!brain.ready_to_fire(1.0, 1.5));
+    assert!(brain.ready_to_fire(PI - 0.02, -PI + 0.02));
+}
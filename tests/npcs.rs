@@ -0,0 +1,123 @@
+use battlexone_shared::grid::SpatialGrid;
+use battlexone_shared::map::{Map, Wall};
+use battlexone_shared::nav::NavGrid;
+use battlexone_shared::npc::{Difficulty, NpcBrain};
+use battlexone_shared::npcs::{has_line_of_fire, random_npc_target, update_npcs, NpcWorld};
+use battlexone_shared::server_config::{Tuning, NPC_MIN_PATROL_DISTANCE};
+use battlexone_shared::{Bullet, Tank, Team};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+
+mod common;
+use common::{pos, tank};
+
+const DT: f32 = 1.0 / 60.0;
+const HIT_RADIUS: f32 = 30.0;
+
+/// A wall across x = 0 from y = 50 up, leaving the line y = 0 open.
+fn walled() -> Map {
+    Map {
+        walls: vec![Wall {
+            from: pos(0.0, 50.0),
+            to: pos(0.0, 400.0),
+            thickness: 20.0,
+        }],
+        ..Map::default()
+    }
+}
+
+fn brains(tanks: &[Tank], difficulty: Difficulty) -> HashMap<String, NpcBrain> {
+    tanks
+        .iter()
+        .filter(|t| !t.is_player)
+        .map(|t| (t.id.clone(), NpcBrain::new(difficulty, t.position.clone())))
+        .collect()
+}
+
+/// Runs `ticks` ticks starting at server time 0, collecting the bullets fired.
+fn run(tanks: &mut [Tank], brains: &mut HashMap<String, NpcBrain>, map: &Map, ticks: u64) -> Vec<Bullet> {
+    let nav = NavGrid::from_map(map);
+    let world = NpcWorld {
+        map,
+        nav: &nav,
+        tuning: Tuning::default(),
+        patrol_radius: 500.0,
+    };
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut fired = Vec::new();
+    for tick in 0..ticks {
+        let now = tick * 1000 / 60;
+        fired.extend(update_npcs(tanks, brains, &world, DT, now, 1_000_000 + now, &mut rng));
+    }
+    fired
+}
+
+#[test]
+fn test_when_wall_or_teammate_is_in_the_way_then_there_is_no_line_of_fire() {
+    let map = walled();
+    let tanks = vec![
+        tank("shooter").npc().team(Team::Red).at(-200.0, 0.0).build(),
+        tank("enemy").team(Team::Blue).at(200.0, 200.0).build(),
+        tank("open").team(Team::Blue).at(200.0, 0.0).build(),
+        tank("friend").npc().team(Team::Red).at(-100.0, -100.0).build(),
+        tank("behind").team(Team::Blue).at(0.0, -200.0).build(),
+    ];
+    let grid = SpatialGrid::from_tanks(&tanks);
+
+    assert!(!has_line_of_fire(&map, &grid, &tanks, 0, &tanks[1], HIT_RADIUS));
+    assert!(has_line_of_fire(&map, &grid, &tanks, 0, &tanks[2], HIT_RADIUS));
+    assert!(!has_line_of_fire(&map, &grid, &tanks, 0, &tanks[4], HIT_RADIUS));
+}
+
+#[test]
+fn test_when_enemy_is_in_sight_then_npc_turns_on_it_and_fires() {
+    let map = Map::default();
+    let mut tanks = vec![tank("npc").npc().at(0.0, 0.0).build(), tank("player").at(0.0, 200.0).build()];
+    let mut brains = brains(&tanks, Difficulty::Hard);
+
+    let fired = run(&mut tanks, &mut brains, &map, 120);
+
+    assert!(!fired.is_empty());
+    let bullet = &fired[0];
+    assert_eq!(bullet.owner_id, "npc");
+    assert!(bullet.velocity.y > 0.0 && bullet.velocity.x.abs() < bullet.velocity.y * 0.2, "{:?}", bullet.velocity);
+    assert!(tanks[1].position == pos(0.0, 200.0), "players are left alone");
+}
+
+#[test]
+fn test_when_only_teammates_or_walls_are_around_then_npc_holds_fire() {
+    let map = walled();
+    let mut tanks = vec![
+        tank("npc").npc().team(Team::Red).at(-150.0, 200.0).build(),
+        tank("mate").team(Team::Red).at(-150.0, 100.0).build(),
+        tank("hidden").team(Team::Blue).at(150.0, 200.0).build(),
+    ];
+    let mut brains = brains(&tanks, Difficulty::Hard);
+
+    assert!(run(&mut tanks, &mut brains, &map, 120).is_empty());
+}
+
+#[test]
+fn test_when_nothing_is_in_sight_then_npc_drives_to_its_patrol_target() {
+    let map = Map::default();
+    let mut tanks = vec![tank("npc").npc().at(0.0, 0.0).build()];
+    let mut brains = brains(&tanks, Difficulty::Normal);
+    brains.get_mut("npc").unwrap().patrol_target = pos(300.0, 0.0);
+
+    run(&mut tanks, &mut brains, &map, 60);
+
+    let speed = Tuning::default().npc_speed;
+    assert!((tanks[0].position.x - speed).abs() < 1.0, "{:?}", tanks[0].position);
+    assert!(tanks[0].position.y.abs() < 1.0);
+}
+
+#[test]
+fn test_when_picking_a_patrol_target_then_it_is_within_the_patrol_ring() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..100 {
+        let target = random_npc_target(&mut rng, 500.0);
+        let distance = target.x.hypot(target.y);
+        assert!((NPC_MIN_PATROL_DISTANCE..500.0).contains(&distance), "{}", distance);
+    }
+}
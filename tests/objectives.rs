@@ -0,0 +1,65 @@
+use battlexone_shared::lifecycle::{MatchLifecycle, MatchSettings};
+use battlexone_shared::objectives::{update_flags, update_zones};
+use battlexone_shared::teams::base_position;
+use battlexone_shared::zones::{zones_for_mode, CAPTURE_SECS, ZONE_SCORE_INTERVAL_SECS};
+use battlexone_shared::{FlagEventKind, GameMode, MatchPhase, Position, Team};
+
+mod common;
+use common::tank;
+
+/// A lifecycle for `mode` whose first match has just started.
+fn in_progress(mode: GameMode) -> MatchLifecycle {
+    let mut lifecycle = MatchLifecycle::new(MatchSettings {
+        mode,
+        warmup_ms: 0,
+        ..MatchSettings::default()
+    });
+    lifecycle.update(0, 1, 0);
+    lifecycle.update(0, 1, 0);
+    assert_eq!(lifecycle.state().phase, MatchPhase::InProgress);
+    lifecycle
+}
+
+fn team_score(lifecycle: &MatchLifecycle, team: Team) -> u32 {
+    lifecycle.state().team_scores.iter().find(|s| s.team == team).unwrap().score
+}
+
+#[test]
+fn test_when_flag_is_captured_then_the_capturing_team_scores() {
+    let mut lifecycle = in_progress(GameMode::CaptureTheFlag);
+    let mut raider = tank("raider").team(Team::Blue).position(base_position(Team::Red)).build();
+    let events = update_flags(&mut lifecycle, &[raider.clone()], 0);
+    assert_eq!(events[0].kind, FlagEventKind::Pickup);
+    assert_eq!(team_score(&lifecycle, Team::Blue), 0);
+
+    raider.position = base_position(Team::Blue);
+    let events = update_flags(&mut lifecycle, &[raider], 16);
+    assert_eq!(events.last().map(|e| e.kind), Some(FlagEventKind::Capture));
+    assert_eq!(team_score(&lifecycle, Team::Blue), 1);
+    assert_eq!(team_score(&lifecycle, Team::Red), 0);
+}
+
+#[test]
+fn test_when_a_zone_is_held_then_its_team_scores_once_per_interval() {
+    let mut lifecycle = in_progress(GameMode::KingOfTheHill);
+    let mut zones = zones_for_mode(GameMode::KingOfTheHill);
+    let mut elapsed = 0.0;
+    let tanks = [tank("red").team(Team::Red).position(Position { x: 0.0, y: 0.0 }).build()];
+    let dt = 0.1;
+
+    let steps = (CAPTURE_SECS / dt).round() as usize;
+    let events: Vec<_> = (0..steps)
+        .flat_map(|_| update_zones(&mut zones, &mut elapsed, &mut lifecycle, &tanks, dt))
+        .collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(zones[0].owner, Some(Team::Red));
+
+    // Held for three more scoring intervals
+    let before = team_score(&lifecycle, Team::Red);
+    let steps = (3.0 * ZONE_SCORE_INTERVAL_SECS / dt).round() as usize;
+    for _ in 0..steps {
+        update_zones(&mut zones, &mut elapsed, &mut lifecycle, &tanks, dt);
+    }
+    assert_eq!(team_score(&lifecycle, Team::Red) - before, 3);
+    assert_eq!(team_score(&lifecycle, Team::Blue), 0);
+}
@@ -0,0 +1,90 @@
+use battlexone_shared::damage::HitSide;
+use battlexone_shared::players::{record_hits, scoreboard_entries, Player};
+use battlexone_shared::profiles::Profile;
+use battlexone_shared::{HitEvent, PlayerStats, Position, Team};
+use std::collections::HashMap;
+
+fn player(name: &str) -> Player {
+    Player {
+        name: name.to_string(),
+        user_id: format!("user-{}", name),
+        team: None,
+        stats: PlayerStats::default(),
+        disconnected_at: None,
+        profile: Profile::new(&format!("user-{}", name), name),
+    }
+}
+
+fn players(names: &[&str]) -> HashMap<String, Player> {
+    names.iter().map(|name| (name.to_string(), player(name))).collect()
+}
+
+fn hit(attacker: &str, target: &str, killed: bool) -> HitEvent {
+    HitEvent {
+        tick: 1,
+        attacker_id: attacker.to_string(),
+        target_id: target.to_string(),
+        damage: 25,
+        side: HitSide::Front,
+        position: Position { x: 0.0, y: 0.0 },
+        health: if killed { 0 } else { 75 },
+        killed,
+    }
+}
+
+#[test]
+fn test_when_a_player_kills_another_then_each_is_credited_once() {
+    let mut players = players(&["ann", "bob"]);
+    record_hits(&mut players, &[hit("ann", "bob", false), hit("ann", "bob", true)]);
+
+    let ann = &players["ann"];
+    assert_eq!((ann.stats.hits, ann.stats.kills, ann.stats.deaths), (2, 1, 0));
+    assert_eq!((ann.profile.hits, ann.profile.kills, ann.profile.deaths), (2, 1, 0));
+    let bob = &players["bob"];
+    assert_eq!((bob.stats.hits, bob.stats.kills, bob.stats.deaths), (0, 0, 1));
+    assert_eq!(bob.profile.deaths, 1);
+}
+
+#[test]
+fn test_when_npcs_are_involved_then_only_the_players_are_credited() {
+    let mut players = players(&["ann"]);
+    record_hits(&mut players, &[hit("ann", "npc-1", true), hit("npc-2", "ann", true), hit("npc-1", "npc-2", true)]);
+
+    let ann = &players["ann"];
+    assert_eq!((ann.stats.kills, ann.stats.deaths), (1, 1));
+    assert_eq!(players.len(), 1);
+}
+
+#[test]
+fn test_when_shots_miss_then_accuracy_counts_them() {
+    let mut players = players(&["ann", "bob"]);
+    assert_eq!(scoreboard_entries(&players)[0].accuracy, 0.0);
+
+    for _ in 0..4 {
+        players.get_mut("ann").unwrap().record_shot();
+    }
+    record_hits(&mut players, &[hit("ann", "bob", false)]);
+
+    let ann = &players["ann"];
+    assert_eq!(ann.profile.shots_fired, 4);
+    let entry = scoreboard_entries(&players).into_iter().find(|e| e.player_id == "ann").unwrap();
+    assert_eq!(entry.accuracy, 0.25);
+    assert_eq!(entry.accuracy, ann.stats.accuracy());
+}
+
+#[test]
+fn test_when_scoreboard_is_built_then_kills_then_deaths_then_name_order_it() {
+    let mut players = players(&["cat", "bob", "ann", "dan"]);
+    players.get_mut("ann").unwrap().team = Some(Team::Blue);
+    record_hits(
+        &mut players,
+        &[hit("dan", "cat", true), hit("cat", "dan", true), hit("ann", "dan", true), hit("bob", "npc", true)],
+    );
+
+    let entries = scoreboard_entries(&players);
+    let order: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+    // Everyone has one kill; ann and bob haven't died
+    assert_eq!(order, ["ann", "bob", "cat", "dan"]);
+    assert_eq!(entries[0].team, Some(Team::Blue));
+    assert_eq!(entries[3].stats.deaths, 2);
+}
@@ -0,0 +1,84 @@
+use battlexone_shared::profiles::{JsonLinesStore, MemoryStore, Profile, ProfileStore};
+use battlexone_shared::rating::DEFAULT_RATING;
+use std::fs;
+use std::path::PathBuf;
+
+/// A path in the temp directory no other test uses.
+fn scratch_path() -> PathBuf {
+    std::env::temp_dir().join(format!("battlexone-profiles-{}.jsonl", uuid::Uuid::new_v4()))
+}
+
+fn veteran() -> Profile {
+    let mut profile = Profile::new("user-1", "Ace");
+    profile.kills = 12;
+    profile.deaths = 3;
+    profile.matches_played = 2;
+    profile.settings.insert("camera".to_string(), "chase".to_string());
+    profile
+}
+
+#[test]
+fn test_when_store_is_reopened_then_profiles_are_still_there() {
+    let path = scratch_path();
+    {
+        let mut store = JsonLinesStore::open(&path).unwrap();
+        assert!(store.is_empty());
+        store.save(&veteran()).unwrap();
+    }
+
+    let store = JsonLinesStore::open(&path).unwrap();
+    assert_eq!(store.load("user-1"), Some(veteran()));
+    assert_eq!(store.load("user-2"), None);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_when_profile_is_saved_twice_then_latest_wins_and_reopening_compacts() {
+    let path = scratch_path();
+    {
+        let mut store = JsonLinesStore::open(&path).unwrap();
+        let mut profile = veteran();
+        store.save(&profile).unwrap();
+        profile.kills += 1;
+        store.save(&profile).unwrap();
+        // Unchanged, so not appended again
+        store.save(&profile).unwrap();
+        store.save(&Profile::new("user-2", "Rookie")).unwrap();
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+    let store = JsonLinesStore::open(&path).unwrap();
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.load("user-1").unwrap().kills, 13);
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_when_last_line_was_cut_short_then_the_rest_still_loads() {
+    let path = scratch_path();
+    let whole = serde_json::to_string(&veteran()).unwrap();
+    fs::write(&path, format!("{}\n{}", whole, &whole[..whole.len() / 2])).unwrap();
+
+    let store = JsonLinesStore::open(&path).unwrap();
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.load("user-1"), Some(veteran()));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_when_profile_predates_a_field_then_it_still_loads() {
+    let profile: Profile = serde_json::from_str(r#"{"user_id":"user-1","name":"Ace","kills":4}"#).unwrap();
+    assert_eq!(profile.kills, 4);
+    assert_eq!(profile.matches_played, 0);
+    assert_eq!(profile.rating, DEFAULT_RATING);
+    assert!(profile.settings.is_empty());
+}
+
+#[test]
+fn test_when_memory_store_saves_then_profile_loads_until_dropped() {
+    let mut store = MemoryStore::default();
+    assert_eq!(store.load("user-1"), None);
+    store.save(&veteran()).unwrap();
+    assert_eq!(store.load("user-1"), Some(veteran()));
+}
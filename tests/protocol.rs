@@ -0,0 +1,240 @@
+use battlexone_shared::damage::HitSide;
+use battlexone_shared::delta::{BulletDelta, GameStateDelta, TankDelta};
+use battlexone_shared::map::{Map, MapBox, Wall};
+use battlexone_shared::teams::FlagBases;
+use battlexone_shared::wire::WIRE_VERSION;
+use battlexone_shared::zones::ZoneSpot;
+use battlexone_shared::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+mod common;
+use common::{pos, tank};
+
+/// Every protocol version and the wire version it shipped with, oldest
+/// first. A new wire version needs a new row, and the row a new protocol
+/// version.
+const HISTORY: [(u32, u8); 4] = [(1, 1), (2, 5), (3, 5), (4, 5)];
+
+fn bullet() -> Bullet {
+    Bullet {
+        id: "bullet".to_string(),
+        position: pos(1.0, 2.0),
+        velocity: Velocity { x: 3.0, y: 4.0 },
+        owner_id: "player".to_string(),
+        created_at: 5,
+        damage: 25,
+    }
+}
+
+fn zone() -> CaptureZone {
+    CaptureZone {
+        name: "A".to_string(),
+        position: pos(0.0, 0.0),
+        radius: 100.0,
+        owner: Some(Team::Red),
+        capturing_team: Some(Team::Red),
+        progress: 1.0,
+        contested: false,
+    }
+}
+
+fn map() -> Map {
+    Map {
+        walls: vec![Wall { from: pos(0.0, 0.0), to: pos(1.0, 0.0), thickness: 20.0 }],
+        boxes: vec![MapBox { min: pos(0.0, 0.0), max: pos(1.0, 1.0) }],
+        spawn_points: vec![pos(0.0, 0.0)],
+        flag_bases: Some(FlagBases::default()),
+        zones: Some(vec![ZoneSpot { name: "A".to_string(), position: pos(0.0, 0.0), radius: 100.0 }]),
+        ..Map::default()
+    }
+}
+
+fn scoreboard_entry() -> ScoreboardEntry {
+    ScoreboardEntry {
+        player_id: "player".to_string(),
+        name: "Ace".to_string(),
+        team: Some(Team::Red),
+        stats: PlayerStats::default(),
+        accuracy: 0.0,
+    }
+}
+
+/// One of every message, with every list filled in so each field shows up.
+fn samples() -> Vec<Value> {
+    let client = [
+        ClientMessage::Join {
+            name: "Ace".to_string(),
+            user_id: Some("user".to_string()),
+            token: Some("token".to_string()),
+            password: Some("password".to_string()),
+            protocol_version: PROTOCOL_VERSION,
+            client_kind: ClientKind::Native,
+            features: vec![Feature::BinaryEncoding],
+        },
+        ClientMessage::Input { seq: 1, tick: 2, input: 3 },
+        ClientMessage::Ack { tick: 1 },
+        ClientMessage::RequestKeyframe,
+        ClientMessage::UpdateSettings { settings: BTreeMap::new() },
+        ClientMessage::RequestLeaderboard,
+    ];
+    let server = [
+        ServerMessage::Joined {
+            player_id: "player".to_string(),
+            user_id: "user".to_string(),
+            token: "token".to_string(),
+            settings: BTreeMap::new(),
+            protocol_version: PROTOCOL_VERSION,
+            features: vec![Feature::BinaryEncoding],
+            map: map(),
+            tick_rate: 60,
+        },
+        ServerMessage::Rejected {
+            reason: RejectReason::ServerFull,
+            message: "full".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+        },
+        ServerMessage::GameState(GameState {
+            tick: 1,
+            server_time: 2,
+            tanks: vec![tank("player").build()],
+            bullets: vec![bullet()],
+            last_processed_input: [("player".to_string(), 1)].into_iter().collect(),
+            zones: vec![zone()],
+        }),
+        ServerMessage::GameStateDelta(GameStateDelta {
+            base_tick: 1,
+            tick: 2,
+            server_time: 3,
+            spawned_tanks: vec![tank("player").build()],
+            changed_tanks: vec![TankDelta {
+                id: "player".to_string(),
+                position: Some(pos(1.0, 1.0)),
+                rotation: Some(1.0),
+                turret_rotation: Some(1.0),
+                health: Some(50),
+                is_dead: Some(false),
+                respawn_time: Some(None),
+                last_fire_time: Some(1),
+                spawn_protected_until: Some(None),
+                team: Some(None),
+            }],
+            despawned_tanks: vec!["gone".to_string()],
+            spawned_bullets: vec![bullet()],
+            moved_bullets: vec![BulletDelta { id: "bullet".to_string(), position: pos(2.0, 2.0) }],
+            despawned_bullets: vec!["spent".to_string()],
+            last_processed_input: [("player".to_string(), 1)].into_iter().collect(),
+            zones: Some(vec![zone()]),
+        }),
+        ServerMessage::Hit(HitEvent {
+            tick: 1,
+            attacker_id: "a".to_string(),
+            target_id: "b".to_string(),
+            damage: 25,
+            side: HitSide::Side,
+            position: pos(0.0, 0.0),
+            health: 75,
+            killed: false,
+        }),
+        ServerMessage::Scoreboard { entries: vec![scoreboard_entry()] },
+        ServerMessage::Leaderboard {
+            entries: vec![LeaderboardEntry {
+                rank: 1,
+                name: "Ace".to_string(),
+                rating: 1500.0,
+                rated_matches: 1,
+                kills: 1,
+                deaths: 1,
+            }],
+        },
+        ServerMessage::MatchState(MatchState {
+            mode: GameMode::CaptureTheFlag,
+            phase: MatchPhase::Results,
+            match_number: 1,
+            phase_ends_at: Some(1),
+            score_limit: 3,
+            time_limit_ms: 1,
+            winner: Some("Red".to_string()),
+            results: vec![scoreboard_entry()],
+            team_scores: vec![TeamScore { team: Team::Red, score: 1 }],
+            flags: vec![Flag {
+                team: Team::Red,
+                base: pos(0.0, 0.0),
+                position: pos(0.0, 0.0),
+                carrier_id: None,
+                dropped_at: None,
+            }],
+        }),
+        ServerMessage::Flag(FlagEvent {
+            kind: FlagEventKind::Pickup,
+            flag_team: Team::Red,
+            tank_id: None,
+            position: pos(0.0, 0.0),
+        }),
+        ServerMessage::Zone(ZoneEvent {
+            kind: ZoneEventKind::Captured,
+            zone: "A".to_string(),
+            team: Team::Red,
+        }),
+    ];
+    client
+        .iter()
+        .map(|m| serde_json::to_value(m).unwrap())
+        .chain(server.iter().map(|m| serde_json::to_value(m).unwrap()))
+        .collect()
+}
+
+/// Every field path in `value`, prefixed by `path`; list items are `[]`.
+fn field_paths(value: &Value, path: &str, out: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                let path = format!("{}.{}", path, key);
+                out.push(path.clone());
+                field_paths(field, &path, out);
+            }
+        }
+        Value::Array(items) => {
+            if let Some(first) = items.first() {
+                field_paths(first, &format!("{}[]", path), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn fingerprint() -> Vec<String> {
+    let mut paths = Vec::new();
+    for message in samples() {
+        let kind = message["type"].as_str().unwrap().to_string();
+        field_paths(&message, &kind, &mut paths);
+    }
+    paths
+}
+
+#[test]
+fn test_when_wire_version_changes_then_protocol_version_is_bumped() {
+    for pair in HISTORY.windows(2) {
+        assert!(pair[1].0 > pair[0].0, "protocol versions must only go up: {:?}", pair);
+        assert!(pair[1].1 >= pair[0].1, "wire versions must not go back: {:?}", pair);
+    }
+    assert_eq!(
+        HISTORY.last(),
+        Some(&(PROTOCOL_VERSION, WIRE_VERSION)),
+        "WIRE_VERSION or PROTOCOL_VERSION changed: bump PROTOCOL_VERSION (and index.html's) and add a row to HISTORY"
+    );
+}
+
+#[test]
+fn test_when_message_fields_change_then_protocol_version_is_bumped() {
+    // Update this and bump PROTOCOL_VERSION together, as older clients
+    // can't read the new format
+    let expected_for_version = 4;
+    let expected = include_str!("protocol_fields.txt");
+    let actual = fingerprint().join("\n");
+    assert_eq!(
+        (PROTOCOL_VERSION, actual.trim()),
+        (expected_for_version, expected.trim()),
+        "message fields changed: bump PROTOCOL_VERSION and update tests/protocol_fields.txt"
+    );
+}
@@ -0,0 +1,166 @@
+use battlexone_shared::lifecycle::{MatchLifecycle, MatchSettings};
+use battlexone_shared::players::Player;
+use battlexone_shared::profiles::Profile;
+use battlexone_shared::rating::{
+    expected_score, free_for_all, leaderboard, rate_match, rating_change, team_match, DEFAULT_RATING, K_FACTOR,
+};
+use battlexone_shared::{GameMode, MatchState, PlayerStats, ScoreboardEntry, Team, TeamScore};
+use std::collections::HashMap;
+use proptest::prelude::*;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+#[test]
+fn test_when_ratings_differ_then_expected_score_follows_the_elo_curve() {
+    assert!(close(expected_score(1500.0, 1500.0), 0.5));
+    assert!(close(expected_score(1600.0, 1400.0), 0.7597469266));
+    assert!(close(expected_score(1400.0, 1600.0), 0.2402530734));
+    // 400 points apart is 10 to 1 odds
+    assert!(close(expected_score(1900.0, 1500.0), 10.0 / 11.0));
+}
+
+#[test]
+fn test_when_rating_the_reference_tournament_then_change_matches() {
+    // The textbook example: 1613 against five opponents scoring 2.5
+    let games = [(1609.0, 0.0), (1477.0, 0.5), (1388.0, 1.0), (1586.0, 1.0), (1720.0, 0.0)];
+    let rating = 1613.0 + rating_change(1613.0, &games, 32.0);
+    assert!(close(rating, 1601.269876862757), "{}", rating);
+    assert_eq!(rating.round(), 1601.0);
+}
+
+#[test]
+fn test_when_two_equal_players_meet_then_winner_gains_half_the_k_factor() {
+    let ratings = free_for_all(&[(DEFAULT_RATING, 0), (DEFAULT_RATING, 1)]);
+    assert!(close(ratings[0], DEFAULT_RATING + K_FACTOR / 2.0));
+    assert!(close(ratings[1], DEFAULT_RATING - K_FACTOR / 2.0));
+}
+
+#[test]
+fn test_when_free_for_all_ends_then_every_pairing_is_rated_by_place() {
+    // K is shared over the two pairings each player has
+    let ratings = free_for_all(&[(1500.0, 0), (1500.0, 1), (1500.0, 2)]);
+    assert!(close(ratings[0], 1516.0) && close(ratings[1], 1500.0) && close(ratings[2], 1484.0), "{:?}", ratings);
+
+    // Sharing a place is a draw
+    let ratings = free_for_all(&[(1500.0, 0), (1500.0, 0)]);
+    assert!(close(ratings[0], 1500.0) && close(ratings[1], 1500.0));
+
+    // Nobody to play against
+    assert_eq!(free_for_all(&[(1600.0, 0)]), vec![1600.0]);
+}
+
+#[test]
+fn test_when_team_match_ends_then_members_move_by_their_teams_result() {
+    let (red, blue) = team_match(&[1500.0, 1600.0], &[1500.0, 1500.0], 1.0);
+    // Red averages 1550 against 1500
+    let change = 13.713180242931795;
+    assert!(close(red[0], 1500.0 + change) && close(red[1], 1600.0 + change), "{:?}", red);
+    assert!(close(blue[0], 1500.0 - change) && close(blue[1], 1500.0 - change), "{:?}", blue);
+
+    let (red, blue) = team_match(&[1500.0], &[1500.0], 0.5);
+    assert_eq!((red, blue), (vec![1500.0], vec![1500.0]));
+    assert_eq!(team_match(&[1500.0], &[], 1.0), (vec![1500.0], vec![]));
+}
+
+proptest! {
+    #[test]
+    fn test_when_free_for_all_is_rated_then_total_rating_is_unchanged(
+        players in prop::collection::vec((1000.0f64..2500.0, 0u32..4), 2..8)
+    ) {
+        let before: f64 = players.iter().map(|(rating, _)| rating).sum();
+        let after: f64 = free_for_all(&players).iter().sum();
+        prop_assert!((before - after).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_when_players_are_equal_then_finishing_higher_never_costs_rating(places in prop::collection::vec(0u32..6, 2..8)) {
+        let players: Vec<(f64, u32)> = places.iter().map(|&place| (DEFAULT_RATING, place)).collect();
+        let ratings = free_for_all(&players);
+        for (i, a) in players.iter().enumerate() {
+            for (j, b) in players.iter().enumerate() {
+                if a.1 < b.1 {
+                    prop_assert!(ratings[i] > ratings[j]);
+                }
+            }
+        }
+    }
+}
+
+fn rated(user_id: &str, rating: f64, rated_matches: u64) -> Profile {
+    Profile { rating, rated_matches, ..Profile::new(user_id, user_id) }
+}
+
+#[test]
+fn test_when_players_are_still_connected_then_the_leaderboard_uses_their_live_profiles() {
+    let saved = vec![rated("ann", 1600.0, 3), rated("bob", 1550.0, 2), rated("cat", 1700.0, 1)];
+    // bob has since played a match this session, and dan his first
+    let live = vec![rated("bob", 1650.0, 3), rated("dan", 1520.0, 1), rated("eve", 1800.0, 0)];
+
+    let entries = leaderboard(saved, live, 10);
+    let rows: Vec<(u32, &str, f64, u64)> =
+        entries.iter().map(|e| (e.rank, e.name.as_str(), e.rating, e.rated_matches)).collect();
+    assert_eq!(
+        rows,
+        [(1, "cat", 1700.0, 1), (2, "bob", 1650.0, 3), (3, "ann", 1600.0, 3), (4, "dan", 1520.0, 1)]
+    );
+
+    let top = leaderboard(vec![rated("ann", 1600.0, 3), rated("cat", 1700.0, 1)], Vec::new(), 1);
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].name, "cat");
+}
+
+fn player(name: &str, team: Option<Team>, rating: f64) -> (String, Player) {
+    let player = Player {
+        name: name.to_string(),
+        user_id: name.to_string(),
+        team,
+        stats: PlayerStats::default(),
+        disconnected_at: None,
+        profile: rated(name, rating, 0),
+    };
+    (name.to_string(), player)
+}
+
+fn finished(name: &str, kills: u32, deaths: u32) -> ScoreboardEntry {
+    let stats = PlayerStats { kills, deaths, ..PlayerStats::default() };
+    ScoreboardEntry { player_id: name.to_string(), name: name.to_string(), team: None, accuracy: 0.0, stats }
+}
+
+fn ended(mode: GameMode) -> MatchState {
+    MatchLifecycle::new(MatchSettings { mode, ..MatchSettings::default() }).state().clone()
+}
+
+#[test]
+fn test_when_free_for_all_ends_then_players_still_here_are_rated_by_standing() {
+    let players: HashMap<String, Player> =
+        [player("ann", None, 1500.0), player("bob", None, 1500.0), player("cat", None, 1500.0)].into();
+    let mut state = ended(GameMode::FreeForAll);
+    // dan left before the end; bob and cat tie
+    state.results = vec![finished("ann", 5, 1), finished("dan", 4, 0), finished("bob", 2, 3), finished("cat", 2, 3)];
+
+    let rated: HashMap<String, f64> = rate_match(&state, &players).into_iter().collect();
+    assert_eq!(rated.len(), 3);
+    assert!(rated["ann"] > 1500.0);
+    assert!(close(rated["bob"], rated["cat"]) && rated["bob"] < 1500.0, "{:?}", rated);
+
+    state.results.truncate(1);
+    assert!(rate_match(&state, &players).is_empty());
+}
+
+#[test]
+fn test_when_team_match_ends_then_every_member_moves_with_the_team() {
+    let mut players: HashMap<String, Player> =
+        [player("ann", Some(Team::Red), 1500.0), player("bob", Some(Team::Red), 1600.0), player("cat", Some(Team::Blue), 1550.0)]
+            .into();
+    let mut state = ended(GameMode::TeamDeathmatch);
+    state.team_scores = vec![TeamScore { team: Team::Red, score: 3 }, TeamScore { team: Team::Blue, score: 5 }];
+
+    let rated: HashMap<String, f64> = rate_match(&state, &players).into_iter().collect();
+    let (red, blue) = team_match(&[1500.0, 1600.0], &[1550.0], 0.0);
+    assert!(close(rated["ann"], red[0]) && close(rated["bob"], red[1]) && close(rated["cat"], blue[0]), "{:?}", rated);
+
+    players.remove("cat");
+    assert!(rate_match(&state, &players).is_empty());
+}
@@ -0,0 +1,30 @@
+use battlexone_shared::reconnect::{grace_expired, Backoff};
+use std::time::Duration;
+
+#[test]
+fn test_when_grace_period_runs_out_then_player_is_gone() {
+    assert!(!grace_expired(1000, 1000, 30_000));
+    assert!(!grace_expired(1000, 30_999, 30_000));
+    assert!(grace_expired(1000, 31_000, 30_000));
+    // A clock reading from before the disconnect is still within grace
+    assert!(!grace_expired(1000, 500, 30_000));
+    // No grace at all removes them straight away
+    assert!(grace_expired(1000, 1000, 0));
+}
+
+#[test]
+fn test_when_retries_keep_failing_then_backoff_doubles_up_to_the_ceiling() {
+    let mut backoff = Backoff::new(500, 3000);
+    let delays: Vec<Duration> = (0..5).map(|_| backoff.next_delay()).collect();
+    let ms: Vec<u64> = delays.iter().map(|d| d.as_millis() as u64).collect();
+    assert_eq!(ms, [500, 1000, 2000, 3000, 3000]);
+}
+
+#[test]
+fn test_when_connection_worked_then_backoff_starts_over() {
+    let mut backoff = Backoff::new(500, 3000);
+    backoff.next_delay();
+    backoff.next_delay();
+    backoff.reset();
+    assert_eq!(backoff.next_delay(), Duration::from_millis(500));
+}
@@ -0,0 +1,41 @@
+use battlexone_shared::respawn::choose_spawn_point;
+
+mod common;
+use common::pos;
+
+#[test]
+fn test_when_enemy_is_near_a_spawn_point_then_the_far_one_is_chosen() {
+    let candidates = [pos(-500.0, 0.0), pos(500.0, 0.0)];
+    let enemies = [pos(-450.0, 20.0)];
+
+    assert_eq!(choose_spawn_point(&candidates, &enemies, &[]), Some(pos(500.0, 0.0)));
+}
+
+#[test]
+fn test_when_someone_just_died_at_a_spawn_point_then_it_is_avoided() {
+    let candidates = [pos(0.0, 500.0), pos(0.0, -500.0)];
+    let enemies = [pos(0.0, 0.0)];
+    let recent_deaths = [pos(0.0, 480.0)];
+
+    assert_eq!(
+        choose_spawn_point(&candidates, &enemies, &recent_deaths),
+        Some(pos(0.0, -500.0))
+    );
+}
+
+#[test]
+fn test_when_choosing_a_spawn_point_then_the_one_with_most_clearance_wins() {
+    let candidates = [pos(0.0, 0.0), pos(300.0, 0.0), pos(600.0, 0.0)];
+    // Closer to the far end than to the middle; the middle is furthest from both
+    let enemies = [pos(-100.0, 0.0), pos(700.0, 0.0)];
+
+    assert_eq!(choose_spawn_point(&candidates, &enemies, &[]), Some(pos(300.0, 0.0)));
+}
+
+#[test]
+fn test_when_map_is_empty_then_first_candidate_is_chosen() {
+    let candidates = [pos(1.0, 2.0), pos(3.0, 4.0)];
+
+    assert_eq!(choose_spawn_point(&candidates, &[], &[]), Some(pos(1.0, 2.0)));
+    assert_eq!(choose_spawn_point(&[], &[], &[]), None);
+}
@@ -0,0 +1,67 @@
+use battlexone_shared::grid::SpatialGrid;
+use battlexone_shared::rewind::{rewind_ticks, TankHistory};
+use battlexone_shared::{Position, Tank};
+
+mod common;
+use common::tank;
+
+/// A target strafing along +y at 5 units a tick, recorded for `ticks` ticks.
+fn strafing(history: &mut TankHistory, ticks: u64) -> Vec<Tank> {
+    let mut tanks = vec![tank("shooter").at(-300.0, 0.0).build(), tank("target").build()];
+    for tick in 1..=ticks {
+        tanks[1].position.y = tick as f32 * 5.0;
+        history.record(tick, &tanks);
+    }
+    tanks
+}
+
+#[test]
+fn test_when_rewound_then_tanks_are_where_they_were_at_that_tick() {
+    let mut history = TankHistory::new(10);
+    let mut tanks = strafing(&mut history, 10);
+    // Someone who joined after tick 4 stays where they are
+    tanks.push(tank("newcomer").at(50.0, 50.0).build());
+
+    let past = history.rewound(&tanks, 4).unwrap();
+    let ids: Vec<&str> = past.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, ["shooter", "target", "newcomer"]);
+    assert_eq!(past[1].position, Position { x: 0.0, y: 20.0 });
+    assert_eq!(past[2].position, Position { x: 50.0, y: 50.0 });
+}
+
+#[test]
+fn test_when_ticks_are_older_than_the_window_then_they_are_forgotten() {
+    let mut history = TankHistory::new(5);
+    let tanks = strafing(&mut history, 10);
+
+    assert!(history.rewound(&tanks, 5).is_none());
+    assert!(history.rewound(&tanks, 6).is_some());
+    assert!(history.rewound(&tanks, 11).is_none());
+    assert!(TankHistory::new(0).rewound(&tanks, 10).is_none());
+}
+
+#[test]
+fn test_when_shooter_lags_then_rewind_covers_the_delay_up_to_the_limit() {
+    assert_eq!(rewind_ticks(100, Some(94), 15), 6);
+    assert_eq!(rewind_ticks(100, Some(40), 15), 15);
+    assert_eq!(rewind_ticks(100, None, 15), 0);
+    // An ack from the future is clamped rather than wrapping
+    assert_eq!(rewind_ticks(100, Some(101), 15), 0);
+}
+
+#[test]
+fn test_when_target_has_moved_on_then_shot_hits_it_where_the_shooter_saw_it() {
+    let mut history = TankHistory::new(16);
+    let tanks = strafing(&mut history, 20);
+    // Aimed at tick 10's position; the target is 50 units further on now
+    let (from, to) = (Position { x: -40.0, y: 50.0 }, Position { x: 40.0, y: 50.0 });
+    let not_shooter = |t: &Tank| t.id != "shooter";
+
+    let present = SpatialGrid::from_tanks(&tanks);
+    assert_eq!(present.first_hit(&tanks, &from, &to, 30.0, not_shooter), None);
+
+    let tick = 20 - rewind_ticks(20, Some(10), 15);
+    let past = history.rewound(&tanks, tick).unwrap();
+    let hit = SpatialGrid::from_tanks(&past).first_hit(&past, &from, &to, 30.0, not_shooter);
+    assert_eq!(hit.map(|(i, _)| i), Some(1));
+}
@@ -0,0 +1,269 @@
+use battlexone_shared::auth::AuthMode;
+use battlexone_shared::map::MapError;
+use battlexone_shared::npc::Difficulty;
+use battlexone_shared::profiles::Profile;
+use battlexone_shared::server_config::{
+    Args, ConfigError, ServerConfig, Tuning, DEFAULT_BIND, MAX_REWIND_MS, MAX_WORLD_SIZE,
+};
+use battlexone_shared::GameMode;
+
+fn flags(args: &[&str]) -> Result<Args, ConfigError> {
+    Args::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+fn overrides(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn test_when_config_is_empty_then_server_runs_on_the_defaults() {
+    let config = ServerConfig::from_toml("", &[]).unwrap();
+    assert_eq!(config, ServerConfig::default());
+    assert_eq!(config.bind, DEFAULT_BIND);
+    assert_eq!(config.world.npc_count, 5);
+    assert_eq!(config.tuning.player_fire_cooldown_ms, 500);
+}
+
+#[test]
+fn test_when_config_file_sets_some_values_then_the_rest_keep_defaults() {
+    let toml = r#"
+        bind = "0.0.0.0:4000"
+        max_players = 4
+
+        [world]
+        size = 1500.0
+        npc_count = 0
+
+        [tuning]
+        bullet_damage = 40
+    "#;
+    let config = ServerConfig::from_toml(toml, &[]).unwrap();
+    assert_eq!(config.bind, "0.0.0.0:4000");
+    assert_eq!(config.max_players, 4);
+    assert_eq!(config.world.size, Some(1500.0));
+    assert_eq!(config.world.npc_count, 0);
+    assert_eq!(config.tuning.bullet_damage, 40);
+    assert_eq!(config.tuning.bullet_speed, Tuning::default().bullet_speed);
+}
+
+#[test]
+fn test_when_flags_are_given_then_they_win_over_the_file() {
+    let args = flags(&["--config", "tuned.toml", "--npcs", "12", "--bind=0.0.0.0:3001", "--bullet-speed", "350"]).unwrap();
+    assert_eq!(args.config.as_deref(), Some(std::path::Path::new("tuned.toml")));
+
+    let toml = "[world]\nnpc_count = 3\nnpc_spawn_radius = 800.0\n";
+    let config = ServerConfig::from_toml(toml, &args.overrides).unwrap();
+    assert_eq!(config.world.npc_count, 12);
+    assert_eq!(config.world.npc_spawn_radius, 800.0);
+    assert_eq!(config.bind, "0.0.0.0:3001");
+    assert_eq!(config.tuning.bullet_speed, 350.0);
+
+    // Still read as an address, so it fails as one rather than as a type
+    let err = ServerConfig::from_toml("", &overrides(&[("bind", "3001")])).unwrap_err();
+    assert!(err.to_string().contains("bind must be an address"), "{}", err);
+}
+
+#[test]
+fn test_when_command_line_is_wrong_then_it_says_what() {
+    assert_eq!(flags(&["--npc", "3"]), Err(ConfigError::Usage("unknown option --npc".to_string())));
+    assert_eq!(flags(&["--tick-rate"]), Err(ConfigError::Usage("--tick-rate needs a value".to_string())));
+    assert!(flags(&["-h"]).unwrap().help);
+
+    let err = ServerConfig::from_toml("", &overrides(&[("world.npc_count", "lots")])).unwrap_err();
+    assert!(matches!(err, ConfigError::Parse(_)), "{:?}", err);
+}
+
+#[test]
+fn test_when_file_has_a_typo_then_the_error_names_it() {
+    let err = ServerConfig::from_toml("[tuning]\nbullet_sped = 300.0\n", &[]).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("bullet_sped") && message.contains("line 2"), "{}", message);
+
+    let err = ServerConfig::from_toml("tick_rate = \"fast\"\n", &[]).unwrap_err();
+    assert!(err.to_string().contains("tick_rate"), "{}", err);
+}
+
+#[test]
+fn test_when_values_are_out_of_range_then_every_problem_is_listed() {
+    let toml = "tick_rate = 0\nmax_players = 0\n[world]\nsize = -5.0\n[tuning]\nbullet_speed = 0.0\n";
+    let Err(ConfigError::Invalid(problems)) = ServerConfig::from_toml(toml, &[]) else {
+        panic!("config should have been refused");
+    };
+    assert_eq!(problems.len(), 5, "{:?}", problems);
+    assert!(problems.iter().any(|p| p.starts_with("tick_rate")));
+    assert!(problems.iter().any(|p| p.starts_with("snapshot_rate")));
+    assert!(problems.iter().any(|p| p.starts_with("max_players")));
+    assert!(problems.iter().any(|p| p.starts_with("world.size")));
+    assert!(problems.iter().any(|p| p.starts_with("tuning.bullet_speed")));
+}
+
+#[test]
+fn test_when_only_tuning_changes_then_no_restart_is_needed() {
+    let running = ServerConfig::default();
+    let mut edited = running.clone();
+    edited.tuning.bullet_damage = 50;
+    edited.tuning.npc_speed = 60.0;
+    assert!(running.restart_needed(&edited).is_empty());
+
+    edited.tick_rate = 30;
+    edited.world.npc_count = 9;
+    assert_eq!(running.restart_needed(&edited), vec!["tick_rate", "world"]);
+}
+
+#[test]
+fn test_when_server_toml_is_loaded_then_it_matches_the_defaults() {
+    let config = ServerConfig::from_toml(include_str!("../server.toml"), &[]).unwrap();
+    assert_eq!(config, ServerConfig::default());
+}
+
+#[test]
+fn test_when_match_and_players_are_configured_then_the_server_uses_them() {
+    let toml = r#"
+        max_rewind_ms = 100
+
+        [match]
+        mode = "ctf"
+        time_limit_secs = 600
+        friendly_fire = true
+
+        [world]
+        map = "maps/arena.json"
+        npc_difficulty = "hard"
+
+        [players]
+        accounts = "accounts.json"
+        profiles = ""
+    "#;
+    let args = flags(&["--respawn-delay-ms", "3000", "--score-limit=5", "--token-ttl-secs", "3600"]).unwrap();
+    let config = ServerConfig::from_toml(toml, &args.overrides).unwrap();
+    assert_eq!(config.max_rewind_ms, 100);
+    assert!(config.match_.friendly_fire);
+    assert_eq!(config.match_.respawn_delay_ms, 3000);
+    assert_eq!(config.world.map, "maps/arena.json");
+    assert_eq!(config.world.npc_difficulty, Difficulty::Hard);
+    assert_eq!(config.players.accounts, "accounts.json");
+    assert_eq!(config.players.profiles, "");
+    assert_eq!(config.players.token_ttl_secs, 3600);
+
+    let settings = config.match_.settings();
+    assert_eq!(settings.mode, GameMode::CaptureTheFlag);
+    assert_eq!(settings.score_limit, 5);
+    assert_eq!(settings.time_limit_ms, 600_000);
+}
+
+#[test]
+fn test_when_score_limit_is_unset_then_the_mode_picks_it() {
+    for (mode, score_limit) in [("ffa", 10), ("tdm", 25), ("ctf", 3), ("koth", 100), ("domination", 200)] {
+        let config = ServerConfig::from_toml("", &overrides(&[("match.mode", mode)])).unwrap();
+        assert_eq!(config.match_.settings().score_limit, score_limit, "{}", mode);
+    }
+}
+
+#[test]
+fn test_when_game_mode_is_unknown_then_loading_fails() {
+    let err = ServerConfig::from_toml("[match]\nmode = \"deathmatch\"\n", &[]).unwrap_err();
+    let message = err.to_string();
+    assert!(matches!(err, ConfigError::Parse(_)), "{:?}", err);
+    assert!(message.contains("unknown game mode \"deathmatch\""), "{}", message);
+
+    let err = ServerConfig::from_toml("", &overrides(&[("match.mode", "capture")])).unwrap_err();
+    assert!(err.to_string().contains("unknown game mode"), "{}", err);
+}
+
+#[test]
+fn test_when_limits_are_too_large_then_they_are_refused() {
+    let toml = "max_rewind_ms = 100000\n[match]\nscore_limit = 0\ntime_limit_secs = 0\nrespawn_delay_ms = 7200000\n\
+                [world]\nsize = 100000.0\nmap = \"\"\n[players]\ntoken_ttl_secs = 0\n";
+    let Err(ConfigError::Invalid(problems)) = ServerConfig::from_toml(toml, &[]) else {
+        panic!("config should have been refused");
+    };
+    assert_eq!(problems.len(), 7, "{:?}", problems);
+    for key in [
+        "max_rewind_ms",
+        "match.score_limit",
+        "match.time_limit_secs",
+        "match.respawn_delay_ms",
+        "world.map",
+        "world.size",
+        "players.token_ttl_secs",
+    ] {
+        assert!(problems.iter().any(|p| p.starts_with(key)), "{} in {:?}", key, problems);
+    }
+
+    assert!(ServerConfig::from_toml("", &overrides(&[("world.size", &MAX_WORLD_SIZE.to_string())])).is_ok());
+    assert!(ServerConfig::from_toml("", &overrides(&[("max_rewind_ms", &MAX_REWIND_MS.to_string())])).is_ok());
+}
+
+#[test]
+fn test_when_match_or_players_change_then_a_restart_is_needed() {
+    let running = ServerConfig::default();
+    let mut edited = running.clone();
+    edited.max_rewind_ms = 0;
+    edited.match_.mode = GameMode::Domination;
+    edited.players.reconnect_grace_ms = 0;
+    assert_eq!(running.restart_needed(&edited), vec!["max_rewind_ms", "match", "players"]);
+}
+
+/// A file under the system temp dir, unique to this test process.
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("battlexone-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn test_when_map_is_loaded_then_it_is_resized_and_checked_against_the_mode() {
+    let path = temp_path("map.json");
+    std::fs::write(&path, r#"{"name": "Small", "bounds": 2000.0}"#).unwrap();
+    let map_path = path.to_str().unwrap();
+
+    let config = ServerConfig::from_toml("", &overrides(&[("world.map", map_path), ("world.size", "800")])).unwrap();
+    let map = config.load_map().unwrap();
+    assert_eq!((map.name.as_str(), map.bounds), ("Small", 800.0));
+
+    let config = ServerConfig::from_toml(
+        "",
+        &overrides(&[("world.map", map_path), ("world.size", "600"), ("match.mode", "ctf")]),
+    )
+    .unwrap();
+    let Err(MapError::Invalid(message)) = config.load_map() else {
+        panic!("capture the flag shouldn't fit");
+    };
+    assert!(message.contains("world.size") && message.contains("flag base"), "{}", message);
+    std::fs::remove_file(&path).unwrap();
+
+    // Only the default map may be missing
+    let default = ServerConfig::from_toml("", &overrides(&[("world.map", "no/such/map.json")])).unwrap();
+    assert!(matches!(default.load_map(), Err(MapError::Io(_))));
+}
+
+#[test]
+fn test_when_players_section_is_set_then_auth_and_stores_follow_it() {
+    let mut config = ServerConfig::default();
+    assert!(matches!(config.auth_mode(None), Ok(AuthMode::Open)));
+    assert!(matches!(config.auth_mode(Some(String::new())), Ok(AuthMode::Open)));
+    assert!(matches!(config.auth_mode(Some("key".to_string())), Ok(AuthMode::SharedKey(key)) if key == "key"));
+
+    let accounts = temp_path("accounts.json");
+    std::fs::write(&accounts, r#"{"accounts": [{"name": "ann", "password_sha256": "AB"}]}"#).unwrap();
+    config.players.accounts = accounts.to_str().unwrap().to_string();
+    assert!(matches!(config.auth_mode(Some("key".to_string())), Ok(AuthMode::Accounts(list)) if list["ann"] == "ab"));
+    std::fs::remove_file(&accounts).unwrap();
+    assert!(config.auth_mode(None).is_err());
+
+    config.players.profiles = String::new();
+    let mut store = config.open_profile_store().unwrap();
+    store.save(&Profile::new("u1", "ann")).unwrap();
+    assert_eq!(store.load("u1").map(|p| p.name), Some("ann".to_string()));
+
+    let tokens = config.token_signer(Some("secret".to_string()));
+    let token = tokens.issue("u1", 0);
+    assert_eq!(config.token_signer(Some("secret".to_string())).verify(&token, 0), Ok("u1".to_string()));
+    assert!(config.token_signer(None).verify(&token, 0).is_err());
+}
+
+#[test]
+fn test_when_rewinding_then_the_limit_is_counted_in_ticks() {
+    let config = ServerConfig::from_toml("tick_rate = 120\nmax_rewind_ms = 250\n", &[]).unwrap();
+    assert_eq!(config.max_rewind_ticks(), 30);
+    let config = ServerConfig::from_toml("max_rewind_ms = 0\n", &[]).unwrap();
+    assert_eq!(config.max_rewind_ticks(), 0);
+}
@@ -0,0 +1,170 @@
+use battlexone_shared::delta::GameStateDelta;
+use battlexone_shared::movement::{apply_movement, SIMULATION_DT};
+use battlexone_shared::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+mod common;
+
+const SNAPSHOT_RATE: u64 = 20;
+const TICKS_PER_SNAPSHOT: u64 = SIMULATION_TICK_RATE as u64 / SNAPSHOT_RATE;
+
+/// Tank number `id`, with a UUID-shaped id like the server's.
+fn tank(id: usize, is_player: bool) -> Tank {
+    let tank = common::tank(&format!("00000000-0000-4000-8000-{:012}", id)).at(id as f32 * 40.0, 0.0);
+    if is_player {
+        tank.build()
+    } else {
+        tank.npc().build()
+    }
+}
+
+fn bullet(id: usize, owner: &Tank) -> Bullet {
+    Bullet {
+        id: format!("b0000000-0000-4000-8000-{:012}", id),
+        position: owner.position.clone(),
+        velocity: Velocity {
+            x: owner.turret_rotation.cos() * 200.0,
+            y: owner.turret_rotation.sin() * 200.0,
+        },
+        owner_id: owner.id.clone(),
+        created_at: 0,
+        damage: 25,
+    }
+}
+
+fn message_len(message: &ServerMessage) -> usize {
+    serde_json::to_string(message).unwrap().len()
+}
+
+#[test]
+fn test_when_delta_applied_to_base_then_matches_current() {
+    let base = GameState {
+        tick: 10,
+        server_time: 500,
+        tanks: vec![tank(1, true), tank(2, false), tank(3, false)],
+        bullets: vec![bullet(1, &tank(1, true))],
+        last_processed_input: [(tank(1, true).id, 4)].into_iter().collect(),
+        zones: Vec::new(),
+    };
+
+    let mut current = base.clone();
+    current.tick = 13;
+    current.server_time = 550;
+    current.tanks.remove(1);
+    current.tanks[0].position.x += 5.0;
+    current.tanks[1].is_dead = true;
+    current.tanks[1].respawn_time = Some(5000);
+    current.tanks.push(tank(4, true));
+    current.bullets[0].position.y += 10.0;
+    current.bullets.push(bullet(2, &tank(3, false)));
+    current.last_processed_input.insert(tank(1, true).id, 7);
+
+    let delta = GameStateDelta::between(&base, &current);
+    assert_eq!(delta.apply(&base), Some(current.clone()));
+
+    // Clearing an optional field has to survive the trip through JSON
+    let mut revived = current.clone();
+    revived.tick = 14;
+    revived.tanks[1].respawn_time = None;
+    let delta = GameStateDelta::between(&current, &revived);
+    let json = serde_json::to_string(&ServerMessage::GameStateDelta(delta)).unwrap();
+    let ServerMessage::GameStateDelta(decoded) = serde_json::from_str(&json).unwrap() else {
+        panic!("expected a delta message");
+    };
+    assert_eq!(decoded.apply(&current), Some(revived));
+}
+
+#[test]
+fn test_when_delta_base_does_not_match_then_apply_fails() {
+    let base = GameState {
+        tick: 1,
+        ..GameState::default()
+    };
+    let current = GameState {
+        tick: 2,
+        ..GameState::default()
+    };
+    let delta = GameStateDelta::between(&base, &current);
+    assert_eq!(delta.apply(&current), None);
+}
+
+#[test]
+fn test_when_zones_are_unchanged_then_delta_leaves_them_out() {
+    let base = GameState {
+        tick: 1,
+        zones: zones::zones_for_mode(GameMode::Domination),
+        ..GameState::default()
+    };
+    let mut current = GameState { tick: 2, ..base.clone() };
+    assert_eq!(GameStateDelta::between(&base, &current).zones, None);
+
+    current.zones[1].capturing_team = Some(Team::Red);
+    current.zones[1].progress = 0.25;
+    let delta = GameStateDelta::between(&base, &current);
+    assert_eq!(delta.zones.as_ref(), Some(&current.zones));
+    assert_eq!(delta.apply(&base), Some(current));
+}
+
+/// Ten seconds of a 32-tank match where each tank switches between idling,
+/// driving, turning and aiming every couple of seconds and fires now and then.
+#[test]
+fn test_when_32_tanks_play_then_delta_bandwidth_stays_small() {
+    let mut rng = StdRng::seed_from_u64(32);
+    let map = map::Map::default();
+    let mut state = GameState {
+        tanks: (0..32).map(|i| tank(i, i < 8)).collect(),
+        ..GameState::default()
+    };
+    let mut inputs = vec![0u16; state.tanks.len()];
+    let mut next_bullet = 0;
+    let seconds = 10;
+
+    let mut full_bytes = 0;
+    let mut delta_bytes = 0;
+    let mut previous = state.clone();
+
+    for tick in 1..=(SIMULATION_TICK_RATE as u64 * seconds) {
+        for (tank, input) in state.tanks.iter_mut().zip(inputs.iter_mut()) {
+            if rng.gen_bool(0.01) {
+                *input = [0, 0b1, 0b1001, 0b100000, 0b100001][rng.gen_range(0..5)];
+            }
+            apply_movement(tank, *input, SIMULATION_DT, &map);
+        }
+        for bullet in state.bullets.iter_mut() {
+            bullet.position.x += bullet.velocity.x * SIMULATION_DT;
+            bullet.position.y += bullet.velocity.y * SIMULATION_DT;
+        }
+        if rng.gen_bool(0.05) {
+            let shooter = &state.tanks[rng.gen_range(0..state.tanks.len())];
+            state.bullets.push(bullet(next_bullet, shooter));
+            next_bullet += 1;
+        }
+        // Bullets live for about two seconds
+        if state.bullets.len() > 6 {
+            state.bullets.remove(0);
+        }
+        state.tick = tick;
+
+        if tick % TICKS_PER_SNAPSHOT == 0 {
+            state.server_time = tick * 1000 / SIMULATION_TICK_RATE as u64;
+            let delta = GameStateDelta::between(&previous, &state);
+            assert_eq!(delta.apply(&previous).as_ref(), Some(&state));
+
+            full_bytes += message_len(&ServerMessage::GameState(state.clone()));
+            delta_bytes += message_len(&ServerMessage::GameStateDelta(delta));
+            previous = state.clone();
+        }
+    }
+
+    let full_per_second = full_bytes / seconds as usize;
+    let delta_per_second = delta_bytes / seconds as usize;
+    assert!(
+        delta_per_second * 2 < full_per_second,
+        "deltas should at least halve the bandwidth: 32 tanks at {} Hz took {} B/s in full snapshots and {} B/s in deltas ({:.0}%)",
+        SNAPSHOT_RATE,
+        full_per_second,
+        delta_per_second,
+        delta_per_second as f64 * 100.0 / full_per_second as f64
+    );
+}
@@ -0,0 +1,119 @@
+use battlexone_shared::grid::SpatialGrid;
+use battlexone_shared::map::{Map, MapBox, Wall};
+use battlexone_shared::sweep::{lerp, segment_box, segment_capsule, segment_circle};
+use battlexone_shared::{Position, Tank};
+use proptest::prelude::*;
+
+mod common;
+use common::{pos, tank};
+
+const HIT_RADIUS: f32 = 30.0;
+const DT: f32 = 1.0 / 60.0;
+
+/// Flies a bullet from `start` along +x at `speed` the way the server does,
+/// returning the tick and point it first hits a tank, stopping at walls.
+fn fly(tanks: &[Tank], map: &Map, start: Position, speed: f32, ticks: usize) -> Option<(usize, Position)> {
+    let grid = SpatialGrid::from_tanks(tanks);
+    let mut position = start;
+    for tick in 0..ticks {
+        let from = position.clone();
+        position.x += speed * DT;
+        let obstacle = map.bullet_impact(&from, &position);
+        let hit = grid.first_hit(tanks, &from, &position, HIT_RADIUS, |_| true);
+        if let Some((_, t)) = hit.filter(|&(_, t)| obstacle.is_none_or(|wall| t <= wall)) {
+            return Some((tick, lerp(&from, &position, t)));
+        }
+        if obstacle.is_some() {
+            return None;
+        }
+    }
+    None
+}
+
+#[test]
+fn test_when_path_crosses_a_circle_then_impact_is_where_it_enters() {
+    // One step carries the bullet clean over the tank
+    let hit = segment_circle(&pos(-100.0, 0.0), &pos(100.0, 0.0), &pos(0.0, 0.0), HIT_RADIUS);
+    assert_eq!(hit, Some(0.35));
+
+    assert_eq!(segment_circle(&pos(-100.0, 40.0), &pos(100.0, 40.0), &pos(0.0, 0.0), HIT_RADIUS), None);
+    assert_eq!(segment_circle(&pos(-100.0, 0.0), &pos(-50.0, 0.0), &pos(0.0, 0.0), HIT_RADIUS), None);
+    // Already inside counts from the start; heading away from outside never hits
+    assert_eq!(segment_circle(&pos(10.0, 0.0), &pos(100.0, 0.0), &pos(0.0, 0.0), HIT_RADIUS), Some(0.0));
+    assert_eq!(segment_circle(&pos(40.0, 0.0), &pos(100.0, 0.0), &pos(0.0, 0.0), HIT_RADIUS), None);
+}
+
+#[test]
+fn test_when_path_crosses_a_box_or_thin_wall_then_it_is_stopped() {
+    let (min, max) = (pos(-10.0, -10.0), pos(10.0, 10.0));
+    assert_eq!(segment_box(&pos(-30.0, 0.0), &pos(30.0, 0.0), &min, &max), Some(20.0 / 60.0));
+    assert_eq!(segment_box(&pos(-30.0, 20.0), &pos(30.0, 20.0), &min, &max), None);
+
+    // A wall 2 units thick, crossed diagonally in a single step
+    let (a, b) = (pos(0.0, -100.0), pos(0.0, 100.0));
+    let hit = segment_capsule(&pos(-50.0, -50.0), &pos(50.0, 50.0), &a, &b, 1.0).unwrap();
+    assert!((hit - 0.49).abs() < 1e-4, "{}", hit);
+    // Past the rounded end of the wall
+    assert_eq!(segment_capsule(&pos(-50.0, 102.0), &pos(50.0, 102.0), &a, &b, 1.0), None);
+}
+
+#[test]
+fn test_when_path_crosses_two_tanks_then_first_hit_is_the_earliest_along_it() {
+    let tanks = [tank("far").at(200.0, 0.0).build(), tank("near").at(100.0, 0.0).build()];
+    let grid = SpatialGrid::from_tanks(&tanks);
+
+    let hit = grid.first_hit(&tanks, &pos(0.0, 0.0), &pos(300.0, 0.0), HIT_RADIUS, |_| true);
+    assert_eq!(hit.map(|(i, _)| i), Some(1));
+    let hit = grid.first_hit(&tanks, &pos(0.0, 0.0), &pos(300.0, 0.0), HIT_RADIUS, |t| t.id == "far");
+    assert_eq!(hit.map(|(i, _)| i), Some(0));
+}
+
+#[test]
+fn test_when_a_thin_wall_is_in_the_way_then_fast_bullets_stop_on_it() {
+    let map = Map {
+        walls: vec![Wall {
+            from: pos(0.0, -200.0),
+            to: pos(0.0, 200.0),
+            thickness: 2.0,
+        }],
+        ..Map::default()
+    };
+    let tanks = [tank("behind").at(100.0, 0.0).build()];
+
+    // 600 units a tick, far more than the wall is thick
+    assert_eq!(fly(&tanks, &map, pos(-500.0, 0.0), 36_000.0, 10), None);
+    assert!(fly(&tanks, &Map::default(), pos(-500.0, 0.0), 36_000.0, 10).is_some());
+}
+
+#[test]
+fn test_when_a_tank_is_in_front_of_a_box_then_the_tank_is_hit_first() {
+    let map = Map {
+        boxes: vec![MapBox {
+            min: pos(150.0, -50.0),
+            max: pos(250.0, 50.0),
+        }],
+        ..Map::default()
+    };
+    let tanks = [tank("front").at(100.0, 0.0).build()];
+
+    let (_, impact) = fly(&tanks, &map, pos(-500.0, 0.0), 60_000.0, 10).unwrap();
+    assert!((impact.x - 70.0).abs() < 0.01, "{:?}", impact);
+}
+
+proptest! {
+    #[test]
+    fn test_when_bullet_is_fast_then_it_never_tunnels_through_tanks(
+        speed in 100.0f32..100_000.0,
+        start in -2000.0f32..-40.0,
+        offset in -29.0f32..29.0,
+    ) {
+        let tanks = [tank("target").build()];
+        let ticks = ((2100.0 / (speed * DT)).ceil() as usize).max(1);
+
+        let hit = fly(&tanks, &Map::default(), pos(start, offset), speed, ticks);
+        let (_, impact) = hit.expect("bullet passed through the tank");
+        // Struck on the near side, where it entered the hitbox
+        prop_assert!(impact.x <= 0.0);
+        prop_assert!((impact.x.hypot(impact.y) - HIT_RADIUS).abs() < 0.05);
+    }
+}
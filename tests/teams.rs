@@ -0,0 +1,106 @@
+use battlexone_shared::teams::{base_position, new_flags, pick_team, update_flags, FlagBases, FLAG_RETURN_MS};
+use battlexone_shared::{Flag, FlagEventKind, Position, Team};
+
+mod common;
+use common::tank;
+
+fn flag(flags: &[Flag], team: Team) -> &Flag {
+    flags.iter().find(|f| f.team == team).unwrap()
+}
+
+fn kinds(events: &[battlexone_shared::FlagEvent]) -> Vec<FlagEventKind> {
+    events.iter().map(|e| e.kind).collect()
+}
+
+#[test]
+fn test_when_teams_are_uneven_then_new_players_join_the_smaller_one() {
+    assert_eq!(pick_team(0, 0), Team::Red);
+    assert_eq!(pick_team(1, 0), Team::Blue);
+    assert_eq!(pick_team(1, 1), Team::Red);
+    assert_eq!(pick_team(2, 3), Team::Red);
+}
+
+#[test]
+fn test_when_enemy_touches_flag_then_it_is_picked_up_and_carried() {
+    let mut flags = new_flags(&FlagBases::default());
+    let mut raider = tank("raider").team(Team::Blue).position(base_position(Team::Red)).build();
+
+    let events = update_flags(&mut flags, &[raider.clone()], 0);
+    assert_eq!(kinds(&events), vec![FlagEventKind::Pickup]);
+    assert_eq!(flag(&flags, Team::Red).carrier_id.as_deref(), Some("raider"));
+
+    raider.position = Position { x: 0.0, y: 50.0 };
+    assert!(update_flags(&mut flags, &[raider.clone()], 16).is_empty());
+    assert_eq!(flag(&flags, Team::Red).position, raider.position);
+}
+
+#[test]
+fn test_when_own_flag_is_touched_at_home_then_nothing_happens() {
+    let mut flags = new_flags(&FlagBases::default());
+    let defender = tank("defender").team(Team::Red).position(base_position(Team::Red)).build();
+
+    assert!(update_flags(&mut flags, &[defender], 0).is_empty());
+    assert_eq!(flags, new_flags(&FlagBases::default()));
+}
+
+#[test]
+fn test_when_carrier_dies_then_flag_drops_where_it_died() {
+    let mut flags = new_flags(&FlagBases::default());
+    let mut raider = tank("raider").team(Team::Blue).position(base_position(Team::Red)).build();
+    update_flags(&mut flags, &[raider.clone()], 0);
+
+    raider.position = Position { x: -300.0, y: 10.0 };
+    raider.is_dead = true;
+    let events = update_flags(&mut flags, &[raider.clone()], 100);
+
+    assert_eq!(kinds(&events), vec![FlagEventKind::Drop]);
+    let red = flag(&flags, Team::Red);
+    assert_eq!(red.carrier_id, None);
+    assert_eq!(red.position, raider.position);
+    assert_eq!(red.dropped_at, Some(100));
+}
+
+#[test]
+fn test_when_defender_touches_dropped_flag_or_it_is_left_then_it_returns() {
+    let mut flags = new_flags(&FlagBases::default());
+    let raider = tank("raider").team(Team::Blue).position(base_position(Team::Red)).build();
+    update_flags(&mut flags, &[raider], 0);
+    // Carrier leaves the game
+    update_flags(&mut flags, &[], 10);
+
+    let dropped_at = flag(&flags, Team::Red).position.clone();
+    let defender = tank("defender").team(Team::Red).position(dropped_at).build();
+    let events = update_flags(&mut flags.clone(), &[defender], 20);
+    assert_eq!(kinds(&events), vec![FlagEventKind::Return]);
+
+    assert!(update_flags(&mut flags, &[], 10 + FLAG_RETURN_MS - 1).is_empty());
+    let events = update_flags(&mut flags, &[], 10 + FLAG_RETURN_MS);
+    assert_eq!(kinds(&events), vec![FlagEventKind::Return]);
+    assert_eq!(flag(&flags, Team::Red).position, base_position(Team::Red));
+}
+
+#[test]
+fn test_when_carrier_reaches_own_base_then_it_captures_only_if_own_flag_is_home() {
+    let mut flags = new_flags(&FlagBases::default());
+    let mut raider = tank("raider").team(Team::Blue).position(base_position(Team::Red)).build();
+    let mut red_raider = tank("red_raider").team(Team::Red).position(base_position(Team::Blue)).build();
+    update_flags(&mut flags, &[raider.clone(), red_raider.clone()], 0);
+
+    // Both flags are out, so reaching home doesn't score
+    raider.position = base_position(Team::Blue);
+    red_raider.position = Position { x: 0.0, y: 300.0 };
+    assert!(update_flags(&mut flags, &[raider.clone(), red_raider.clone()], 16).is_empty());
+
+    // Blue's flag gets returned, then the capture goes through
+    red_raider.is_dead = true;
+    let defender = tank("defender").team(Team::Blue).position(Position { x: 0.0, y: 300.0 }).build();
+    let events = update_flags(&mut flags, &[raider, red_raider, defender], 32);
+
+    assert_eq!(
+        kinds(&events),
+        vec![FlagEventKind::Drop, FlagEventKind::Return, FlagEventKind::Capture]
+    );
+    assert_eq!(events[2].flag_team, Team::Red);
+    assert_eq!(events[2].tank_id.as_deref(), Some("raider"));
+    assert_eq!(flags, new_flags(&FlagBases::default()));
+}
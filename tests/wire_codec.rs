@@ -0,0 +1,326 @@
+use battlexone_shared::delta::GameStateDelta;
+use battlexone_shared::wire::{self, WireError, POSITION_SCALE};
+use battlexone_shared::*;
+use proptest::prelude::*;
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+mod common;
+
+const POSITION_TOLERANCE: f32 = 0.5 / POSITION_SCALE + 1e-3;
+const ANGLE_TOLERANCE: f32 = TAU / 65536.0;
+
+fn arb_id() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<u128>().prop_map(|n| uuid::Uuid::from_u128(n).to_string()),
+        "[a-z_0-9]{1,20}",
+    ]
+}
+
+fn arb_position() -> impl Strategy<Value = Position> {
+    (-5000.0f32..5000.0, -5000.0f32..5000.0).prop_map(|(x, y)| Position { x, y })
+}
+
+fn arb_tank() -> impl Strategy<Value = Tank> {
+    (
+        arb_id(),
+        arb_position(),
+        -20.0f32..20.0,
+        -20.0f32..20.0,
+        any::<bool>(),
+        -500i32..500,
+        any::<bool>(),
+        proptest::option::of(any::<u64>()),
+        any::<u64>(),
+        proptest::option::of(any::<u64>()),
+        arb_team(),
+    )
+        .prop_map(
+            |(
+                id,
+                position,
+                rotation,
+                turret_rotation,
+                is_player,
+                health,
+                is_dead,
+                respawn_time,
+                last_fire_time,
+                spawn_protected_until,
+                team,
+            )| Tank {
+                id,
+                position,
+                rotation,
+                turret_rotation,
+                is_player,
+                health,
+                is_dead,
+                respawn_time,
+                last_fire_time,
+                spawn_protected_until,
+                team,
+            },
+        )
+}
+
+fn arb_team() -> impl Strategy<Value = Option<Team>> {
+    proptest::option::of(prop_oneof![Just(Team::Red), Just(Team::Blue)])
+}
+
+fn arb_zone() -> impl Strategy<Value = CaptureZone> {
+    (
+        "[A-Za-z ]{1,8}",
+        arb_position(),
+        10.0f32..500.0,
+        arb_team(),
+        arb_team(),
+        0.0f32..=1.0,
+        any::<bool>(),
+    )
+        .prop_map(|(name, position, radius, owner, capturing_team, progress, contested)| CaptureZone {
+            name,
+            position,
+            radius,
+            owner,
+            capturing_team,
+            progress,
+            contested,
+        })
+}
+
+fn arb_game_state() -> impl Strategy<Value = GameState> {
+    (
+        any::<u64>(),
+        any::<u64>(),
+        proptest::collection::vec(arb_tank(), 0..12),
+        proptest::collection::vec((arb_id(), arb_position(), arb_position(), any::<u64>(), 0i32..500), 0..12),
+        proptest::collection::vec(any::<u32>(), 0..12),
+        proptest::collection::vec(arb_zone(), 0..4),
+    )
+        .prop_map(|(tick, server_time, mut tanks, bullets, seqs, zones)| {
+            // Ids are unique within a snapshot
+            let mut seen = std::collections::HashSet::new();
+            tanks.retain(|t| seen.insert(t.id.clone()));
+
+            let bullets = bullets
+                .into_iter()
+                .enumerate()
+                .filter(|(_, (id, ..))| seen.insert(id.clone()))
+                .map(|(i, (id, position, velocity, created_at, damage))| Bullet {
+                    id,
+                    position,
+                    velocity: Velocity { x: velocity.x, y: velocity.y },
+                    // Mostly live tanks, sometimes an owner that already left
+                    owner_id: tanks.get(i).map_or_else(|| format!("gone_{}", i), |t| t.id.clone()),
+                    created_at,
+                    damage,
+                })
+                .collect();
+            let last_processed_input = tanks.iter().zip(seqs).map(|(t, seq)| (t.id.clone(), seq)).collect();
+
+            GameState {
+                tick,
+                server_time,
+                tanks,
+                bullets,
+                last_processed_input,
+                zones,
+            }
+        })
+}
+
+fn angle_close(a: f32, b: f32) -> bool {
+    let diff = (a - b + PI).rem_euclid(TAU) - PI;
+    diff.abs() <= ANGLE_TOLERANCE
+}
+
+fn position_close(a: &Position, b: &Position) -> bool {
+    (a.x - b.x).abs() <= POSITION_TOLERANCE && (a.y - b.y).abs() <= POSITION_TOLERANCE
+}
+
+fn assert_tank_close(decoded: &Tank, original: &Tank) {
+    assert_eq!(decoded.id, original.id);
+    assert!(position_close(&decoded.position, &original.position));
+    assert!(angle_close(decoded.rotation, original.rotation));
+    assert!(angle_close(decoded.turret_rotation, original.turret_rotation));
+    assert_eq!(decoded.is_player, original.is_player);
+    assert_eq!(decoded.health, original.health);
+    assert_eq!(decoded.is_dead, original.is_dead);
+    assert_eq!(decoded.respawn_time, original.respawn_time);
+    assert_eq!(decoded.last_fire_time, original.last_fire_time);
+    assert_eq!(decoded.spawn_protected_until, original.spawn_protected_until);
+    assert_eq!(decoded.team, original.team);
+}
+
+fn assert_state_close(decoded: &GameState, original: &GameState) {
+    assert_eq!(decoded.tick, original.tick);
+    assert_eq!(decoded.server_time, original.server_time);
+    assert_eq!(decoded.tanks.len(), original.tanks.len());
+    for (decoded, original) in decoded.tanks.iter().zip(&original.tanks) {
+        assert_tank_close(decoded, original);
+    }
+    assert_eq!(decoded.bullets.len(), original.bullets.len());
+    for (decoded, original) in decoded.bullets.iter().zip(&original.bullets) {
+        assert_eq!(decoded.id, original.id);
+        assert_eq!(decoded.owner_id, original.owner_id);
+        assert_eq!(decoded.created_at, original.created_at);
+        assert_eq!(decoded.damage, original.damage);
+        assert!(position_close(&decoded.position, &original.position));
+        assert!((decoded.velocity.x - original.velocity.x).abs() <= POSITION_TOLERANCE);
+        assert!((decoded.velocity.y - original.velocity.y).abs() <= POSITION_TOLERANCE);
+    }
+    assert_eq!(decoded.last_processed_input, original.last_processed_input);
+    assert_eq!(decoded.zones.len(), original.zones.len());
+    for (decoded, original) in decoded.zones.iter().zip(&original.zones) {
+        assert_eq!(decoded.name, original.name);
+        assert!(position_close(&decoded.position, &original.position));
+        assert!((decoded.radius - original.radius).abs() <= POSITION_TOLERANCE);
+        assert_eq!(decoded.owner, original.owner);
+        assert_eq!(decoded.capturing_team, original.capturing_team);
+        assert!((decoded.progress - original.progress).abs() <= 0.001);
+        assert_eq!(decoded.contested, original.contested);
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_when_game_state_is_encoded_then_it_round_trips(state in arb_game_state()) {
+        let message = ServerMessage::GameState(state.clone());
+        let bytes = wire::encode_server_message(&message, None).unwrap();
+        let ServerMessage::GameState(decoded) = wire::decode_server_message(&bytes, |_| None).unwrap() else {
+            panic!("expected a game state");
+        };
+        assert_state_close(&decoded, &state);
+    }
+
+    #[test]
+    fn test_when_delta_is_encoded_then_it_round_trips_against_base(base in arb_game_state(), next in arb_game_state(), keep in 0usize..12) {
+        // Some of the base entities survive and move, the rest despawn, and
+        // the entities of an unrelated snapshot spawn
+        let mut current = GameState {
+            tick: base.tick.wrapping_add(1),
+            server_time: next.server_time,
+            tanks: Vec::new(),
+            bullets: Vec::new(),
+            last_processed_input: next.last_processed_input.clone(),
+            zones: next.zones.clone(),
+        };
+        for tank in base.tanks.iter().take(keep) {
+            let mut moved = tank.clone();
+            moved.position.x += 3.0;
+            moved.health -= 10;
+            current.tanks.push(moved);
+        }
+        for bullet in base.bullets.iter().take(keep) {
+            let mut moved = bullet.clone();
+            moved.position.y -= 4.0;
+            current.bullets.push(moved);
+        }
+        current.tanks.extend(next.tanks.into_iter().filter(|t| !base.tanks.iter().any(|b| b.id == t.id)));
+        current.bullets.extend(next.bullets.into_iter().filter(|t| !base.bullets.iter().any(|b| b.id == t.id)));
+
+        let delta = GameStateDelta::between(&base, &current);
+        let message = ServerMessage::GameStateDelta(delta.clone());
+        let bytes = wire::encode_server_message(&message, Some(&base)).unwrap();
+        let ServerMessage::GameStateDelta(decoded) =
+            wire::decode_server_message(&bytes, |tick| (tick == base.tick).then_some(&base)).unwrap()
+        else {
+            panic!("expected a delta");
+        };
+
+        let expected = delta.apply(&base).unwrap();
+        let applied = decoded.apply(&base).unwrap();
+        assert_state_close(&applied, &expected);
+    }
+
+    #[test]
+    fn test_when_input_is_encoded_then_it_round_trips_exactly(seq in any::<u32>(), tick in any::<u64>(), input in any::<u16>()) {
+        let message = ClientMessage::Input { seq, tick, input };
+        let decoded = wire::decode_client_message(&wire::encode_client_message(&message)).unwrap();
+        let ClientMessage::Input { seq: s, tick: t, input: i } = decoded else {
+            panic!("expected an input");
+        };
+        prop_assert_eq!((s, t, i), (seq, tick, input));
+    }
+
+    #[test]
+    fn test_when_decoding_garbage_then_it_never_panics(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+        let _ = wire::decode_client_message(&bytes);
+        let base = GameState::default();
+        let _ = wire::decode_server_message(&bytes, |_| Some(&base));
+    }
+}
+
+#[test]
+fn test_when_delta_base_is_unknown_then_decode_reports_missing_base() {
+    let base = GameState { tick: 7, ..GameState::default() };
+    let current = GameState { tick: 9, ..GameState::default() };
+    let message = ServerMessage::GameStateDelta(GameStateDelta::between(&base, &current));
+    let bytes = wire::encode_server_message(&message, Some(&base)).unwrap();
+
+    assert_eq!(
+        wire::decode_server_message(&bytes, |_| None).unwrap_err(),
+        WireError::MissingBase(7)
+    );
+}
+
+#[test]
+fn test_when_version_differs_then_decode_rejects_frame() {
+    let mut bytes = wire::encode_client_message(&ClientMessage::Ack { tick: 1 });
+    bytes[0] = wire::WIRE_VERSION + 1;
+    assert_eq!(
+        wire::decode_client_message(&bytes).unwrap_err(),
+        WireError::UnsupportedVersion(wire::WIRE_VERSION + 1)
+    );
+}
+
+#[test]
+fn test_when_message_has_no_compact_form_then_it_falls_back_to_json() {
+    let join = ClientMessage::Join {
+        name: "Bevy Player".to_string(),
+        user_id: Some("window_abc".to_string()),
+        token: Some("window_abc.1.00".to_string()),
+        password: None,
+        protocol_version: PROTOCOL_VERSION,
+        client_kind: ClientKind::Native,
+        features: vec![Feature::BinaryEncoding],
+    };
+    let decoded = wire::decode_client_message(&wire::encode_client_message(&join)).unwrap();
+    let ClientMessage::Join { name, user_id, token, features, .. } = decoded else {
+        panic!("expected a join");
+    };
+    assert_eq!(name, "Bevy Player");
+    assert_eq!(user_id.as_deref(), Some("window_abc"));
+    assert_eq!(token.as_deref(), Some("window_abc.1.00"));
+    assert_eq!(features, vec![Feature::BinaryEncoding]);
+}
+
+#[test]
+fn test_when_keyframe_is_binary_then_it_is_smaller_than_json() {
+    let tanks: Vec<Tank> = (0..32)
+        .map(|i| {
+            let tank = common::tank(&uuid::Uuid::new_v4().to_string())
+                .at(i as f32 * 13.7, -(i as f32) * 7.3)
+                .facing(i as f32 * 0.3)
+                .turret(i as f32 * 0.7);
+            let tank = if i < 8 { tank } else { tank.npc() };
+            Tank {
+                last_fire_time: 1_700_000_000_000,
+                ..tank.build()
+            }
+        })
+        .collect();
+    let state = GameState {
+        tick: 1234,
+        server_time: 20_567,
+        last_processed_input: tanks.iter().take(8).map(|t| (t.id.clone(), 99)).collect::<HashMap<_, _>>(),
+        tanks,
+        bullets: Vec::new(),
+        zones: Vec::new(),
+    };
+    let message = ServerMessage::GameState(state);
+    let json = serde_json::to_string(&message).unwrap().len();
+    let binary = wire::encode_server_message(&message, None).unwrap().len();
+    assert!(binary * 3 < json, "binary {} bytes vs JSON {} bytes", binary, json);
+}
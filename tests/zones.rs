@@ -0,0 +1,119 @@
+use battlexone_shared::zones::{held_zone_points, update_zones, zones_for_mode, CAPTURE_SECS, RECOVERY_RATE};
+use battlexone_shared::{CaptureZone, GameMode, Tank, Team, ZoneEvent, ZoneEventKind};
+
+mod common;
+use common::tank;
+
+const DT: f32 = 0.1;
+
+fn hill() -> Vec<CaptureZone> {
+    zones_for_mode(GameMode::KingOfTheHill)
+}
+
+/// Runs `secs` of simulation, collecting every event.
+fn run(zones: &mut [CaptureZone], tanks: &[Tank], secs: f32) -> Vec<ZoneEvent> {
+    let steps = (secs / DT).round() as usize;
+    (0..steps).flat_map(|_| update_zones(zones, tanks, DT)).collect()
+}
+
+#[test]
+fn test_when_mode_changes_then_it_has_its_own_zones() {
+    assert_eq!(zones_for_mode(GameMode::KingOfTheHill).len(), 1);
+    let names: Vec<String> = zones_for_mode(GameMode::Domination).into_iter().map(|z| z.name).collect();
+    assert_eq!(names, ["A", "B", "C"]);
+    assert!(zones_for_mode(GameMode::CaptureTheFlag).is_empty());
+}
+
+#[test]
+fn test_when_one_team_holds_the_zone_long_enough_then_it_captures() {
+    let mut zones = hill();
+    let tanks = [tank("red").team(Team::Red).build()];
+
+    assert!(run(&mut zones, &tanks, CAPTURE_SECS - 1.0).is_empty());
+    assert_eq!(zones[0].capturing_team, Some(Team::Red));
+    assert!(zones[0].progress > 0.7 && zones[0].progress < 1.0);
+
+    let events = run(&mut zones, &tanks, 1.0);
+    assert_eq!(
+        events,
+        vec![ZoneEvent {
+            kind: ZoneEventKind::Captured,
+            zone: "Hill".to_string(),
+            team: Team::Red,
+        }]
+    );
+    assert_eq!(zones[0].owner, Some(Team::Red));
+}
+
+#[test]
+fn test_when_more_tanks_are_inside_then_capture_is_faster() {
+    let mut alone = hill();
+    let mut together = hill();
+    run(&mut alone, &[tank("a").team(Team::Blue).build()], 1.0);
+    run(&mut together, &[tank("a").team(Team::Blue).build(), tank("b").team(Team::Blue).at(10.0, 0.0).build()], 1.0);
+
+    assert!((together[0].progress - 2.0 * alone[0].progress).abs() < 1e-4);
+}
+
+#[test]
+fn test_when_both_teams_are_inside_then_zone_is_contested_and_frozen() {
+    let mut zones = hill();
+    run(&mut zones, &[tank("red").team(Team::Red).build()], 2.0);
+    let progress = zones[0].progress;
+
+    run(&mut zones, &[tank("red").team(Team::Red).build(), tank("blue").team(Team::Blue).at(20.0, 0.0).build()], 2.0);
+    assert!(zones[0].contested);
+    assert_eq!(zones[0].progress, progress);
+}
+
+#[test]
+fn test_when_tanks_are_outside_or_dead_then_they_do_not_count() {
+    let mut zones = hill();
+    let dead = tank("dead").team(Team::Red).dead().build();
+
+    run(&mut zones, &[dead, tank("far").team(Team::Red).at(500.0, 0.0).build()], 2.0);
+    assert_eq!(zones[0].progress, 0.0);
+    assert_eq!(zones[0].capturing_team, None);
+}
+
+#[test]
+fn test_when_enemy_enters_a_held_zone_then_it_must_neutralize_before_capturing() {
+    let mut zones = hill();
+    run(&mut zones, &[tank("red").team(Team::Red).build()], CAPTURE_SECS);
+    assert_eq!(zones[0].owner, Some(Team::Red));
+
+    let blue = [tank("blue").team(Team::Blue).build()];
+    let events = run(&mut zones, &blue, CAPTURE_SECS);
+    assert_eq!(events.iter().map(|e| e.kind).collect::<Vec<_>>(), [ZoneEventKind::Neutralized]);
+    assert_eq!(zones[0].owner, None);
+
+    let events = run(&mut zones, &blue, CAPTURE_SECS);
+    assert_eq!(events.iter().map(|e| e.kind).collect::<Vec<_>>(), [ZoneEventKind::Captured]);
+    assert_eq!(zones[0].owner, Some(Team::Blue));
+}
+
+#[test]
+fn test_when_zone_is_empty_then_it_drifts_back_to_owner_or_neutral() {
+    let mut zones = zones_for_mode(GameMode::Domination);
+    run(&mut zones, &[tank("red").team(Team::Red).build()], CAPTURE_SECS);
+    run(&mut zones, &[tank("blue").team(Team::Blue).build(), tank("blue2").team(Team::Blue).at(-450.0, 0.0).build()], 1.0);
+    assert!(zones[1].progress < 1.0);
+    assert!(zones[0].progress > 0.0);
+
+    run(&mut zones, &[], CAPTURE_SECS / RECOVERY_RATE);
+    assert_eq!(zones[1].owner, Some(Team::Red));
+    assert_eq!(zones[1].progress, 1.0);
+    assert_eq!(zones[0].progress, 0.0);
+    assert_eq!(zones[0].capturing_team, None);
+}
+
+#[test]
+fn test_when_zones_are_held_then_each_scores_for_its_owner() {
+    let mut zones = zones_for_mode(GameMode::Domination);
+    zones[0].owner = Some(Team::Red);
+    zones[1].owner = Some(Team::Blue);
+    zones[2].owner = Some(Team::Blue);
+
+    assert_eq!(held_zone_points(&zones), vec![(Team::Red, 1), (Team::Blue, 2)]);
+    assert!(held_zone_points(&hill()).is_empty());
+}